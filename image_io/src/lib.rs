@@ -0,0 +1,188 @@
+// image output shared by the renderers: PPM/PNG writers, the gamma encoding
+// from linear colors to 8-bit, and the row padding of GPU readbacks.
+// Pixels always run row by row from the top-left corner.
+
+use std::fs::File;
+use std::io::{BufWriter, Result, Write};
+use std::path::Path;
+
+use image::{ExtendedColorType, ImageFormat, ImageResult};
+pub use math::Color;
+
+// the gamma the raytracer has always written with
+pub const DEFAULT_GAMMA: f64 = 2.0;
+
+// texture-to-buffer copies need each row aligned to this many bytes (wgpu's
+// COPY_BYTES_PER_ROW_ALIGNMENT)
+pub const ROW_ALIGNMENT: u32 = 256;
+
+// linear 0..1 to display space
+pub fn gamma_encode(linear: f64, gamma: f64) -> f64 {
+    linear.max(0.0).powf(1.0 / gamma)
+}
+
+// display space back to linear
+pub fn gamma_decode(encoded: f64, gamma: f64) -> f64 {
+    encoded.max(0.0).powf(gamma)
+}
+
+// linear color to 8-bit with the default gamma; anything past 1 clips
+pub fn to_rgb8(color: Color) -> [u8; 3] {
+    encode_rgb8(color, 1.0, DEFAULT_GAMMA)
+}
+
+// linear color scaled by `exposure`, then clipped and gamma-encoded to 8-bit
+pub fn encode_rgb8(color: Color, exposure: f64, gamma: f64) -> [u8; 3] {
+    let channel = |v: f64| (255.999 * gamma_encode((v * exposure).clamp(0.0, 0.999), gamma)) as u8;
+    [channel(color.x), channel(color.y), channel(color.z)]
+}
+
+// plain-text P3 PPM, encoded with `exposure` and `gamma`
+pub fn write_ppm(
+    path: impl AsRef<Path>,
+    width: u32,
+    height: u32,
+    pixels: &[Color],
+    exposure: f64,
+    gamma: f64,
+) -> Result<()> {
+    assert_eq!(pixels.len(), width as usize * height as usize);
+    let mut out = BufWriter::new(File::create(path)?);
+    writeln!(out, "P3")?;
+    writeln!(out, "{width} {height}")?;
+    writeln!(out, "255")?;
+    for &pixel in pixels {
+        let [r, g, b] = encode_rgb8(pixel, exposure, gamma);
+        writeln!(out, "{r} {g} {b}")?;
+    }
+    out.flush()
+}
+
+pub fn write_png(
+    path: impl AsRef<Path>,
+    width: u32,
+    height: u32,
+    pixels: &[Color],
+) -> ImageResult<()> {
+    let data: Vec<u8> = pixels.iter().flat_map(|&pixel| to_rgb8(pixel)).collect();
+    write_png_rgb8(path, width, height, &data)
+}
+
+// already display-encoded 8-bit RGB
+pub fn write_png_rgb8(
+    path: impl AsRef<Path>,
+    width: u32,
+    height: u32,
+    data: &[u8],
+) -> ImageResult<()> {
+    image::save_buffer_with_format(
+        path,
+        data,
+        width,
+        height,
+        ExtendedColorType::Rgb8,
+        ImageFormat::Png,
+    )
+}
+
+// already display-encoded 8-bit RGBA, e.g. read back from an sRGB texture
+pub fn write_png_rgba8(
+    path: impl AsRef<Path>,
+    width: u32,
+    height: u32,
+    data: &[u8],
+) -> ImageResult<()> {
+    image::save_buffer_with_format(
+        path,
+        data,
+        width,
+        height,
+        ExtendedColorType::Rgba8,
+        ImageFormat::Png,
+    )
+}
+
+// stride of an RGBA8 row in a texture-to-buffer copy
+pub fn padded_bytes_per_row(width: u32) -> u32 {
+    (width * 4).next_multiple_of(ROW_ALIGNMENT)
+}
+
+// tightly packed RGBA8 rows out of a padded readback
+pub fn unpad_rows(data: &[u8], width: u32, padded_bytes_per_row: u32) -> Vec<u8> {
+    let row_bytes = (width * 4) as usize;
+    let mut pixels = Vec::with_capacity(data.len() / padded_bytes_per_row as usize * row_bytes);
+    for row in data.chunks(padded_bytes_per_row as usize) {
+        pixels.extend_from_slice(&row[..row_bytes]);
+    }
+    pixels
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rows_pad_to_the_alignment() {
+        assert_eq!(padded_bytes_per_row(1), 256);
+        assert_eq!(padded_bytes_per_row(64), 256);
+        assert_eq!(padded_bytes_per_row(65), 512);
+    }
+
+    #[test]
+    fn unpadding_drops_only_the_padding() {
+        // two rows of three pixels, each padded to 256 bytes with 0xff
+        let (width, stride) = (3, padded_bytes_per_row(3));
+        let mut data = vec![0xff; stride as usize * 2];
+        for row in 0..2 {
+            for i in 0..12 {
+                data[row * stride as usize + i] = (row * 12 + i) as u8;
+            }
+        }
+        assert_eq!(
+            unpad_rows(&data, width, stride),
+            (0..24).collect::<Vec<u8>>()
+        );
+    }
+
+    #[test]
+    fn gamma_round_trips() {
+        for gamma in [1.0, DEFAULT_GAMMA, 2.2] {
+            for linear in [0.0, 0.01, 0.25, 0.5, 1.0] {
+                let encoded = gamma_encode(linear, gamma);
+                assert!((gamma_decode(encoded, gamma) - linear).abs() < 1e-12);
+            }
+        }
+        assert_eq!(gamma_encode(0.25, 2.0), 0.5);
+        assert_eq!(gamma_encode(-1.0, 2.0), 0.0);
+    }
+
+    #[test]
+    fn encoding_exposes_then_clips_then_gamma_encodes() {
+        assert_eq!(to_rgb8(Color::new(0.25, 0.0, 1.0)), [127, 0, 255]);
+        assert_eq!(
+            encode_rgb8(Color::new(-1.0, 2.0, 100.0), 1.0, 2.0),
+            [0, 255, 255]
+        );
+        // exposure scales the linear color, so a quarter of 0.25 encodes to a
+        // quarter, not a quarter of its encoding
+        assert_eq!(
+            encode_rgb8(Color::new(0.25, 0.25, 0.25), 0.25, 2.0),
+            [63; 3]
+        );
+        assert_eq!(
+            encode_rgb8(Color::new(0.25, 0.25, 0.25), 4.0, 2.0),
+            [255; 3]
+        );
+    }
+
+    #[test]
+    fn png_holds_the_encoded_pixels() {
+        let path = std::env::temp_dir().join(format!("image_io_test_{}.png", std::process::id()));
+        let pixels = [Color::new(0.25, 0.0, 1.0), Color::new(1.0, 0.25, 0.0)];
+        write_png(&path, 2, 1, &pixels).unwrap();
+        let image = image::open(&path).unwrap().into_rgb8();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(image.dimensions(), (2, 1));
+        assert_eq!(image.into_raw(), [127, 0, 255, 255, 127, 0]);
+    }
+}
@@ -0,0 +1,12 @@
+// vector and matrix types shared by the renderers; everything is `#[repr(C)]`
+// and `Pod` so it can be copied straight into GPU buffers
+
+mod mat;
+mod vec;
+
+pub use mat::{Mat3, Mat4};
+pub use vec::{DVec3, Vec3};
+
+// the raytracer works in double precision
+pub type Point3 = DVec3;
+pub type Color = DVec3;
@@ -0,0 +1,123 @@
+use std::ops::Mul;
+
+use crate::Vec3;
+
+// column-major, like WGSL; note a `mat3x3<f32>` uniform pads each column to 16
+// bytes, so upload a Mat4 instead
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct Mat3 {
+    pub cols: [Vec3; 3],
+}
+
+impl Mat3 {
+    pub const IDENTITY: Self = Self::from_cols(
+        Vec3::new(1.0, 0.0, 0.0),
+        Vec3::new(0.0, 1.0, 0.0),
+        Vec3::new(0.0, 0.0, 1.0),
+    );
+
+    pub const fn from_cols(x: Vec3, y: Vec3, z: Vec3) -> Self {
+        Self { cols: [x, y, z] }
+    }
+
+    // camera basis looking along `forward`: columns are right, up and forward,
+    // so it maps (x, y, focal length) to a world-space ray direction
+    pub fn look_to(forward: Vec3, world_up: Vec3) -> Self {
+        let forward = forward.unit();
+        let right = forward.cross(&world_up).unit();
+        let up = right.cross(&forward);
+        Self::from_cols(right, up, forward)
+    }
+
+    pub fn transpose(&self) -> Self {
+        let [x, y, z] = self.cols;
+        Self::from_cols(
+            Vec3::new(x.x, y.x, z.x),
+            Vec3::new(x.y, y.y, z.y),
+            Vec3::new(x.z, y.z, z.z),
+        )
+    }
+}
+
+impl Mul<Vec3> for Mat3 {
+    type Output = Vec3;
+    fn mul(self, v: Vec3) -> Vec3 {
+        let [x, y, z] = self.cols;
+        x * v.x + y * v.y + z * v.z
+    }
+}
+
+impl Mul for Mat3 {
+    type Output = Self;
+    fn mul(self, rhs: Self) -> Self {
+        let [x, y, z] = rhs.cols;
+        Self::from_cols(self * x, self * y, self * z)
+    }
+}
+
+// column-major 4x4 affine/projective transform, laid out like `mat4x4<f32>`
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct Mat4 {
+    pub cols: [[f32; 4]; 4],
+}
+
+impl Mat4 {
+    pub const IDENTITY: Self = Self {
+        cols: [
+            [1.0, 0.0, 0.0, 0.0],
+            [0.0, 1.0, 0.0, 0.0],
+            [0.0, 0.0, 1.0, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ],
+    };
+
+    // rotates by `rotation`, then moves by `translation`
+    pub fn from_rotation_translation(rotation: Mat3, translation: Vec3) -> Self {
+        let [x, y, z] = rotation.cols;
+        Self {
+            cols: [
+                [x.x, x.y, x.z, 0.0],
+                [y.x, y.y, y.z, 0.0],
+                [z.x, z.y, z.z, 0.0],
+                [translation.x, translation.y, translation.z, 1.0],
+            ],
+        }
+    }
+
+    // camera-to-world transform for an eye at `eye` looking at `target`; the
+    // inverse of the usual view matrix
+    pub fn look_at(eye: Vec3, target: Vec3, world_up: Vec3) -> Self {
+        Self::from_rotation_translation(Mat3::look_to(target - eye, world_up), eye)
+    }
+
+    pub fn transform_point(&self, p: Vec3) -> Vec3 {
+        let [x, y, z, w] = self.mul_vec4([p.x, p.y, p.z, 1.0]);
+        Vec3::new(x, y, z) / w
+    }
+
+    pub fn transform_vector(&self, v: Vec3) -> Vec3 {
+        let [x, y, z, _] = self.mul_vec4([v.x, v.y, v.z, 0.0]);
+        Vec3::new(x, y, z)
+    }
+
+    fn mul_vec4(&self, v: [f32; 4]) -> [f32; 4] {
+        let mut out = [0.0; 4];
+        for (col, &s) in self.cols.iter().zip(&v) {
+            for (o, &c) in out.iter_mut().zip(col) {
+                *o += c * s;
+            }
+        }
+        out
+    }
+}
+
+impl Mul for Mat4 {
+    type Output = Self;
+    fn mul(self, rhs: Self) -> Self {
+        Self {
+            cols: rhs.cols.map(|col| self.mul_vec4(col)),
+        }
+    }
+}
@@ -0,0 +1,109 @@
+use std::ops::{Add, AddAssign, Div, Mul, Neg, Sub};
+
+// the f32 and f64 vectors only differ in their component type
+macro_rules! vec3 {
+    ($name:ident, $t:ty) => {
+        #[repr(C)]
+        #[derive(Clone, Copy, Debug, Default, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+        pub struct $name {
+            pub x: $t,
+            pub y: $t,
+            pub z: $t,
+        }
+
+        impl $name {
+            pub const ZERO: Self = Self::new(0.0, 0.0, 0.0);
+
+            pub const fn new(x: $t, y: $t, z: $t) -> Self {
+                Self { x, y, z }
+            }
+
+            pub fn length(&self) -> $t {
+                self.length_squared().sqrt()
+            }
+
+            pub fn length_squared(&self) -> $t {
+                self.x * self.x + self.y * self.y + self.z * self.z
+            }
+
+            pub fn dot(&self, other: &Self) -> $t {
+                self.x * other.x + self.y * other.y + self.z * other.z
+            }
+
+            pub fn cross(&self, other: &Self) -> Self {
+                Self::new(
+                    self.y * other.z - self.z * other.y,
+                    self.z * other.x - self.x * other.z,
+                    self.x * other.y - self.y * other.x,
+                )
+            }
+
+            pub fn unit(self) -> Self {
+                let len = self.length();
+                Self::new(self.x / len, self.y / len, self.z / len)
+            }
+
+            pub fn to_array(self) -> [$t; 3] {
+                [self.x, self.y, self.z]
+            }
+        }
+
+        impl From<[$t; 3]> for $name {
+            fn from([x, y, z]: [$t; 3]) -> Self {
+                Self::new(x, y, z)
+            }
+        }
+
+        impl Add for $name {
+            type Output = Self;
+            fn add(self, rhs: Self) -> Self::Output {
+                Self::new(self.x + rhs.x, self.y + rhs.y, self.z + rhs.z)
+            }
+        }
+
+        impl AddAssign for $name {
+            fn add_assign(&mut self, rhs: Self) {
+                *self = *self + rhs;
+            }
+        }
+
+        impl Sub for $name {
+            type Output = Self;
+            fn sub(self, rhs: Self) -> Self::Output {
+                Self::new(self.x - rhs.x, self.y - rhs.y, self.z - rhs.z)
+            }
+        }
+
+        impl Neg for $name {
+            type Output = Self;
+            fn neg(self) -> Self::Output {
+                Self::new(-self.x, -self.y, -self.z)
+            }
+        }
+
+        impl Mul<$t> for $name {
+            type Output = Self;
+            fn mul(self, t: $t) -> Self::Output {
+                Self::new(self.x * t, self.y * t, self.z * t)
+            }
+        }
+
+        // component-wise, e.g. for tinting a color
+        impl Mul for $name {
+            type Output = Self;
+            fn mul(self, rhs: Self) -> Self::Output {
+                Self::new(self.x * rhs.x, self.y * rhs.y, self.z * rhs.z)
+            }
+        }
+
+        impl Div<$t> for $name {
+            type Output = Self;
+            fn div(self, t: $t) -> Self::Output {
+                Self::new(self.x / t, self.y / t, self.z / t)
+            }
+        }
+    };
+}
+
+vec3!(Vec3, f32);
+vec3!(DVec3, f64);
@@ -0,0 +1,83 @@
+use clap::ValueEnum;
+use math::DVec3 as Vec3;
+use rand::rngs::SmallRng;
+use rand::SeedableRng;
+
+use crate::camera::Camera;
+use crate::hittable::{hit_world, Hittable};
+
+// auxiliary passes for compositing, taken from the first hit of one ray through
+// each pixel's centre
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+pub enum Aov {
+    // inverse distance to the first hit, white at the nearest hit and half as
+    // bright twice as far away, which keeps contrast up close when the ground
+    // runs far off; misses are black
+    Depth,
+    // the shading normal encoded as n * 0.5 + 0.5; misses are black
+    Normal,
+}
+
+impl Aov {
+    pub fn name(self) -> &'static str {
+        match self {
+            Aov::Depth => "depth",
+            Aov::Normal => "normal",
+        }
+    }
+}
+
+// what a pixel's first hit looked like; also guides the denoiser
+#[derive(Clone, Copy)]
+pub struct Guide {
+    // distance from the camera
+    pub depth: f64,
+    pub normal: Vec3,
+}
+
+// rows from the top; None where the ray missed everything
+pub fn guides(
+    camera: &Camera,
+    world: &[Box<dyn Hittable>],
+    width: usize,
+    height: usize,
+) -> Vec<Option<Guide>> {
+    // only the camera draws from it, for the shutter time
+    let mut rng = SmallRng::seed_from_u64(0);
+    let mut guides = Vec::with_capacity(width * height);
+    for y in 0..height {
+        for x in 0..width {
+            let u = (x as f64 + 0.5) / (width - 1) as f64;
+            let v = ((height - 1 - y) as f64 + 0.5) / (height - 1) as f64;
+            let r = camera.get_ray(u, v, &mut rng);
+            guides.push(hit_world(&r, world, 0.001, f64::INFINITY).map(|hit| Guide {
+                depth: hit.t * r.direction.length(),
+                normal: hit.material.shading_normal(&hit),
+            }));
+        }
+    }
+    guides
+}
+
+// 8-bit RGB rows from the top, stored linearly rather than gamma-encoded
+pub fn render(aov: Aov, guides: &[Option<Guide>]) -> Vec<u8> {
+    let min_depth = guides
+        .iter()
+        .flatten()
+        .map(|guide| guide.depth)
+        .fold(f64::INFINITY, f64::min);
+    guides
+        .iter()
+        .flat_map(|guide| {
+            let value = match (aov, guide) {
+                (_, None) => [0.0; 3],
+                (Aov::Depth, Some(guide)) => [min_depth / guide.depth; 3],
+                (Aov::Normal, Some(guide)) => {
+                    let n = guide.normal * 0.5;
+                    [n.x + 0.5, n.y + 0.5, n.z + 0.5]
+                }
+            };
+            value.map(|c| (c.clamp(0.0, 1.0) * 255.0).round() as u8)
+        })
+        .collect()
+}
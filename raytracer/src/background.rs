@@ -0,0 +1,213 @@
+use std::f64::consts::PI;
+use std::path::Path;
+use std::str::FromStr;
+
+use image::DynamicImage;
+use math::{Color, DVec3 as Vec3};
+use rand::Rng;
+
+use crate::pdf::Pdf;
+
+// what a ray that hits nothing sees
+pub enum Background {
+    Sky(Sky),
+    Env(EnvMap),
+}
+
+impl Background {
+    pub fn color(&self, direction: Vec3) -> Color {
+        match self {
+            Background::Sky(sky) => sky.color(direction),
+            Background::Env(env) => env.sample(direction),
+        }
+    }
+
+    // an environment image to aim shadow rays into; a sky is too even to gain
+    // anything from it
+    pub fn env_map(&self) -> Option<&EnvMap> {
+        match self {
+            Background::Sky(_) => None,
+            Background::Env(env) => Some(env),
+        }
+    }
+}
+
+// a sky without an image. Parsed from `black`, a solid `r,g,b`, or a vertical
+// gradient `r,g,b:r,g,b` from straight down to straight up.
+#[derive(Clone, Copy)]
+pub enum Sky {
+    Solid(Color),
+    Gradient { bottom: Color, top: Color },
+}
+
+impl Sky {
+    fn color(&self, direction: Vec3) -> Color {
+        match *self {
+            Sky::Solid(color) => color,
+            Sky::Gradient { bottom, top } => {
+                let unit_dir = direction.unit();
+                let t = 0.5 * (unit_dir.y + 1.0);
+                bottom * (1.0 - t) + top * t
+            }
+        }
+    }
+}
+
+fn parse_color(s: &str) -> Result<Color, String> {
+    let channels: Vec<f64> = s
+        .split(',')
+        .map(|c| c.trim().parse::<f64>())
+        .collect::<Result<_, _>>()
+        .map_err(|err| format!("bad color `{s}`: {err}"))?;
+    match channels[..] {
+        [r, g, b] => Ok(Color::new(r, g, b)),
+        _ => Err(format!("bad color `{s}`: expected r,g,b")),
+    }
+}
+
+impl FromStr for Sky {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s == "black" {
+            return Ok(Sky::Solid(Color::ZERO));
+        }
+        match s.split_once(':') {
+            Some((bottom, top)) => Ok(Sky::Gradient {
+                bottom: parse_color(bottom)?,
+                top: parse_color(top)?,
+            }),
+            None => Ok(Sky::Solid(parse_color(s)?)),
+        }
+    }
+}
+
+// equirectangular environment image: longitude across, latitude down
+pub struct EnvMap {
+    width: u32,
+    height: u32,
+    pixels: Vec<Color>,
+    // for drawing directions by brightness, so shadow rays find the sun and
+    // windows of an HDR rather than wandering: each pixel's share of the
+    // image's luminance, and the cumulative shares down the rows and across
+    // each row
+    density: Vec<f64>,
+    row_cdf: Vec<f64>,
+    column_cdf: Vec<f64>,
+}
+
+impl EnvMap {
+    // HDR and EXR are linear already; integer formats are display-encoded and
+    // get decoded with the gamma the output is written with
+    pub fn load(path: &Path, gamma: f64) -> image::ImageResult<Self> {
+        let image = image::open(path)?;
+        let linear = matches!(
+            image,
+            DynamicImage::ImageRgb32F(_) | DynamicImage::ImageRgba32F(_)
+        );
+        let image = image.into_rgb32f();
+        let (width, height) = image.dimensions();
+        let channel = |v: f32| {
+            if linear {
+                v as f64
+            } else {
+                image_io::gamma_decode(v as f64, gamma)
+            }
+        };
+        let pixels: Vec<Color> = image
+            .pixels()
+            .map(|p| Color::new(channel(p[0]), channel(p[1]), channel(p[2])))
+            .collect();
+        Ok(Self::with_distribution(width, height, pixels))
+    }
+
+    // the rows near the poles cover less of the sphere, so each pixel is
+    // weighted by its luminance times the sine of its latitude's angle from +y;
+    // an image that's black all over is sampled evenly instead
+    fn with_distribution(width: u32, height: u32, pixels: Vec<Color>) -> Self {
+        let (w, h) = (width as usize, height as usize);
+        let row_sin = |y: usize| ((y as f64 + 0.5) / h as f64 * PI).sin();
+        let mut weights: Vec<f64> = pixels
+            .iter()
+            .enumerate()
+            .map(|(i, c)| (0.2126 * c.x + 0.7152 * c.y + 0.0722 * c.z).max(0.0) * row_sin(i / w))
+            .collect();
+        if !weights.iter().any(|&weight| weight > 0.0) {
+            for (i, weight) in weights.iter_mut().enumerate() {
+                *weight = row_sin(i / w);
+            }
+        }
+        let total: f64 = weights.iter().sum();
+
+        let mut column_cdf = Vec::with_capacity(w * h);
+        let mut row_cdf = Vec::with_capacity(h);
+        let mut rows_so_far = 0.0;
+        for row in weights.chunks(w) {
+            let row_total: f64 = row.iter().sum();
+            let mut so_far = 0.0;
+            for &weight in row {
+                so_far += weight;
+                column_cdf.push(if row_total > 0.0 {
+                    so_far / row_total
+                } else {
+                    0.0
+                });
+            }
+            rows_so_far += row_total;
+            row_cdf.push(rows_so_far / total);
+        }
+        // density over the image's unit square
+        let density = weights
+            .iter()
+            .map(|weight| weight / total * (w * h) as f64)
+            .collect();
+        Self {
+            width,
+            height,
+            pixels,
+            density,
+            row_cdf,
+            column_cdf,
+        }
+    }
+
+    // +y is up and the image centre looks down -z
+    pub fn sample(&self, direction: Vec3) -> Color {
+        self.pixels[self.pixel(direction)]
+    }
+
+    fn pixel(&self, direction: Vec3) -> usize {
+        let d = direction.unit();
+        let u = 0.5 + d.x.atan2(-d.z) / (2.0 * PI);
+        let v = d.y.clamp(-1.0, 1.0).acos() / PI;
+        let x = ((u * self.width as f64) as u32).min(self.width - 1);
+        let y = ((v * self.height as f64) as u32).min(self.height - 1);
+        (y * self.width + x) as usize
+    }
+}
+
+// directions by the image's brightness; the density over the unit square is
+// spread over 2 pi^2 sin(theta) of solid angle per unit of it
+impl Pdf for EnvMap {
+    fn value(&self, direction: Vec3) -> f64 {
+        let sin_theta = (1.0 - direction.unit().y.powi(2)).max(0.0).sqrt();
+        if sin_theta <= 0.0 {
+            return 0.0;
+        }
+        self.density[self.pixel(direction)] / (2.0 * PI * PI * sin_theta)
+    }
+
+    // a row by its share, a pixel within it by its share of the row, and a
+    // uniform point within the pixel
+    fn generate(&self, rng: &mut impl Rng) -> Vec3 {
+        let (w, h) = (self.width as usize, self.height as usize);
+        let pick = |cdf: &[f64], xi: f64| cdf.partition_point(|&c| c <= xi).min(cdf.len() - 1);
+        let y = pick(&self.row_cdf, rng.gen());
+        let x = pick(&self.column_cdf[y * w..(y + 1) * w], rng.gen());
+        let u = (x as f64 + rng.gen::<f64>()) / w as f64;
+        let v = (y as f64 + rng.gen::<f64>()) / h as f64;
+        let (sin_phi, cos_phi) = ((u - 0.5) * 2.0 * PI).sin_cos();
+        let (sin_theta, cos_theta) = (v * PI).sin_cos();
+        Vec3::new(sin_theta * sin_phi, cos_theta, -sin_theta * cos_phi)
+    }
+}
@@ -0,0 +1,85 @@
+// `--bench`: a few fixed scenes rendered at a fixed size, sample count and
+// seed, each timed over several runs, so changes to the renderer can be
+// measured against each other
+use std::time::{Duration, Instant};
+
+use crate::background::Background;
+use crate::camera::Projection;
+use crate::mesh::Mesh;
+use crate::presets::Preset;
+use crate::sampling::{PixelFilter, Sampler};
+use crate::tiles::TileOrder;
+use crate::{render, scene_camera, Scene};
+
+const WIDTH: usize = 200;
+const SAMPLES: u32 = 8;
+const SEED: u64 = 0;
+const RUNS: usize = 3;
+
+struct Case {
+    name: &'static str,
+    preset: Preset,
+    // rings and segments of a sphere mesh to use as the scene's model
+    mesh: Option<(usize, usize)>,
+}
+
+const CASES: &[Case] = &[
+    // many spheres, for the shading and intersection of analytic shapes
+    Case {
+        name: "spheres",
+        preset: Preset::Grid,
+        mesh: None,
+    },
+    // one model of about 1000 triangles, for the cost of the triangle tests
+    Case {
+        name: "triangles",
+        preset: Preset::Mesh,
+        mesh: Some((16, 32)),
+    },
+];
+
+pub fn run() {
+    println!("{WIDTH} pixels wide, {SAMPLES} samples, best and mean of {RUNS} runs");
+    for case in CASES {
+        let mesh = case
+            .mesh
+            .map(|(rings, segments)| Mesh::sphere(rings, segments));
+        let contents = case.preset.build(None, SEED, mesh);
+        let camera = scene_camera(
+            contents.aspect_ratio,
+            contents.vfov,
+            Projection::Perspective,
+            0.0,
+        );
+        let height = (WIDTH as f64 / contents.aspect_ratio) as usize;
+        let scene = Scene {
+            world: contents.world,
+            lights: contents.lights,
+            area_lights: contents.area_lights,
+            background: Background::Sky(contents.sky),
+            sampler: Sampler::Cosine,
+            filter: PixelFilter::Box,
+            rr_start_depth: 3,
+            spectral: false,
+            seed: SEED,
+            transparent: false,
+            tile_order: TileOrder::Spiral,
+        };
+        let times: Vec<Duration> = (0..RUNS)
+            .map(|_| {
+                let start = Instant::now();
+                render(&camera, &scene, WIDTH, height, SAMPLES, None);
+                start.elapsed()
+            })
+            .collect();
+        let best = times.iter().min().unwrap();
+        let mean = times.iter().sum::<Duration>() / RUNS as u32;
+        println!(
+            "{:<10} {:>5} objects  best {:>8.3} s  mean {:>8.3} s",
+            case.name,
+            scene.world.len(),
+            best.as_secs_f64(),
+            mean.as_secs_f64()
+        );
+    }
+}
@@ -0,0 +1,61 @@
+use math::Color;
+
+// adds a glow around everything brighter than `threshold`: the excess over it
+// is blurred with a Gaussian reaching `radius` pixels, then added back
+pub fn bloom(pixels: &mut [Color], width: usize, height: usize, threshold: f64, radius: usize) {
+    let bright: Vec<Color> = pixels
+        .iter()
+        .map(|c| {
+            Color::new(
+                (c.x - threshold).max(0.0),
+                (c.y - threshold).max(0.0),
+                (c.z - threshold).max(0.0),
+            )
+        })
+        .collect();
+    let kernel = gaussian_kernel(radius);
+    // separable: blur the rows, then the columns of the result
+    let rows = blur(&bright, &kernel, width, height, (1, 0));
+    let glow = blur(&rows, &kernel, width, height, (0, 1));
+    for (pixel, glow) in pixels.iter_mut().zip(glow) {
+        *pixel += glow;
+    }
+}
+
+// weights for offsets 0..=radius, summing to 1 over both sides
+fn gaussian_kernel(radius: usize) -> Vec<f64> {
+    // the tails beyond three sigma are negligible
+    let sigma = (radius as f64 / 3.0).max(0.5);
+    let weights: Vec<f64> = (0..=radius)
+        .map(|i| (-((i * i) as f64) / (2.0 * sigma * sigma)).exp())
+        .collect();
+    let total = weights[0] + 2.0 * weights[1..].iter().sum::<f64>();
+    weights.into_iter().map(|w| w / total).collect()
+}
+
+// one Gaussian pass along `(dx, dy)`, clamping at the image edges
+fn blur(
+    src: &[Color],
+    kernel: &[f64],
+    width: usize,
+    height: usize,
+    (dx, dy): (usize, usize),
+) -> Vec<Color> {
+    let at = |x: isize, y: isize| {
+        let x = x.clamp(0, width as isize - 1) as usize;
+        let y = y.clamp(0, height as isize - 1) as usize;
+        src[y * width + x]
+    };
+    let mut out = Vec::with_capacity(src.len());
+    for y in 0..height as isize {
+        for x in 0..width as isize {
+            let mut sum = at(x, y) * kernel[0];
+            for (i, &w) in kernel.iter().enumerate().skip(1) {
+                let (ox, oy) = ((i * dx) as isize, (i * dy) as isize);
+                sum += (at(x - ox, y - oy) + at(x + ox, y + oy)) * w;
+            }
+            out.push(sum);
+        }
+    }
+    out
+}
@@ -0,0 +1,84 @@
+use clap::ValueEnum;
+use math::{DVec3 as Vec3, Point3};
+use rand::Rng;
+
+use crate::ray::Ray;
+
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+pub enum Projection {
+    // rays fan out from the eye, so farther things look smaller
+    Perspective,
+    // parallel rays from across the image plane keep sizes independent of
+    // depth, like an engineering drawing
+    Ortho,
+}
+
+pub struct Camera {
+    pub origin: Point3,
+    // from the origin to the centre of the image plane; the ortho view direction
+    pub forward: Vec3,
+    pub horizontal: Vec3,
+    pub vertical: Vec3,
+    pub projection: Projection,
+    // each ray is sent at a random moment while the shutter is open
+    pub shutter: (f64, f64),
+}
+
+impl Camera {
+    // the same view moved to `eye` and turned about the vertical to face
+    // `target`, keeping the image plane's size and distance. Straight up or
+    // down there's no turning about the vertical that faces it, so it tilts
+    // instead, keeping the image's horizontal
+    pub fn looking_at(&self, eye: Point3, target: Point3) -> Camera {
+        let up = self.vertical.unit();
+        let forward = (target - eye).unit();
+        let right = forward.cross(&up);
+        let right = if right.length_squared() < 1e-12 {
+            self.horizontal.unit()
+        } else {
+            right.unit()
+        };
+        Camera {
+            origin: eye,
+            forward: forward * self.forward.length(),
+            horizontal: right * self.horizontal.length(),
+            vertical: right.cross(&forward) * self.vertical.length(),
+            ..*self
+        }
+    }
+
+    // (u, v) in 0..1 from the bottom-left of the image
+    pub fn get_ray(&self, u: f64, v: f64, rng: &mut impl Rng) -> Ray {
+        let offset = self.horizontal * (u - 0.5) + self.vertical * (v - 0.5);
+        let time = rng.gen_range(self.shutter.0..self.shutter.1);
+        match self.projection {
+            Projection::Perspective => Ray::new(self.origin, self.forward + offset, time),
+            Projection::Ortho => Ray::new(self.origin + offset, self.forward, time),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn looking_straight_up_keeps_the_horizontal() {
+        let camera = Camera {
+            origin: Point3::ZERO,
+            forward: Vec3::new(0.0, 0.0, -1.0),
+            horizontal: Vec3::new(2.0, 0.0, 0.0),
+            vertical: Vec3::new(0.0, 1.0, 0.0),
+            projection: Projection::Perspective,
+            shutter: (0.0, 1.0),
+        };
+        let up = camera.looking_at(Point3::ZERO, Point3::new(0.0, 3.0, 0.0));
+        assert!((up.forward - Vec3::new(0.0, 1.0, 0.0)).length() < 1e-9);
+        assert!((up.horizontal - camera.horizontal).length() < 1e-9);
+        assert!((up.vertical - Vec3::new(0.0, 0.0, 1.0)).length() < 1e-9);
+        // anywhere else it only turns about the vertical
+        let side = camera.looking_at(Point3::ZERO, Point3::new(1.0, 0.0, 0.0));
+        assert!((side.horizontal - Vec3::new(0.0, 0.0, 2.0)).length() < 1e-9);
+        assert!((side.vertical - camera.vertical).length() < 1e-9);
+    }
+}
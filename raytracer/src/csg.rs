@@ -0,0 +1,76 @@
+use crate::hittable::{first_crossing, HitRecord, Hittable, Solid, Span};
+use crate::ray::Ray;
+
+#[derive(Clone, Copy)]
+pub enum CsgOp {
+    Union,
+    Intersection,
+    // `a` with `b` carved out of it
+    Difference,
+}
+
+impl CsgOp {
+    fn inside(self, in_a: bool, in_b: bool) -> bool {
+        match self {
+            CsgOp::Union => in_a || in_b,
+            CsgOp::Intersection => in_a && in_b,
+            CsgOp::Difference => in_a && !in_b,
+        }
+    }
+}
+
+// a boolean combination of two solids, itself a solid so they nest; surfaces
+// keep the material of the solid they come from, so a carved hollow shows the
+// cutter's
+pub struct Csg {
+    pub op: CsgOp,
+    pub a: Box<dyn Solid>,
+    pub b: Box<dyn Solid>,
+}
+
+impl Solid for Csg {
+    // sweeps the boundaries of both operands along the ray, keeping the ones
+    // where being inside the result changes
+    fn spans(&self, r: &Ray) -> Vec<Span> {
+        let mut crossings: Vec<(HitRecord, bool, bool)> = Vec::new();
+        for (solid, is_a) in [(&self.a, true), (&self.b, false)] {
+            for span in solid.spans(r) {
+                crossings.push((span.enter, is_a, true));
+                crossings.push((span.exit, is_a, false));
+            }
+        }
+        crossings.sort_by(|x, y| x.0.t.total_cmp(&y.0.t));
+
+        let (mut in_a, mut in_b) = (false, false);
+        let mut enter: Option<HitRecord> = None;
+        let mut spans = Vec::new();
+        for (mut hit, is_a, entering) in crossings {
+            let was_inside = self.op.inside(in_a, in_b);
+            if is_a {
+                in_a = entering;
+            } else {
+                in_b = entering;
+            }
+            let inside = self.op.inside(in_a, in_b);
+            if inside == was_inside {
+                continue;
+            }
+            // the normal points out of the operand; flip it where entering the
+            // operand leaves the result, e.g. the floor of a carved hollow
+            if inside != entering {
+                hit.normal = -hit.normal;
+            }
+            match enter.take() {
+                None => enter = Some(hit),
+                Some(enter) => spans.push(Span { enter, exit: hit }),
+            }
+        }
+        spans
+    }
+}
+
+impl Hittable for Csg {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        first_crossing(self.spans(r), t_min, t_max)
+    }
+}
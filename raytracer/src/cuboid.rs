@@ -0,0 +1,87 @@
+use math::{DVec3 as Vec3, Point3};
+
+use crate::hittable::{first_crossing, HitRecord, Hittable, Solid, Span};
+use crate::material::Material;
+use crate::ray::Ray;
+
+// an axis-aligned box between the corners `min` and `max`. Each face has uv 0..1
+// across it: along z and y on the x faces, x and z on the y faces, and x and y
+// on the z faces, so v points up the sides
+pub struct Cuboid {
+    pub min: Point3,
+    pub max: Point3,
+    pub material: Material,
+}
+
+impl Solid for Cuboid {
+    // slab test: the ray is inside the box where it's between all three pairs
+    // of planes
+    fn spans(&self, r: &Ray) -> Vec<Span> {
+        let (origin, direction) = (r.origin.to_array(), r.direction.to_array());
+        let (min, max) = (self.min.to_array(), self.max.to_array());
+        let mut enter = (f64::NEG_INFINITY, Vec3::ZERO);
+        let mut exit = (f64::INFINITY, Vec3::ZERO);
+        for axis in 0..3 {
+            if direction[axis] == 0.0 {
+                if origin[axis] < min[axis] || origin[axis] > max[axis] {
+                    return Vec::new();
+                }
+                continue;
+            }
+            let mut normal = [0.0; 3];
+            normal[axis] = 1.0;
+            let normal = Vec3::from(normal);
+            let t0 = (min[axis] - origin[axis]) / direction[axis];
+            let t1 = (max[axis] - origin[axis]) / direction[axis];
+            // the ray crosses the min plane going in if it travels along +axis
+            let (near, far) = if t0 < t1 {
+                ((t0, -normal), (t1, normal))
+            } else {
+                ((t1, normal), (t0, -normal))
+            };
+            if near.0 > enter.0 {
+                enter = near;
+            }
+            if far.0 < exit.0 {
+                exit = far;
+            }
+        }
+        if enter.0 >= exit.0 {
+            return Vec::new();
+        }
+        let record = |(t, normal)| self.record(t, r.at(t), normal, &min, &max);
+        vec![Span {
+            enter: record(enter),
+            exit: record(exit),
+        }]
+    }
+}
+
+impl Cuboid {
+    fn record(&self, t: f64, p: Point3, normal: Vec3, min: &[f64; 3], max: &[f64; 3]) -> HitRecord {
+        let axis = normal
+            .to_array()
+            .iter()
+            .position(|&c| c != 0.0)
+            .unwrap_or(0);
+        let (u_axis, v_axis) = match axis {
+            0 => (2, 1),
+            1 => (0, 2),
+            _ => (0, 1),
+        };
+        let point = p.to_array();
+        let along = |i: usize| (point[i] - min[i]) / (max[i] - min[i]);
+        let mut tangent = [0.0; 3];
+        tangent[u_axis] = 1.0;
+        let mut hit = HitRecord::new(t, p, normal, self.material.clone());
+        hit.uv = (along(u_axis), along(v_axis));
+        hit.tangent = Vec3::from(tangent);
+        hit
+    }
+}
+
+impl Hittable for Cuboid {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        first_crossing(self.spans(r), t_min, t_max)
+    }
+}
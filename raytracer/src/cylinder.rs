@@ -0,0 +1,92 @@
+use math::{DVec3 as Vec3, Point3};
+
+use crate::disk::{disk_uv, facing, hit_disk};
+use crate::hittable::{HitRecord, Hittable};
+use crate::material::Material;
+use crate::ray::Ray;
+use crate::sampling::Onb;
+
+// a tube of `radius` from `base` along `axis` for `height`, closed with flat end
+// caps when `caps` is set; `axis` needn't be unit length. On the wall u goes once
+// around the axis and v from the base to the top; the caps are mapped like disks
+pub struct Cylinder {
+    pub base: Point3,
+    pub axis: Vec3,
+    pub radius: f64,
+    pub height: f64,
+    pub caps: bool,
+    pub material: Material,
+}
+
+impl Cylinder {
+    // nearest hit on the tube wall between the two ends, with its radial normal
+    fn hit_wall(&self, axis: Vec3, r: &Ray, t_min: f64, t_max: f64) -> Option<(f64, Vec3)> {
+        // the infinite tube is a circle once the axis component is projected out
+        let oc = r.origin - self.base;
+        let d = r.direction - axis * r.direction.dot(&axis);
+        let o = oc - axis * oc.dot(&axis);
+        let a = d.length_squared();
+        if a < 1e-12 {
+            return None;
+        }
+        let half_b = o.dot(&d);
+        let c = o.length_squared() - self.radius * self.radius;
+        let discriminant = half_b * half_b - a * c;
+        if discriminant < 0.0 {
+            return None;
+        }
+        let sqrtd = discriminant.sqrt();
+        [(-half_b - sqrtd) / a, (-half_b + sqrtd) / a]
+            .into_iter()
+            .filter(|&t| t > t_min && t < t_max)
+            .find_map(|t| {
+                let h = (r.at(t) - self.base).dot(&axis);
+                if !(0.0..=self.height).contains(&h) {
+                    return None;
+                }
+                let normal = (o + d * t) / self.radius;
+                Some((t, normal))
+            })
+    }
+}
+
+impl Hittable for Cylinder {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        let axis = self.axis.unit();
+        let mut closest = self.hit_wall(axis, r, t_min, t_max);
+        if self.caps {
+            let top = self.base + axis * self.height;
+            for (center, normal) in [(self.base, -axis), (top, axis)] {
+                let t_max = closest.map_or(t_max, |(t, _)| t);
+                if let Some(t) = hit_disk(center, normal, self.radius, r, t_min, t_max) {
+                    closest = Some((t, normal));
+                }
+            }
+        }
+        let (t, normal) = closest?;
+        let p = r.at(t);
+        // cap normals are along the axis, the wall's across it
+        let (uv, tangent) = if normal.dot(&axis).abs() > 0.5 {
+            let center = if normal.dot(&axis) > 0.0 {
+                self.base + axis * self.height
+            } else {
+                self.base
+            };
+            disk_uv(center, normal, self.radius, p)
+        } else {
+            let onb = Onb::from_w(axis);
+            let around = normal
+                .dot(&axis.cross(&onb.u()))
+                .atan2(normal.dot(&onb.u()));
+            let u = around / (2.0 * std::f64::consts::PI) + 0.5;
+            let v = (p - self.base).dot(&axis) / self.height;
+            ((u, v), axis.cross(&normal))
+        };
+        // closed, the wall and caps point outward; an open tube can be seen
+        // from inside, and is thin like a disk
+        let normal = if self.caps { normal } else { facing(normal, r) };
+        let mut hit = HitRecord::new(t, p, normal, self.material.clone());
+        (hit.uv, hit.tangent) = (uv, tangent);
+        Some(hit)
+    }
+}
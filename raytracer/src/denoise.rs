@@ -0,0 +1,82 @@
+use math::Color;
+
+use crate::aov::Guide;
+
+// passes of the à-trous filter; the taps spread twice as far each pass, so the
+// last one reaches 2 * 2^(ITERATIONS - 1) pixels
+const ITERATIONS: u32 = 5;
+// B3 spline, applied separately along x and y offsets of the 5x5 taps
+const KERNEL: [f64; 5] = [1.0 / 16.0, 1.0 / 4.0, 3.0 / 8.0, 1.0 / 4.0, 1.0 / 16.0];
+// how different a neighbour may be and still count: color difference at
+// strength 1, 1 - cos of the angle between normals, and depth relative to the
+// pixel's
+const COLOR_SIGMA: f64 = 0.5;
+const NORMAL_SIGMA: f64 = 0.05;
+const DEPTH_SIGMA: f64 = 0.02;
+
+// edge-avoiding à-trous wavelet filter: repeated 5x5 blurs with ever wider
+// spacing, where each tap is weighted down the more its color, normal or depth
+// differs from the centre's, so noise in flat regions is smoothed while edges
+// stay sharp; misses only blend with other misses
+pub fn denoise(
+    pixels: &mut Vec<Color>,
+    guides: &[Option<Guide>],
+    width: usize,
+    height: usize,
+    strength: f64,
+) {
+    for i in 0..ITERATIONS {
+        // finer color detail left after each pass is more likely to be real
+        let color_sigma = COLOR_SIGMA * strength / (1 << i) as f64;
+        *pixels = pass(pixels, guides, width, height, 1 << i, color_sigma);
+    }
+}
+
+fn pass(
+    src: &[Color],
+    guides: &[Option<Guide>],
+    width: usize,
+    height: usize,
+    step: usize,
+    color_sigma: f64,
+) -> Vec<Color> {
+    let mut out = Vec::with_capacity(src.len());
+    for y in 0..height {
+        for x in 0..width {
+            let centre = y * width + x;
+            let (c, g) = (src[centre], guides[centre]);
+            let mut sum = Color::ZERO;
+            let mut total = 0.0;
+            for (ky, wy) in KERNEL.iter().enumerate() {
+                for (kx, wx) in KERNEL.iter().enumerate() {
+                    // taps past the edges are clamped onto them
+                    let sx = (x as isize + (kx as isize - 2) * step as isize)
+                        .clamp(0, width as isize - 1) as usize;
+                    let sy = (y as isize + (ky as isize - 2) * step as isize)
+                        .clamp(0, height as isize - 1) as usize;
+                    let tap = sy * width + sx;
+                    let geometry = match (g, guides[tap]) {
+                        (None, None) => 1.0,
+                        (Some(a), Some(b)) => {
+                            let normal = (1.0 - a.normal.dot(&b.normal)).max(0.0) / NORMAL_SIGMA;
+                            let depth = (a.depth - b.depth).abs() / (DEPTH_SIGMA * a.depth);
+                            (-normal - depth).exp()
+                        }
+                        _ => 0.0,
+                    };
+                    if geometry == 0.0 {
+                        continue;
+                    }
+                    let d = src[tap] - c;
+                    let color = (-d.dot(&d) / (color_sigma * color_sigma)).exp();
+                    let w = wx * wy * geometry * color;
+                    sum += src[tap] * w;
+                    total += w;
+                }
+            }
+            // the centre tap always has full weight, so total > 0
+            out.push(sum * (1.0 / total));
+        }
+    }
+    out
+}
@@ -0,0 +1,67 @@
+use math::{DVec3 as Vec3, Point3};
+
+use crate::hittable::{HitRecord, Hittable};
+use crate::material::Material;
+use crate::ray::Ray;
+use crate::sampling::Onb;
+
+// a flat round surface; `normal` needn't be unit length
+pub struct Disk {
+    pub center: Point3,
+    pub normal: Vec3,
+    pub radius: f64,
+    pub material: Material,
+}
+
+// ray parameter where the ray meets the plane through `center` within
+// `radius` of it; `normal` must be unit length
+pub fn hit_disk(
+    center: Point3,
+    normal: Vec3,
+    radius: f64,
+    r: &Ray,
+    t_min: f64,
+    t_max: f64,
+) -> Option<f64> {
+    let denom = normal.dot(&r.direction);
+    // parallel to the plane
+    if denom.abs() < 1e-12 {
+        return None;
+    }
+    let t = (center - r.origin).dot(&normal) / denom;
+    if t <= t_min || t >= t_max {
+        return None;
+    }
+    ((r.at(t) - center).length_squared() <= radius * radius).then_some(t)
+}
+
+// planar coordinates for a round face: the square around the circle mapped to
+// 0..1 each way, along two axes picked from `normal` alone so both sides agree;
+// also the direction u grows in
+pub fn disk_uv(center: Point3, normal: Vec3, radius: f64, p: Point3) -> ((f64, f64), Vec3) {
+    let onb = Onb::from_w(normal);
+    let d = (p - center) / (2.0 * radius);
+    let tangent = onb.u();
+    let bitangent = normal.unit().cross(&tangent);
+    ((0.5 + d.dot(&tangent), 0.5 + d.dot(&bitangent)), tangent)
+}
+
+// a thin surface has no inside, so its normal is turned to face the ray
+pub fn facing(normal: Vec3, r: &Ray) -> Vec3 {
+    if normal.dot(&r.direction) > 0.0 {
+        -normal
+    } else {
+        normal
+    }
+}
+
+impl Hittable for Disk {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        let normal = self.normal.unit();
+        let t = hit_disk(self.center, normal, self.radius, r, t_min, t_max)?;
+        let p = r.at(t);
+        let mut hit = HitRecord::new(t, p, facing(normal, r), self.material.clone());
+        (hit.uv, hit.tangent) = disk_uv(self.center, normal, self.radius, p);
+        Some(hit)
+    }
+}
@@ -0,0 +1,146 @@
+// golden-image regression test: a few spheres, one of each material under a
+// point light and the sky, rendered small at a fixed seed and compared with
+// the reference committed next to the crate. After a change that's meant to
+// alter the image, `BLESS=1 cargo test -p raytracer golden` writes a new
+// reference instead of comparing; look it over before committing it
+use std::path::PathBuf;
+
+use math::{Color, Point3};
+
+use crate::background::{Background, Sky};
+use crate::camera::Projection;
+use crate::hittable::Hittable;
+use crate::light::Light;
+use crate::material::{Cauchy, Material};
+use crate::sampling::{PixelFilter, Sampler};
+use crate::sphere::Sphere;
+use crate::texture::Texture;
+use crate::tiles::TileOrder;
+use crate::{render, scene_camera, Scene};
+
+const WIDTH: usize = 80;
+const HEIGHT: usize = 45;
+const SAMPLES: u32 = 16;
+const SEED: u64 = 7;
+// how far a channel may stray, in 8-bit levels, before the pixel counts as
+// changed; room for floating-point differences between platforms, far less
+// than any shading change shows up as
+const TOLERANCE: u8 = 2;
+
+fn reference_path() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/golden/spheres.png")
+}
+
+fn matte(albedo: Color) -> Material {
+    Material::Lambertian {
+        albedo: Texture::Solid(albedo),
+        normal_map: None,
+    }
+}
+
+fn spheres() -> Scene {
+    let sphere = |x: f64, y: f64, z: f64, radius: f64, material: Material| -> Box<dyn Hittable> {
+        Box::new(Sphere {
+            center: Point3::new(x, y, z),
+            radius,
+            material,
+        })
+    };
+    Scene {
+        world: vec![
+            sphere(0.0, -100.5, -1.0, 100.0, matte(Color::new(0.5, 0.5, 0.45))),
+            sphere(0.0, 0.0, -1.2, 0.5, matte(Color::new(0.7, 0.3, 0.3))),
+            sphere(
+                -1.0,
+                0.0,
+                -1.5,
+                0.5,
+                Material::Metal {
+                    albedo: Color::new(0.8, 0.8, 0.8),
+                    fuzz: 0.2,
+                },
+            ),
+            sphere(
+                1.0,
+                0.0,
+                -1.5,
+                0.5,
+                Material::Dielectric { ior: Cauchy::CROWN },
+            ),
+        ],
+        lights: vec![Light::Point {
+            position: Point3::new(-2.0, 3.0, 0.5),
+            intensity: Color::new(30.0, 27.0, 22.0),
+        }],
+        area_lights: Vec::new(),
+        background: Background::Sky(Sky::Gradient {
+            bottom: Color::new(1.0, 1.0, 1.0),
+            top: Color::new(0.5, 0.7, 1.0),
+        }),
+        sampler: Sampler::Cosine,
+        filter: PixelFilter::Box,
+        rr_start_depth: 3,
+        spectral: false,
+        seed: SEED,
+        transparent: false,
+        tile_order: TileOrder::Spiral,
+    }
+}
+
+fn render_rgb8() -> Vec<u8> {
+    let camera = scene_camera(
+        WIDTH as f64 / HEIGHT as f64,
+        90.0,
+        Projection::Perspective,
+        0.0,
+    );
+    let (pixels, _) = render(&camera, &spheres(), WIDTH, HEIGHT, SAMPLES, None);
+    pixels
+        .iter()
+        .flat_map(|&pixel| image_io::encode_rgb8(pixel, 1.0, image_io::DEFAULT_GAMMA))
+        .collect()
+}
+
+#[test]
+fn spheres_match_reference() {
+    let path = reference_path();
+    let actual = render_rgb8();
+    if std::env::var_os("BLESS").is_some() {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        image_io::write_png_rgb8(&path, WIDTH as u32, HEIGHT as u32, &actual).unwrap();
+        return;
+    }
+
+    let reference = image::open(&path)
+        .unwrap_or_else(|err| panic!("{}: {err}; run with BLESS=1 to create it", path.display()))
+        .into_rgb8();
+    assert_eq!(
+        reference.dimensions(),
+        (WIDTH as u32, HEIGHT as u32),
+        "reference is a different size"
+    );
+    let expected = reference.into_raw();
+    let changed: Vec<(usize, u8)> = actual
+        .chunks(3)
+        .zip(expected.chunks(3))
+        .enumerate()
+        .filter_map(|(i, (a, e))| {
+            let difference = a.iter().zip(e).map(|(a, e)| a.abs_diff(*e)).max().unwrap();
+            (difference > TOLERANCE).then_some((i, difference))
+        })
+        .collect();
+    if let Some(&(worst, difference)) = changed.iter().max_by_key(|(_, difference)| *difference) {
+        // saved for comparing by eye
+        let out = std::env::temp_dir().join("raytracer-golden-spheres.png");
+        image_io::write_png_rgb8(&out, WIDTH as u32, HEIGHT as u32, &actual).unwrap();
+        panic!(
+            "{} of {} pixels differ from {} by more than {TOLERANCE} levels, the most by {difference} at ({}, {}); the render is at {}",
+            changed.len(),
+            WIDTH * HEIGHT,
+            path.display(),
+            worst % WIDTH,
+            worst / WIDTH,
+            out.display()
+        );
+    }
+}
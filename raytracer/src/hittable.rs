@@ -0,0 +1,78 @@
+use math::{DVec3 as Vec3, Point3};
+
+use crate::material::Material;
+use crate::ray::Ray;
+use crate::sampling::Onb;
+
+#[derive(Clone)]
+pub struct HitRecord {
+    pub t: f64,
+    pub p: Point3,
+    // unit length, pointing out of the surface
+    pub normal: Vec3,
+    // surface coordinates in 0..1 for textures, and the direction u grows in
+    // at `p`, perpendicular to the normal; each primitive documents how it maps
+    // them, and instances and CSG pass them through unchanged
+    pub uv: (f64, f64),
+    pub tangent: Vec3,
+    pub material: Material,
+}
+
+impl HitRecord {
+    // for surfaces without a parameterization: uv at the origin and an
+    // arbitrary tangent
+    pub fn new(t: f64, p: Point3, normal: Vec3, material: Material) -> Self {
+        Self {
+            t,
+            p,
+            normal,
+            uv: (0.0, 0.0),
+            tangent: Onb::from_w(normal).u(),
+            material,
+        }
+    }
+}
+
+// shared by the render threads
+pub trait Hittable: Send + Sync {
+    // nearest intersection with t in (t_min, t_max)
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord>;
+}
+
+// a stretch of the ray inside a solid, with the surface crossings at both ends
+pub struct Span {
+    pub enter: HitRecord,
+    pub exit: HitRecord,
+}
+
+// closed shapes with an inside, which CSG can combine
+pub trait Solid: Send + Sync {
+    // every span along the whole line of the ray, negative t included, sorted
+    // and non-overlapping
+    fn spans(&self, r: &Ray) -> Vec<Span>;
+}
+
+// closest hit against everything in the world
+pub fn hit_world(
+    r: &Ray,
+    world: &[Box<dyn Hittable>],
+    t_min: f64,
+    t_max: f64,
+) -> Option<HitRecord> {
+    let mut closest: Option<HitRecord> = None;
+    for object in world {
+        let t_max = closest.as_ref().map_or(t_max, |hit| hit.t);
+        if let Some(hit) = object.hit(r, t_min, t_max) {
+            closest = Some(hit);
+        }
+    }
+    closest
+}
+
+// nearest span boundary within (t_min, t_max), for solids hit through their spans
+pub fn first_crossing(spans: Vec<Span>, t_min: f64, t_max: f64) -> Option<HitRecord> {
+    spans
+        .into_iter()
+        .flat_map(|span| [span.enter, span.exit])
+        .find(|hit| hit.t > t_min && hit.t < t_max)
+}
@@ -0,0 +1,87 @@
+use math::DVec3 as Vec3;
+
+use crate::hittable::{HitRecord, Hittable};
+use crate::ray::Ray;
+
+// another object moved by `offset`; the ray is moved the other way instead
+pub struct Translate {
+    pub object: Box<dyn Hittable>,
+    pub offset: Vec3,
+}
+
+impl Hittable for Translate {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        let moved = Ray::new(r.origin - self.offset, r.direction, r.time);
+        let mut hit = self.object.hit(&moved, t_min, t_max)?;
+        hit.p += self.offset;
+        Some(hit)
+    }
+}
+
+// an `nx` by `nz` field of objects `spacing` apart across x and z, centred on
+// where `make` puts each of them, which is told the cell it's building
+pub fn grid(
+    nx: usize,
+    nz: usize,
+    spacing: f64,
+    make: impl Fn(usize, usize) -> Box<dyn Hittable>,
+) -> Vec<Box<dyn Hittable>> {
+    let centre = |n: usize, i: usize| (i as f64 - (n - 1) as f64 / 2.0) * spacing;
+    (0..nz)
+        .flat_map(|k| (0..nx).map(move |i| (i, k)))
+        .map(|(i, k)| -> Box<dyn Hittable> {
+            Box::new(Translate {
+                object: make(i, k),
+                offset: Vec3::new(centre(nx, i), 0.0, centre(nz, k)),
+            })
+        })
+        .collect()
+}
+
+// another object turned about the world's y axis through the origin
+pub struct RotateY {
+    object: Box<dyn Hittable>,
+    sin: f64,
+    cos: f64,
+}
+
+impl RotateY {
+    // counter-clockwise seen from above
+    pub fn new(object: Box<dyn Hittable>, degrees: f64) -> Self {
+        let (sin, cos) = degrees.to_radians().sin_cos();
+        Self { object, sin, cos }
+    }
+
+    // world to the object's frame
+    fn to_object(&self, v: Vec3) -> Vec3 {
+        Vec3::new(
+            self.cos * v.x - self.sin * v.z,
+            v.y,
+            self.sin * v.x + self.cos * v.z,
+        )
+    }
+
+    fn to_world(&self, v: Vec3) -> Vec3 {
+        Vec3::new(
+            self.cos * v.x + self.sin * v.z,
+            v.y,
+            -self.sin * v.x + self.cos * v.z,
+        )
+    }
+}
+
+impl Hittable for RotateY {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        let turned = Ray::new(
+            self.to_object(r.origin),
+            self.to_object(r.direction),
+            r.time,
+        );
+        // a rotation keeps lengths, so t carries over unchanged
+        let mut hit = self.object.hit(&turned, t_min, t_max)?;
+        hit.p = self.to_world(hit.p);
+        hit.normal = self.to_world(hit.normal);
+        hit.tangent = self.to_world(hit.tangent);
+        Some(hit)
+    }
+}
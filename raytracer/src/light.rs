@@ -0,0 +1,129 @@
+use math::{Color, Point3};
+use rand::Rng;
+
+use crate::background::EnvMap;
+use crate::hittable::{hit_world, HitRecord, Hittable};
+use crate::material::Material;
+use crate::pdf::{LightPdf, Pdf};
+use crate::ray::Ray;
+use crate::sampling::{random_in_unit_sphere, Sampler};
+
+// lights that aren't part of the geometry; they're only reached through shadow
+// rays, never by a bounce
+#[derive(Clone, Copy)]
+pub enum Light {
+    Point {
+        position: Point3,
+        intensity: Color,
+    },
+    // a glowing ball: each shadow ray aims at a random point inside it, which
+    // softens the shadow edges
+    Sphere {
+        center: Point3,
+        radius: f64,
+        intensity: Color,
+    },
+}
+
+impl Light {
+    // the point a shadow ray should aim at, and the light's intensity
+    fn sample(&self, rng: &mut impl Rng) -> (Point3, Color) {
+        match *self {
+            Light::Point {
+                position,
+                intensity,
+            } => (position, intensity),
+            Light::Sphere {
+                center,
+                radius,
+                intensity,
+            } => (center + random_in_unit_sphere(rng) * radius, intensity),
+        }
+    }
+}
+
+// irradiance-like term at a hit from every light that isn't occluded at `time`:
+// intensity * cos / dist^2, divided by pi for the Lambertian BRDF
+pub fn direct_light(
+    hit: &HitRecord,
+    time: f64,
+    world: &[Box<dyn Hittable>],
+    lights: &[Light],
+    rng: &mut impl Rng,
+) -> Color {
+    let (p, n) = (hit.p, hit.normal);
+    let mut total = Color::ZERO;
+    for light in lights {
+        let (target, intensity) = light.sample(rng);
+        let to_light = target - p;
+        let dist_squared = to_light.length_squared();
+        let dist = dist_squared.sqrt();
+        let dir = to_light / dist;
+        let cos = n.dot(&dir);
+        if cos <= 0.0 {
+            continue;
+        }
+        // the shadow ray's direction is normalized, so t is a distance
+        let shadow = Ray::new(p, dir, time);
+        if hit_world(&shadow, world, 0.001, dist).is_some() {
+            continue;
+        }
+        total += intensity * (cos / (std::f64::consts::PI * dist_squared));
+    }
+    total
+}
+
+// the same term from the area lights: one shadow ray towards a point on one of
+// them, which counts if it reaches the lamp's emissive surface. Balance-weighted
+// against `sampler`'s diffuse bounce finding the lamp too, the weight over the
+// light sample's density leaves one over the two densities' sum
+pub fn area_light(
+    hit: &HitRecord,
+    time: f64,
+    world: &[Box<dyn Hittable>],
+    lamps: &LightPdf,
+    sampler: Sampler,
+    rng: &mut impl Rng,
+) -> Color {
+    let direction = lamps.generate(rng);
+    let cos = hit.normal.dot(&direction.unit());
+    if cos <= 0.0 {
+        return Color::ZERO;
+    }
+    let shadow = Ray::new(hit.p, direction, time);
+    match hit_world(&shadow, world, 0.001, f64::INFINITY) {
+        Some(HitRecord {
+            material: Material::Emissive { radiance },
+            ..
+        }) => {
+            let densities = lamps.value(direction) + sampler.pdf(hit.normal, direction);
+            radiance * (cos / (std::f64::consts::PI * densities))
+        }
+        _ => Color::ZERO,
+    }
+}
+
+// the same term from an environment image: one shadow ray in a direction drawn
+// by the image's brightness, which counts if it escapes the scene. Balanced
+// against `sampler`'s diffuse bounce escaping the same way, like `area_light`,
+// this leaves one over the sum of the two densities
+pub fn env_light(
+    hit: &HitRecord,
+    time: f64,
+    world: &[Box<dyn Hittable>],
+    env: &EnvMap,
+    sampler: Sampler,
+    rng: &mut impl Rng,
+) -> Color {
+    let direction = env.generate(rng);
+    let cos = hit.normal.dot(&direction);
+    if cos <= 0.0 {
+        return Color::ZERO;
+    }
+    let shadow = Ray::new(hit.p, direction, time);
+    if hit_world(&shadow, world, 0.001, f64::INFINITY).is_some() {
+        return Color::ZERO;
+    }
+    let densities = env.value(direction) + sampler.pdf(hit.normal, direction);
+    env.sample(direction) * (cos / (std::f64::consts::PI * densities))
+}
@@ -1,211 +1,751 @@
-use std::fs::File;
-use std::io::{BufWriter, Write};
-
-use rand::Rng;
-
-#[derive(Clone, Copy, Debug)]
-struct Vec3 {
-    x: f64,
-    y: f64,
-    z: f64,
+mod aov;
+mod background;
+mod bench;
+mod bloom;
+mod camera;
+mod csg;
+mod cuboid;
+mod cylinder;
+mod denoise;
+mod disk;
+#[cfg(test)]
+mod golden;
+mod hittable;
+mod instance;
+mod light;
+mod material;
+mod mesh;
+mod path;
+mod pdf;
+mod presets;
+mod preview;
+mod quad;
+mod ray;
+mod sampling;
+mod spectrum;
+mod sphere;
+mod stereo;
+mod texture;
+mod tiles;
+
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+use clap::{error::ErrorKind, CommandFactory, Parser};
+use math::{Color, DVec3 as Vec3, Point3};
+use rand::rngs::SmallRng;
+use rand::{Rng, SeedableRng};
+
+use aov::Aov;
+use background::{Background, EnvMap, Sky};
+use camera::{Camera, Projection};
+use hittable::{hit_world, HitRecord, Hittable};
+use light::{area_light, direct_light, env_light, Light};
+use material::{dielectric_bounce, metal_bounce, Material, REFERENCE_WAVELENGTH};
+use mesh::Mesh;
+use path::CameraPath;
+use pdf::{AreaLight, LightPdf, Pdf};
+use presets::Preset;
+use preview::Framebuffer;
+use ray::Ray;
+use sampling::{PixelFilter, Sampler};
+use stereo::Stereo;
+use texture::{NormalMap, TextureFilter};
+use tiles::TileOrder;
+
+#[derive(Parser)]
+#[command(about = "Renders a small ray-traced scene to image.ppm")]
+struct Args {
+    /// Built-in scene to render
+    #[arg(long, value_enum, default_value_t = Preset::Showcase)]
+    scene: Preset,
+    /// Seed for the layout of `--scene random` and for the samples, so the same command renders the same image
+    #[arg(long, default_value_t = 0)]
+    seed: u64,
+    /// Wavefront OBJ model for `--scene mesh`; smooth-shaded where it has vertex normals
+    #[arg(long)]
+    obj: Option<PathBuf>,
+    /// Rays per pixel
+    #[arg(long, default_value_t = 20)]
+    samples: u32,
+    /// How diffuse bounces are sampled, to compare their noise
+    #[arg(long, value_enum, default_value_t = Sampler::Cosine)]
+    sampler: Sampler,
+    /// Pixel reconstruction filter: box averages the samples in each pixel, tent and gaussian weight them by distance from its centre and reach into the neighbours
+    #[arg(long, value_enum, default_value_t = PixelFilter::Box)]
+    filter: PixelFilter,
+    /// Equirectangular environment image (HDR, EXR, PNG, ...) for the sky instead of the gradient
+    #[arg(long)]
+    env: Option<PathBuf>,
+    /// Sky color for missed rays: `black`, a solid `r,g,b`, or a `bottom:top` gradient; each scene has its own default
+    #[arg(long)]
+    sky: Option<Sky>,
+    /// Make everything brighter than this glow; no bloom when omitted
+    #[arg(long)]
+    bloom_threshold: Option<f64>,
+    /// Reach of the bloom glow in pixels
+    #[arg(long, default_value_t = 8)]
+    bloom_radius: usize,
+    /// Smooth out sampling noise, guided by the depth and normal passes so edges stay sharp
+    #[arg(long)]
+    denoise: bool,
+    /// How strong a color difference the denoiser still smooths over; above 0
+    #[arg(long, default_value_t = 1.0)]
+    denoise_strength: f64,
+    /// Tangent-space normal map (PNG, ...) to make the red sphere look bumpy
+    #[arg(long)]
+    normal_map: Option<PathBuf>,
+    /// How textures are looked up between texels
+    #[arg(long, value_enum, default_value_t = TextureFilter::Bilinear)]
+    texture_filter: TextureFilter,
+    /// Extra passes from each pixel's first hit, written next to the image as image.<pass>.png
+    #[arg(long, value_enum, value_delimiter = ',')]
+    aov: Vec<Aov>,
+    /// Perspective, or parallel rays for a diagram-like look
+    #[arg(long, value_enum, default_value_t = Projection::Perspective)]
+    projection: Projection,
+    /// Height of the scene in world units that fills the image with `--projection ortho`
+    #[arg(long, default_value_t = 2.5)]
+    ortho_scale: f64,
+    /// Gamma the image is encoded with; above 0
+    #[arg(long, default_value_t = image_io::DEFAULT_GAMMA)]
+    gamma: f64,
+    /// Brightness multiplier applied to the linear image before clipping and gamma; 0 or more
+    #[arg(long, default_value_t = 1.0)]
+    exposure: f64,
+    /// Bounces before Russian roulette may end a path early
+    #[arg(long, default_value_t = 3)]
+    rr_start_depth: u32,
+    /// Give glass a different index per wavelength, for rainbow fringes; noisier and slower
+    #[arg(long)]
+    spectral: bool,
+    /// Render a view for each eye and combine them, for 3D glasses
+    #[arg(long, value_enum)]
+    stereo: Option<Stereo>,
+    /// Distance between the two eyes with `--stereo`, in world units
+    #[arg(long, default_value_t = 0.06)]
+    interocular: f64,
+    /// How far ahead the eyes' lines of sight meet with `--stereo`; things at this distance appear at the depth of the screen
+    #[arg(long, default_value_t = 2.0)]
+    convergence: f64,
+    /// Time renders of fixed benchmark scenes instead, and print the results
+    #[arg(long)]
+    bench: bool,
+    /// Render an animation along the camera keyframes in this JSON file, one PNG per frame in `--out-dir`
+    #[arg(long, value_name = "PATH.json", conflicts_with_all = ["stereo", "aov"])]
+    camera_path: Option<PathBuf>,
+    /// Number of frames to render along `--camera-path`
+    #[arg(long, default_value_t = 60, value_parser = clap::value_parser!(u32).range(1..))]
+    frames: u32,
+    /// Directory for the `--camera-path` frames, created if missing
+    #[arg(long, default_value = "frames")]
+    out_dir: PathBuf,
+    /// Leave the background see-through where camera rays miss everything, writing image.png with alpha instead of image.ppm; reflections and refractions still show the sky
+    #[arg(long, conflicts_with = "stereo")]
+    transparent: bool,
+    /// Show the render in a window as the tiles finish, as well as writing it; the window stays open until closed
+    #[arg(long)]
+    preview: bool,
+    /// Order the tiles are rendered in, to see the middle of the image (spiral) or a compact patch of it (hilbert) first in `--preview`
+    #[arg(long, value_enum, default_value_t = TileOrder::Spiral)]
+    tile_order: TileOrder,
 }
 
-impl Vec3 {
-    fn new(x: f64, y: f64, z: f64) -> Self {
-        Self { x, y, z }
-    }
-
-    fn length(&self) -> f64 {
-        self.length_squared().sqrt()
-    }
+// everything a ray can interact with, plus how it's sampled
+struct Scene {
+    world: Vec<Box<dyn Hittable>>,
+    lights: Vec<Light>,
+    area_lights: Vec<AreaLight>,
+    background: Background,
+    sampler: Sampler,
+    filter: PixelFilter,
+    rr_start_depth: u32,
+    spectral: bool,
+    // mixed into each pixel's seed
+    seed: u64,
+    // `--transparent`: camera rays that miss leave the pixel see-through
+    // instead of showing the background
+    transparent: bool,
+    tile_order: TileOrder,
+}
 
-    fn length_squared(&self) -> f64 {
-        self.x * self.x + self.y * self.y + self.z * self.z
-    }
+// bounces before a path is cut off and contributes nothing more; Russian
+// roulette ends nearly all paths long before, so this is only a safety net
+const MAX_DEPTH: u32 = 32;
 
-    fn dot(&self, other: &Self) -> f64 {
-        self.x * other.x + self.y * other.y + self.z * other.z
-    }
+// the image is rendered in square tiles of this many pixels, which the threads
+// take from a shared queue as they go; heavy tiles then don't hold up a whole
+// row or thread's share
+const TILE_SIZE: usize = 16;
 
-    fn unit(self) -> Self {
-        let len = self.length();
-        Self::new(self.x / len, self.y / len, self.z / len)
-    }
+// how much of the light a bounce finds counts, for the share of it the hit it
+// left already sampled with a shadow ray: `lamps` scales any lamp it hits and
+// `sky` an environment image it escapes to
+#[derive(Clone, Copy)]
+struct EmissionWeights {
+    lamps: f64,
+    sky: f64,
 }
 
-use std::ops::{Add, Mul, Sub};
-
-impl Add for Vec3 {
-    type Output = Self;
-    fn add(self, rhs: Self) -> Self::Output {
-        Self::new(self.x + rhs.x, self.y + rhs.y, self.z + rhs.z)
-    }
+impl EmissionWeights {
+    const FULL: Self = Self {
+        lamps: 1.0,
+        sky: 1.0,
+    };
 }
 
-impl Sub for Vec3 {
-    type Output = Self;
-    fn sub(self, rhs: Self) -> Self::Output {
-        Self::new(self.x - rhs.x, self.y - rhs.y, self.z - rhs.z)
+// `throughput` is how much of this ray's light still reaches the camera, the
+// product of the albedos along the path so far. `wavelength` is set once a
+// `--spectral` path has split off at glass
+fn ray_color(
+    r: &Ray,
+    scene: &Scene,
+    depth: u32,
+    throughput: Color,
+    weights: EmissionWeights,
+    wavelength: Option<f64>,
+    rng: &mut impl Rng,
+) -> Color {
+    if depth == 0 {
+        return Color::ZERO;
     }
-}
 
-impl Mul<f64> for Vec3 {
-    type Output = Self;
-    fn mul(self, t: f64) -> Self::Output {
-        Self::new(self.x * t, self.y * t, self.z * t)
+    match hit_world(r, &scene.world, 0.001, f64::INFINITY) {
+        Some(hit) => hit_color(r, hit, scene, depth, throughput, weights, wavelength, rng),
+        None => scene.background.color(r.direction) * weights.sky,
     }
 }
 
-type Color = Vec3;
-type Point3 = Vec3;
-
-#[derive(Clone, Copy)]
-struct Ray {
-    origin: Point3,
-    direction: Vec3,
-}
-
-impl Ray {
-    fn new(origin: Point3, direction: Vec3) -> Self {
-        Self { origin, direction }
+// what `r` sees at `hit`, with the path state of `ray_color`
+#[allow(clippy::too_many_arguments)]
+fn hit_color(
+    r: &Ray,
+    mut hit: HitRecord,
+    scene: &Scene,
+    depth: u32,
+    throughput: Color,
+    weights: EmissionWeights,
+    wavelength: Option<f64>,
+    rng: &mut impl Rng,
+) -> Color {
+    // both the lighting and the bounce see any bumps
+    hit.normal = hit.material.shading_normal(&hit);
+    let albedo = match hit.material {
+        Material::Lambertian { ref albedo, .. } => albedo.value(hit.uv),
+        Material::Emissive { radiance } => return radiance * weights.lamps,
+        Material::Metal { albedo, fuzz } => {
+            // mirrors are as unsampleable by lights as glass
+            let Some(direction) = metal_bounce(r.direction, hit.normal, fuzz, rng) else {
+                return Color::ZERO;
+            };
+            let bounce = Ray::new(hit.p, direction, r.time);
+            let throughput = throughput * albedo;
+            return albedo
+                * continue_path(
+                    &bounce,
+                    scene,
+                    depth,
+                    throughput,
+                    EmissionWeights::FULL,
+                    wavelength,
+                    rng,
+                );
+        }
+        Material::Dielectric { ior } => {
+            // the first glass a spectral path meets picks the one
+            // wavelength it carries on with, weighted by its color
+            let (wavelength, tint) = match wavelength {
+                None if scene.spectral => {
+                    let (wavelength, tint) = spectrum::sample(rng);
+                    (Some(wavelength), tint)
+                }
+                _ => (wavelength, Color::new(1.0, 1.0, 1.0)),
+            };
+            let ior = ior.ior(wavelength.unwrap_or(REFERENCE_WAVELENGTH));
+            let direction = dielectric_bounce(r.direction, hit.normal, ior, rng);
+            // no light can be sampled through a perfect refraction, so
+            // whatever the ray finds next counts in full
+            let bounce = Ray::new(hit.p, direction, r.time);
+            let throughput = throughput * tint;
+            return tint
+                * continue_path(
+                    &bounce,
+                    scene,
+                    depth,
+                    throughput,
+                    EmissionWeights::FULL,
+                    wavelength,
+                    rng,
+                );
+        }
+    };
+    // next-event estimation: the explicit lights and any environment image
+    // through shadow rays, plus the sky, lamps and other surfaces through one
+    // diffuse bounce
+    let mut direct = direct_light(&hit, r.time, &scene.world, &scene.lights, rng);
+    let env = scene.background.env_map();
+    if let Some(env) = env {
+        direct += env_light(&hit, r.time, &scene.world, env, scene.sampler, rng);
     }
-
-    fn at(&self, t: f64) -> Point3 {
-        self.origin + self.direction * t
+    // lamps are reached both ways, so each way counts with its MIS weight
+    let lamps = (!scene.area_lights.is_empty()).then(|| LightPdf {
+        origin: hit.p,
+        lights: &scene.area_lights,
+    });
+    if let Some(lamps) = &lamps {
+        direct += area_light(&hit, r.time, &scene.world, lamps, scene.sampler, rng);
     }
+    let direction = scene.sampler.diffuse_direction(hit.normal, rng);
+    let bounce_density = scene.sampler.pdf(hit.normal, direction);
+    let lamps = lamps.as_ref().map_or(1.0, |lamps| {
+        let densities = bounce_density + lamps.value(direction);
+        if densities > 0.0 {
+            bounce_density / densities
+        } else {
+            1.0
+        }
+    });
+    let sky = env.map_or(1.0, |env| {
+        let densities = bounce_density + env.value(direction);
+        if densities > 0.0 {
+            bounce_density / densities
+        } else {
+            1.0
+        }
+    });
+    let bounce = Ray::new(hit.p, direction, r.time);
+    let throughput = throughput * albedo;
+    let indirect = continue_path(
+        &bounce,
+        scene,
+        depth,
+        throughput,
+        EmissionWeights { lamps, sky },
+        wavelength,
+        rng,
+    );
+    albedo * (direct + indirect)
 }
 
-#[derive(Clone, Copy)]
-struct Sphere {
-    center: Point3,
-    radius: f64,
+// `ray_color` for a camera ray, and whether it hit anything rather than
+// seeing the sky
+fn camera_ray_color(r: &Ray, scene: &Scene, rng: &mut impl Rng) -> (Color, bool) {
+    let white = Color::new(1.0, 1.0, 1.0);
+    match hit_world(r, &scene.world, 0.001, f64::INFINITY) {
+        Some(hit) => (
+            hit_color(
+                r,
+                hit,
+                scene,
+                MAX_DEPTH,
+                white,
+                EmissionWeights::FULL,
+                None,
+                rng,
+            ),
+            true,
+        ),
+        None => (scene.background.color(r.direction), false),
+    }
 }
 
-fn hit_sphere(center: Point3, radius: f64, r: &Ray) -> Option<f64> {
-    let oc = r.origin - center;
-    let a = r.direction.length_squared();
-    let half_b = oc.dot(&r.direction);
-    let c = oc.length_squared() - radius * radius;
-    let discriminant = half_b * half_b - a * c;
-    if discriminant < 0.0 {
-        None
+// follows `bounce` from a hit at `depth`. Russian roulette: past the first few
+// bounces, dim paths are ended at random, and the survivors count for the ones
+// ended to stay unbiased
+fn continue_path(
+    bounce: &Ray,
+    scene: &Scene,
+    depth: u32,
+    throughput: Color,
+    weights: EmissionWeights,
+    wavelength: Option<f64>,
+    rng: &mut impl Rng,
+) -> Color {
+    let survival = if MAX_DEPTH - depth >= scene.rr_start_depth {
+        throughput.x.max(throughput.y).max(throughput.z).min(1.0)
+    } else {
+        1.0
+    };
+    if rng.gen::<f64>() < survival {
+        let boost = 1.0 / survival;
+        ray_color(
+            bounce,
+            scene,
+            depth - 1,
+            throughput * boost,
+            weights,
+            wavelength,
+            rng,
+        ) * boost
     } else {
-        Some((-half_b - discriminant.sqrt()) / a)
+        Color::ZERO
     }
 }
 
-fn ray_color(r: &Ray, world: &[Sphere]) -> Color {
-    let mut closest_so_far = f64::INFINITY;
-    let mut hit_sphere_idx: Option<usize> = None;
-
-    for (i, s) in world.iter().enumerate() {
-        if let Some(t) = hit_sphere(s.center, s.radius, r) {
-            if t > 0.001 && t < closest_so_far {
-                closest_so_far = t;
-                hit_sphere_idx = Some(i);
-            }
+// the scene through `camera`, linear and from the top row down, and each
+// pixel's alpha: the share of its samples that hit something, or 1 throughout
+// unless `scene.transparent`; each tile also goes to `preview` as soon as
+// it's done
+fn render(
+    camera: &Camera,
+    scene: &Scene,
+    width: usize,
+    height: usize,
+    samples_per_pixel: u32,
+    preview: Option<&Framebuffer>,
+) -> (Vec<Color>, Vec<f64>) {
+    let tiles = scene
+        .tile_order
+        .tiles(width.div_ceil(TILE_SIZE), height.div_ceil(TILE_SIZE));
+    let tile_count = tiles.len();
+    // rows from the top of the image, as the PPM stores them
+    let pixels = Mutex::new((vec![Color::ZERO; width * height], vec![1.0; width * height]));
+    let next_tile = AtomicUsize::new(0);
+    let finished = AtomicUsize::new(0);
+    let threads = std::thread::available_parallelism().map_or(1, |n| n.get());
+    let radius = scene.filter.radius();
+
+    std::thread::scope(|s| {
+        for _ in 0..threads {
+            s.spawn(|| {
+                let mut tile = Vec::with_capacity(TILE_SIZE * TILE_SIZE);
+                let mut tile_alpha = Vec::with_capacity(TILE_SIZE * TILE_SIZE);
+                while let Some(&(column, row)) =
+                    tiles.get(next_tile.fetch_add(1, Ordering::Relaxed))
+                {
+                    let (x0, y0) = (column * TILE_SIZE, row * TILE_SIZE);
+                    let (x1, y1) = ((x0 + TILE_SIZE).min(width), (y0 + TILE_SIZE).min(height));
+
+                    tile.clear();
+                    tile_alpha.clear();
+                    for y in y0..y1 {
+                        let j = (height - 1 - y) as f64;
+                        for x in x0..x1 {
+                            // a generator per pixel, seeded from its position
+                            // rather than shared or per thread, so the image
+                            // doesn't depend on which thread took which tile
+                            let mut rng =
+                                SmallRng::seed_from_u64(sampling::pixel_seed(x, y, scene.seed));
+                            // the filter-weighted sum of the samples, and of the
+                            // weights to normalize it by; made transparent, only
+                            // the samples that hit something count towards the
+                            // color, and their share of the weight is the alpha
+                            let mut pixel_color = Color::ZERO;
+                            let mut total_weight = 0.0;
+                            let mut hit_weight = 0.0;
+                            for _ in 0..samples_per_pixel {
+                                let dx = rng.gen_range(-radius..radius);
+                                let dy = rng.gen_range(-radius..radius);
+                                let weight = scene.filter.weight(dx, dy);
+                                let u = (x as f64 + 0.5 + dx) / (width - 1) as f64;
+                                let v = (j + 0.5 + dy) / (height - 1) as f64;
+                                let r = camera.get_ray(u, v, &mut rng);
+                                let (color, hit) = camera_ray_color(&r, scene, &mut rng);
+                                if hit {
+                                    hit_weight += weight;
+                                }
+                                if hit || !scene.transparent {
+                                    pixel_color += color * weight;
+                                }
+                                total_weight += weight;
+                            }
+                            let color_weight = if scene.transparent {
+                                hit_weight
+                            } else {
+                                total_weight
+                            };
+                            tile.push(if color_weight > 0.0 {
+                                pixel_color * (1.0 / color_weight)
+                            } else {
+                                Color::ZERO
+                            });
+                            tile_alpha.push(if scene.transparent && total_weight > 0.0 {
+                                hit_weight / total_weight
+                            } else {
+                                1.0
+                            });
+                        }
+                    }
+
+                    let mut pixels = pixels.lock().unwrap();
+                    let (colors, alphas) = &mut *pixels;
+                    for ((row, alpha_row), y) in tile
+                        .chunks(x1 - x0)
+                        .zip(tile_alpha.chunks(x1 - x0))
+                        .zip(y0..y1)
+                    {
+                        colors[y * width + x0..y * width + x1].copy_from_slice(row);
+                        alphas[y * width + x0..y * width + x1].copy_from_slice(alpha_row);
+                    }
+                    drop(pixels);
+                    if let Some(preview) = preview {
+                        preview.tile(x0, y0, x1 - x0, &tile);
+                    }
+                    let done = finished.fetch_add(1, Ordering::Relaxed) + 1;
+                    eprint!("\rTiles: {done}/{tile_count}");
+                }
+            });
         }
-    }
-
-    if let Some(i) = hit_sphere_idx {
-        let s = world[i];
-        let p = r.at(closest_so_far);
-        let n = (p - s.center).unit();
-        return Color::new(n.x + 1.0, n.y + 1.0, n.z + 1.0) * 0.5;
-    }
+    });
+    eprintln!();
+    pixels.into_inner().unwrap()
+}
 
-    // background gradient
-    let unit_dir = r.direction.unit();
-    let t = 0.5 * (unit_dir.y + 1.0);
-    Color::new(1.0, 1.0, 1.0) * (1.0 - t) + Color::new(0.5, 0.7, 1.0) * t
+// 8-bit RGBA for `--transparent`, the alpha linear like the coverage it is
+fn encode_rgba8(pixels: &[Color], alpha: &[f64], exposure: f64, gamma: f64) -> Vec<u8> {
+    pixels
+        .iter()
+        .zip(alpha)
+        .flat_map(|(&pixel, &alpha)| {
+            let [r, g, b] = image_io::encode_rgb8(pixel, exposure, gamma);
+            [r, g, b, (255.999 * alpha.clamp(0.0, 1.0)) as u8]
+        })
+        .collect()
 }
 
-fn write_color<W: Write>(out: &mut W, pixel_color: Color) -> std::io::Result<()> {
-    // simple gamma correction (gamma 2.0)
-    let r = pixel_color.x.clamp(0.0, 0.999).sqrt();
-    let g = pixel_color.y.clamp(0.0, 0.999).sqrt();
-    let b = pixel_color.z.clamp(0.0, 0.999).sqrt();
+// at the origin looking down -z; ortho rays start on the plane through the
+// origin, so nothing behind the eye is seen either way
+fn scene_camera(aspect_ratio: f64, vfov: f64, projection: Projection, ortho_scale: f64) -> Camera {
+    let viewport_height = match projection {
+        Projection::Perspective => 2.0 * (vfov.to_radians() / 2.0).tan(),
+        Projection::Ortho => ortho_scale,
+    };
+    let viewport_width = aspect_ratio * viewport_height;
+    let focal_length = 1.0;
 
-    let ir = (255.999 * r) as i32;
-    let ig = (255.999 * g) as i32;
-    let ib = (255.999 * b) as i32;
-    writeln!(out, "{ir} {ig} {ib}")
+    Camera {
+        origin: Point3::new(0.0, 0.0, 0.0),
+        forward: Vec3::new(0.0, 0.0, -focal_length),
+        horizontal: Vec3::new(viewport_width, 0.0, 0.0),
+        vertical: Vec3::new(0.0, viewport_height, 0.0),
+        projection,
+        shutter: (0.0, 1.0),
+    }
 }
 
 fn main() -> std::io::Result<()> {
+    let args = Args::parse();
+    // at 0 the denoiser would divide by zero on every pixel
+    if !(args.denoise_strength > 0.0 && args.denoise_strength.is_finite()) {
+        Args::command()
+            .error(
+                ErrorKind::InvalidValue,
+                "--denoise-strength must be a finite number above 0",
+            )
+            .exit();
+    }
+    // gamma 0 raises to the power of infinity, blacking out the image
+    if !(args.gamma > 0.0 && args.gamma.is_finite()) {
+        Args::command()
+            .error(
+                ErrorKind::InvalidValue,
+                "--gamma must be a finite number above 0",
+            )
+            .exit();
+    }
+    if !(args.exposure >= 0.0 && args.exposure.is_finite()) {
+        Args::command()
+            .error(
+                ErrorKind::InvalidValue,
+                "--exposure must be a finite number, 0 or more",
+            )
+            .exit();
+    }
+    if args.bench {
+        bench::run();
+        return Ok(());
+    }
+
+    let normal_map = args.normal_map.as_ref().map(|path| {
+        Arc::new(
+            NormalMap::load(path, args.texture_filter).unwrap_or_else(|err| {
+                eprintln!("Failed to load {}: {err}", path.display());
+                std::process::exit(1);
+            }),
+        )
+    });
+    let mesh = args.obj.as_ref().map(|path| {
+        Mesh::load(path).unwrap_or_else(|err| {
+            eprintln!("Failed to load {err}");
+            std::process::exit(1);
+        })
+    });
+    let contents = args.scene.build(normal_map, args.seed, mesh);
+
     // Image
-    let aspect_ratio = 16.0 / 9.0;
+    let aspect_ratio = contents.aspect_ratio;
     let image_width: i32 = 400;
     let image_height: i32 = ((image_width as f64) / aspect_ratio) as i32;
-    let samples_per_pixel = 20;
+    let samples_per_pixel = args.samples.max(1);
 
     // Camera
-    let viewport_height = 2.0;
-    let viewport_width = aspect_ratio * viewport_height;
-    let focal_length = 1.0;
-
-    let origin = Point3::new(0.0, 0.0, 0.0);
-    let horizontal = Vec3::new(viewport_width, 0.0, 0.0);
-    let vertical = Vec3::new(0.0, viewport_height, 0.0);
-    let lower_left_corner = origin
-        - horizontal * 0.5
-        - vertical * 0.5
-        - Vec3::new(0.0, 0.0, focal_length);
-
-    // World: ground + three spheres
-    let world = vec![
-        Sphere {
-            center: Point3::new(0.0, 0.0, -1.0),
-            radius: 0.5,
-        },
-        Sphere {
-            center: Point3::new(0.0, -100.5, -1.0),
-            radius: 100.0,
-        },
-        Sphere {
-            center: Point3::new(1.0, 0.0, -1.5),
-            radius: 0.5,
-        },
-        Sphere {
-            center: Point3::new(-1.0, 0.0, -1.5),
-            radius: 0.5,
-        },
-    ];
-
-    let file = File::create("image.ppm")?;
-    let mut writer = BufWriter::new(file);
-
-    writeln!(writer, "P3")?;
-    writeln!(writer, "{image_width} {image_height}")?;
-    writeln!(writer, "255")?;
-
-    let mut rng = rand::thread_rng();
-
-    for j in (0..image_height).rev() {
-        for i in 0..image_width {
-            let mut pixel_color = Color::new(0.0, 0.0, 0.0);
-
-            for _ in 0..samples_per_pixel {
-                let u = (i as f64 + rng.gen::<f64>()) / (image_width - 1) as f64;
-                let v = (j as f64 + rng.gen::<f64>()) / (image_height - 1) as f64;
-
-                let r = Ray::new(
-                    origin,
-                    lower_left_corner + horizontal * u + vertical * v - origin,
+    let camera = scene_camera(
+        aspect_ratio,
+        contents.vfov,
+        args.projection,
+        args.ortho_scale,
+    );
+
+    let background = match &args.env {
+        Some(path) => Background::Env(EnvMap::load(path, args.gamma).unwrap_or_else(|err| {
+            eprintln!("Failed to load {}: {err}", path.display());
+            std::process::exit(1);
+        })),
+        None => Background::Sky(args.sky.unwrap_or(contents.sky)),
+    };
+
+    let mut scene = Scene {
+        world: contents.world,
+        lights: contents.lights,
+        area_lights: contents.area_lights,
+        background,
+        sampler: args.sampler,
+        filter: args.filter,
+        rr_start_depth: args.rr_start_depth,
+        spectral: args.spectral,
+        seed: args.seed,
+        transparent: args.transparent,
+        tile_order: args.tile_order,
+    };
+
+    let (width, height) = (image_width as usize, image_height as usize);
+    let preview = args
+        .preview
+        .then(|| Framebuffer::new(width, height, args.exposure, args.gamma));
+    let mut output = || -> std::io::Result<()> {
+        if let Some(path) = &args.camera_path {
+            let camera_path = CameraPath::load(path).unwrap_or_else(|err| {
+                eprintln!("Failed to load {err}");
+                std::process::exit(1);
+            });
+            std::fs::create_dir_all(&args.out_dir)?;
+            for frame in 0..args.frames {
+                let pose = camera_path.pose(frame, args.frames, contents.vfov);
+                let camera =
+                    scene_camera(aspect_ratio, pose.fov, args.projection, args.ortho_scale)
+                        .looking_at(pose.position, pose.look_at);
+                // reproducible like a still, but with fresh noise every frame, so
+                // it doesn't look stuck to the screen as the view moves
+                scene.seed = args
+                    .seed
+                    .wrapping_add((frame as u64 + 1).wrapping_mul(0x9e37_79b9_7f4a_7c15));
+                let (mut pixels, alpha) = render(
+                    &camera,
+                    &scene,
+                    width,
+                    height,
+                    samples_per_pixel,
+                    preview.as_ref(),
                 );
+                if args.denoise {
+                    let guides = aov::guides(&camera, &scene.world, width, height);
+                    denoise::denoise(&mut pixels, &guides, width, height, args.denoise_strength);
+                }
+                if let Some(threshold) = args.bloom_threshold {
+                    bloom::bloom(&mut pixels, width, height, threshold, args.bloom_radius);
+                }
+                if let Some(preview) = &preview {
+                    preview.show(&pixels);
+                }
+                let out = args.out_dir.join(format!("frame_{:05}.png", frame + 1));
+                if args.transparent {
+                    let data = encode_rgba8(&pixels, &alpha, args.exposure, args.gamma);
+                    image_io::write_png_rgba8(&out, width as u32, height as u32, &data)
+                } else {
+                    let data: Vec<u8> = pixels
+                        .iter()
+                        .flat_map(|&pixel| image_io::encode_rgb8(pixel, args.exposure, args.gamma))
+                        .collect();
+                    image_io::write_png_rgb8(&out, width as u32, height as u32, &data)
+                }
+                .map_err(std::io::Error::other)?;
+                println!("Wrote {} ({}/{})", out.display(), frame + 1, args.frames);
+            }
+            return Ok(());
+        }
 
-                pixel_color = pixel_color + ray_color(&r, &world);
+        let (mut pixels, alpha) = match args.stereo {
+            None => render(
+                &camera,
+                &scene,
+                width,
+                height,
+                samples_per_pixel,
+                preview.as_ref(),
+            ),
+            // each eye is denoised with its own guides, before the two are mixed
+            Some(Stereo::Anaglyph) => {
+                let [left, right] =
+                    stereo::eyes(&camera, args.interocular, args.convergence).map(|eye| {
+                        let (mut pixels, _) = render(
+                            &eye,
+                            &scene,
+                            width,
+                            height,
+                            samples_per_pixel,
+                            preview.as_ref(),
+                        );
+                        if args.denoise {
+                            let guides = aov::guides(&eye, &scene.world, width, height);
+                            denoise::denoise(
+                                &mut pixels,
+                                &guides,
+                                width,
+                                height,
+                                args.denoise_strength,
+                            );
+                        }
+                        pixels
+                    });
+                (stereo::anaglyph(&left, &right), Vec::new())
             }
+        };
+        // the passes are from the centre view, between the eyes
+        let guides = if (args.denoise && args.stereo.is_none()) || !args.aov.is_empty() {
+            aov::guides(&camera, &scene.world, width, height)
+        } else {
+            Vec::new()
+        };
+        if args.denoise && args.stereo.is_none() {
+            denoise::denoise(&mut pixels, &guides, width, height, args.denoise_strength);
+        }
+        if let Some(threshold) = args.bloom_threshold {
+            bloom::bloom(&mut pixels, width, height, threshold, args.bloom_radius);
+        }
+        if let Some(preview) = &preview {
+            preview.show(&pixels);
+        }
 
-            let scale = 1.0 / samples_per_pixel as f64;
-            pixel_color = pixel_color * scale;
+        // PPM has no alpha channel
+        if args.transparent {
+            let data = encode_rgba8(&pixels, &alpha, args.exposure, args.gamma);
+            image_io::write_png_rgba8("image.png", width as u32, height as u32, &data)
+                .map_err(std::io::Error::other)?;
+            println!("Wrote image.png");
+        } else {
+            image_io::write_ppm(
+                "image.ppm",
+                image_width as u32,
+                image_height as u32,
+                &pixels,
+                args.exposure,
+                args.gamma,
+            )?;
+            println!("Wrote image.ppm");
+        }
 
-            write_color(&mut writer, pixel_color)?;
+        for &aov in &args.aov {
+            let path = format!("image.{}.png", aov.name());
+            let data = aov::render(aov, &guides);
+            image_io::write_png_rgb8(&path, width as u32, height as u32, &data)
+                .map_err(std::io::Error::other)?;
+            println!("Wrote {path}");
         }
+        Ok(())
+    };
+    match &preview {
+        Some(framebuffer) => preview::run(framebuffer, output),
+        None => output(),
     }
-
-    println!("Wrote image.ppm");
-    Ok(())
 }
-
-
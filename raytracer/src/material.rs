@@ -0,0 +1,113 @@
+use std::sync::Arc;
+
+use math::{Color, DVec3 as Vec3};
+use rand::Rng;
+
+use crate::hittable::HitRecord;
+use crate::sampling::random_in_unit_sphere;
+use crate::texture::{NormalMap, Texture};
+
+// the sodium d line, where glass catalogues quote a single index; paths that
+// don't carry a wavelength refract as if at this one
+pub const REFERENCE_WAVELENGTH: f64 = 587.6;
+
+// index of refraction a + b / wavelength^2, with the wavelength in micrometres;
+// b is how strongly the glass disperses
+#[derive(Clone, Copy)]
+pub struct Cauchy {
+    pub a: f64,
+    pub b: f64,
+}
+
+impl Cauchy {
+    // ordinary window and lens glass, barely dispersive
+    pub const CROWN: Cauchy = Cauchy {
+        a: 1.5046,
+        b: 0.0042,
+    };
+
+    // a dense flint, dispersive enough for clearly visible fringes
+    pub const FLINT: Cauchy = Cauchy {
+        a: 1.728,
+        b: 0.01342,
+    };
+
+    // `wavelength` in nanometres
+    pub fn ior(self, wavelength: f64) -> f64 {
+        let micrometres = wavelength * 1e-3;
+        self.a + self.b / (micrometres * micrometres)
+    }
+}
+
+#[derive(Clone)]
+pub enum Material {
+    // matte, reflecting `albedo` of the incoming light evenly in all directions;
+    // a normal map makes the flat surface shade as if bumpy
+    Lambertian {
+        albedo: Texture,
+        normal_map: Option<Arc<NormalMap>>,
+    },
+    // glows with `radiance` and reflects nothing; bounces find it by chance,
+    // or aimed at it through a matching `AreaLight`
+    Emissive {
+        radiance: Color,
+    },
+    // a mirror tinted by `albedo`, its reflections blurred by up to `fuzz`
+    Metal {
+        albedo: Color,
+        fuzz: f64,
+    },
+    // clear glass: every ray goes on, either reflected or refracted
+    Dielectric {
+        ior: Cauchy,
+    },
+}
+
+impl Material {
+    // the normal lighting should use at the hit, bumped by any normal map
+    pub fn shading_normal(&self, hit: &HitRecord) -> Vec3 {
+        match self {
+            Material::Lambertian {
+                normal_map: Some(map),
+                ..
+            } => map.perturb(hit),
+            Material::Lambertian { .. }
+            | Material::Emissive { .. }
+            | Material::Metal { .. }
+            | Material::Dielectric { .. } => hit.normal,
+        }
+    }
+}
+
+// the mirror direction off a metal, pushed a random way within `fuzz`; None
+// where that sends it into the surface, which then absorbs it
+pub fn metal_bounce(direction: Vec3, normal: Vec3, fuzz: f64, rng: &mut impl Rng) -> Option<Vec3> {
+    let d = direction.unit();
+    let reflected = d - normal * (2.0 * d.dot(&normal)) + random_in_unit_sphere(rng) * fuzz;
+    (reflected.dot(&normal) > 0.0).then_some(reflected)
+}
+
+// the way on from glass of index `ior`: reflected or refracted at random, with
+// Schlick's approximation of the Fresnel reflectance as the odds, and always
+// reflected past the critical angle
+pub fn dielectric_bounce(direction: Vec3, normal: Vec3, ior: f64, rng: &mut impl Rng) -> Vec3 {
+    let d = direction.unit();
+    // the normal points out, so a ray along it is leaving the glass
+    let entering = d.dot(&normal) < 0.0;
+    let (n, eta) = if entering {
+        (normal, 1.0 / ior)
+    } else {
+        (-normal, ior)
+    };
+    let cos = (-d.dot(&n)).min(1.0);
+    let sin = (1.0 - cos * cos).sqrt();
+    let r0 = ((1.0 - eta) / (1.0 + eta)).powi(2);
+    let reflectance = r0 + (1.0 - r0) * (1.0 - cos).powi(5);
+    if eta * sin > 1.0 || rng.gen::<f64>() < reflectance {
+        d - n * (2.0 * d.dot(&n))
+    } else {
+        let perpendicular = (d + n * cos) * eta;
+        let parallel = n * -(1.0 - perpendicular.length_squared()).abs().sqrt();
+        perpendicular + parallel
+    }
+}
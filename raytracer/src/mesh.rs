@@ -0,0 +1,239 @@
+use std::path::Path;
+
+use math::{DVec3 as Vec3, Point3};
+
+use crate::disk::facing;
+use crate::hittable::{HitRecord, Hittable};
+use crate::material::Material;
+use crate::ray::Ray;
+
+// a single face; with `normals` the shading normal is blended from them across
+// the face, so a mesh approximating a curved surface looks curved, and
+// without them it's the flat face normal; uv is the barycentric weight of the
+// second and third vertex
+pub struct Triangle {
+    pub vertices: [Point3; 3],
+    pub normals: Option<[Vec3; 3]>,
+    pub material: Material,
+}
+
+impl Hittable for Triangle {
+    // Möller–Trumbore: solves for t and the barycentric coordinates together
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        let [v0, v1, v2] = self.vertices;
+        let (e1, e2) = (v1 - v0, v2 - v0);
+        let p = r.direction.cross(&e2);
+        let det = e1.dot(&p);
+        // parallel to the face, or the face has no area
+        if det.abs() < 1e-12 {
+            return None;
+        }
+        let inv_det = 1.0 / det;
+        let s = r.origin - v0;
+        let u = s.dot(&p) * inv_det;
+        if !(0.0..=1.0).contains(&u) {
+            return None;
+        }
+        let q = s.cross(&e1);
+        let v = r.direction.dot(&q) * inv_det;
+        if v < 0.0 || u + v > 1.0 {
+            return None;
+        }
+        let t = e2.dot(&q) * inv_det;
+        if t <= t_min || t >= t_max {
+            return None;
+        }
+
+        let geometric = facing(e1.cross(&e2).unit(), r);
+        let normal = match self.normals {
+            Some([n0, n1, n2]) => {
+                let n = (n0 * (1.0 - u - v) + n1 * u + n2 * v).unit();
+                // on the side the ray sees, like the face itself
+                if n.dot(&geometric) < 0.0 {
+                    -n
+                } else {
+                    n
+                }
+            }
+            None => geometric,
+        };
+        let mut hit = HitRecord::new(t, r.at(t), normal, self.material.clone());
+        hit.uv = (u, v);
+        hit.tangent = (e1 - normal * e1.dot(&normal)).unit();
+        Some(hit)
+    }
+}
+
+// indexed triangles, as read from an OBJ file
+pub struct Mesh {
+    positions: Vec<Point3>,
+    normals: Vec<Vec3>,
+    // each corner's position and, where the file gives one, its normal
+    faces: Vec<[(usize, Option<usize>); 3]>,
+}
+
+impl Mesh {
+    // the `v`, `vn` and `f` lines of an OBJ file; polygons are split into fans
+    // of triangles, and everything else (texture coordinates, groups,
+    // materials) is skipped
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text =
+            std::fs::read_to_string(path).map_err(|err| format!("{}: {err}", path.display()))?;
+        let mut mesh = Mesh {
+            positions: Vec::new(),
+            normals: Vec::new(),
+            faces: Vec::new(),
+        };
+        for (number, line) in text.lines().enumerate() {
+            mesh.parse_line(line)
+                .map_err(|err| format!("{}:{}: {err}", path.display(), number + 1))?;
+        }
+        if mesh.faces.is_empty() {
+            return Err(format!("{}: no faces", path.display()));
+        }
+        Ok(mesh)
+    }
+
+    fn parse_line(&mut self, line: &str) -> Result<(), String> {
+        let mut fields = line.split_whitespace();
+        match fields.next() {
+            Some("v") => self.positions.push(parse_vector(fields)?),
+            Some("vn") => self.normals.push(parse_vector(fields)?.unit()),
+            Some("f") => {
+                let corners = fields
+                    .map(|corner| self.parse_corner(corner))
+                    .collect::<Result<Vec<_>, _>>()?;
+                if corners.len() < 3 {
+                    return Err("a face needs at least three corners".to_string());
+                }
+                for i in 1..corners.len() - 1 {
+                    self.faces.push([corners[0], corners[i], corners[i + 1]]);
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    // `v`, `v/vt`, `v//vn` or `v/vt/vn`, each index from 1 or, when negative,
+    // counting back from the latest
+    fn parse_corner(&self, corner: &str) -> Result<(usize, Option<usize>), String> {
+        let mut indices = corner.split('/');
+        let position = resolve_index(indices.next().unwrap_or(""), self.positions.len())?;
+        let normal = match indices.nth(1) {
+            Some(index) if !index.is_empty() => Some(resolve_index(index, self.normals.len())?),
+            _ => None,
+        };
+        Ok((position, normal))
+    }
+
+    // a sphere of radius 1 around the origin, `rings` faces from pole to pole
+    // and `segments` around, with the exact sphere normals at its vertices
+    pub fn sphere(rings: usize, segments: usize) -> Self {
+        let mut positions = Vec::new();
+        for ring in 0..=rings {
+            let theta = std::f64::consts::PI * ring as f64 / rings as f64;
+            for segment in 0..segments {
+                let phi = std::f64::consts::TAU * segment as f64 / segments as f64;
+                positions.push(Point3::new(
+                    theta.sin() * phi.cos(),
+                    theta.cos(),
+                    -theta.sin() * phi.sin(),
+                ));
+            }
+        }
+        let index = |ring: usize, segment: usize| {
+            let i = ring * segments + segment % segments;
+            (i, Some(i))
+        };
+        let mut faces = Vec::new();
+        for ring in 0..rings {
+            for segment in 0..segments {
+                let (a, b) = (index(ring, segment), index(ring, segment + 1));
+                let (c, d) = (index(ring + 1, segment), index(ring + 1, segment + 1));
+                // the poles' rows of vertices all sit in one spot, so one
+                // triangle of each quad there would have no area
+                if ring > 0 {
+                    faces.push([a, c, b]);
+                }
+                if ring + 1 < rings {
+                    faces.push([b, c, d]);
+                }
+            }
+        }
+        Mesh {
+            normals: positions.clone(),
+            positions,
+            faces,
+        }
+    }
+
+    // the same faces shaded flat
+    pub fn faceted(self) -> Self {
+        Mesh {
+            normals: Vec::new(),
+            faces: self
+                .faces
+                .into_iter()
+                .map(|face| face.map(|(position, _)| (position, None)))
+                .collect(),
+            ..self
+        }
+    }
+
+    // the triangles, scaled uniformly so the longest side of their bounding box
+    // is `size`, and moved to stand on `base`, centred over it
+    pub fn triangles(&self, base: Point3, size: f64, material: Material) -> Vec<Box<dyn Hittable>> {
+        let far = Vec3::new(f64::INFINITY, f64::INFINITY, f64::INFINITY);
+        let (min, max) = self.positions.iter().fold((far, -far), |(min, max), p| {
+            (
+                Vec3::new(min.x.min(p.x), min.y.min(p.y), min.z.min(p.z)),
+                Vec3::new(max.x.max(p.x), max.y.max(p.y), max.z.max(p.z)),
+            )
+        });
+        let extent = max - min;
+        let scale = size / extent.x.max(extent.y).max(extent.z).max(1e-12);
+        let anchor = Point3::new((min.x + max.x) / 2.0, min.y, (min.z + max.z) / 2.0);
+        let place = |p: Point3| base + (p - anchor) * scale;
+        self.faces
+            .iter()
+            .map(|face| {
+                let normals = match face.map(|(_, normal)| normal) {
+                    [Some(a), Some(b), Some(c)] => {
+                        Some([self.normals[a], self.normals[b], self.normals[c]])
+                    }
+                    // partly given normals can't be blended, so the face is flat
+                    _ => None,
+                };
+                Box::new(Triangle {
+                    vertices: face.map(|(position, _)| place(self.positions[position])),
+                    normals,
+                    material: material.clone(),
+                }) as Box<dyn Hittable>
+            })
+            .collect()
+    }
+}
+
+fn parse_vector<'a>(mut fields: impl Iterator<Item = &'a str>) -> Result<Vec3, String> {
+    let mut component = || -> Result<f64, String> {
+        let field = fields.next().ok_or("expected three coordinates")?;
+        field
+            .parse()
+            .map_err(|_| format!("invalid coordinate `{field}`"))
+    };
+    Ok(Vec3::new(component()?, component()?, component()?))
+}
+
+// a zero-based index into a list of `len` items from an OBJ index
+fn resolve_index(index: &str, len: usize) -> Result<usize, String> {
+    let n: i64 = index
+        .parse()
+        .map_err(|_| format!("invalid index `{index}`"))?;
+    let resolved = if n < 0 { len as i64 + n } else { n - 1 };
+    if (0..len as i64).contains(&resolved) {
+        Ok(resolved as usize)
+    } else {
+        Err(format!("index {n} is out of range"))
+    }
+}
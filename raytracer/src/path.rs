@@ -0,0 +1,182 @@
+// `--camera-path`: keyframed camera moves, read from JSON like
+//
+//   {
+//     "interpolation": "catmull-rom",
+//     "keyframes": [
+//       { "position": [0, 0, 0], "look_at": [0, 0, -1], "fov": 40 },
+//       { "position": [1, 0.5, 0.5], "look_at": [0, 0, -1] }
+//     ]
+//   }
+//
+// the keyframes are spread evenly over the frames, first to last; a keyframe
+// without `fov` uses the scene's, and none may look at its own position
+use std::path::Path;
+
+use math::{DVec3 as Vec3, Point3};
+use serde::Deserialize;
+
+#[derive(Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Interpolation {
+    // straight lines between keyframes, turning sharply at each
+    #[default]
+    Linear,
+    // a curve through every keyframe that leaves each one heading for the
+    // next but one, so the moves flow into each other
+    CatmullRom,
+}
+
+#[derive(Deserialize)]
+pub struct Keyframe {
+    position: [f64; 3],
+    look_at: [f64; 3],
+    fov: Option<f64>,
+}
+
+#[derive(Deserialize)]
+pub struct CameraPath {
+    #[serde(default)]
+    interpolation: Interpolation,
+    keyframes: Vec<Keyframe>,
+}
+
+// where the camera is, what it looks at and its vertical field of view in
+// degrees, at one frame
+pub struct Pose {
+    pub position: Point3,
+    pub look_at: Point3,
+    pub fov: f64,
+}
+
+impl CameraPath {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text =
+            std::fs::read_to_string(path).map_err(|err| format!("{}: {err}", path.display()))?;
+        let camera_path: CameraPath =
+            serde_json::from_str(&text).map_err(|err| format!("{}: {err}", path.display()))?;
+        if camera_path.keyframes.is_empty() {
+            return Err(format!("{}: no keyframes", path.display()));
+        }
+        if let Some(i) = camera_path
+            .keyframes
+            .iter()
+            .position(|key| key.position == key.look_at)
+        {
+            return Err(format!(
+                "{}: keyframe {i} looks at its own position",
+                path.display()
+            ));
+        }
+        Ok(camera_path)
+    }
+
+    // frame `frame` of `frames`, any past the last holding on the last
+    // keyframe; `scene_fov` stands in for keyframes without one
+    pub fn pose(&self, frame: u32, frames: u32, scene_fov: f64) -> Pose {
+        let last = self.keyframes.len() - 1;
+        let s = if frames > 1 {
+            (frame as f64 / (frames - 1) as f64).min(1.0) * last as f64
+        } else {
+            0.0
+        };
+        let segment = (s.floor() as usize).min(last.saturating_sub(1));
+        let t = s - segment as f64;
+        // the ends repeat, so the curve starts and stops on them
+        let keys = [-1, 0, 1, 2].map(|offset| {
+            &self.keyframes[(segment as isize + offset).clamp(0, last as isize) as usize]
+        });
+        let weights = match self.interpolation {
+            Interpolation::Linear => [0.0, 1.0 - t, t, 0.0],
+            Interpolation::CatmullRom => catmull_rom_weights(t),
+        };
+        let blend = |value: fn(&Keyframe) -> [f64; 3]| {
+            keys.iter()
+                .zip(weights)
+                .fold(Vec3::ZERO, |sum, (key, weight)| {
+                    let [x, y, z] = value(key);
+                    sum + Vec3::new(x, y, z) * weight
+                })
+        };
+        let position = blend(|key| key.position);
+        let look_at = blend(|key| key.look_at);
+        let fov = keys
+            .iter()
+            .zip(weights)
+            .map(|(key, weight)| key.fov.unwrap_or(scene_fov) * weight)
+            .sum();
+        Pose {
+            position,
+            look_at,
+            fov,
+        }
+    }
+}
+
+// how much each of four keyframes counts at `t` between the middle two, on
+// the uniform Catmull-Rom spline through them
+fn catmull_rom_weights(t: f64) -> [f64; 4] {
+    let (t2, t3) = (t * t, t * t * t);
+    [
+        0.5 * (-t + 2.0 * t2 - t3),
+        0.5 * (2.0 - 5.0 * t2 + 3.0 * t3),
+        0.5 * (t + 4.0 * t2 - 3.0 * t3),
+        0.5 * (-t2 + t3),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(x: f64, fov: Option<f64>) -> Keyframe {
+        Keyframe {
+            position: [x, 0.0, 0.0],
+            look_at: [x, 0.0, -1.0],
+            fov,
+        }
+    }
+
+    fn path(interpolation: Interpolation) -> CameraPath {
+        CameraPath {
+            interpolation,
+            keyframes: vec![key(0.0, Some(30.0)), key(1.0, None), key(4.0, Some(60.0))],
+        }
+    }
+
+    #[test]
+    fn poses_pass_through_the_keyframes() {
+        for interpolation in [Interpolation::Linear, Interpolation::CatmullRom] {
+            let path = path(interpolation);
+            for (frame, x, fov) in [(0, 0.0, 30.0), (4, 1.0, 40.0), (8, 4.0, 60.0)] {
+                let pose = path.pose(frame, 9, 40.0);
+                assert!((pose.position - Vec3::new(x, 0.0, 0.0)).length() < 1e-9);
+                assert!((pose.look_at - Vec3::new(x, 0.0, -1.0)).length() < 1e-9);
+                assert!((pose.fov - fov).abs() < 1e-9);
+            }
+        }
+    }
+
+    #[test]
+    fn linear_poses_go_straight_between_keyframes() {
+        let pose = path(Interpolation::Linear).pose(6, 9, 40.0);
+        assert!((pose.position.x - 2.5).abs() < 1e-9);
+        assert!((pose.fov - 50.0).abs() < 1e-9);
+        // the curve leaves the first keyframe slowly, to speed up for the
+        // longer move after the second
+        let pose = path(Interpolation::CatmullRom).pose(2, 9, 40.0);
+        assert!((pose.position.x - 0.3125).abs() < 1e-9);
+    }
+
+    #[test]
+    fn poses_clamp_to_the_ends() {
+        let path = path(Interpolation::CatmullRom);
+        assert!((path.pose(20, 9, 40.0).position.x - 4.0).abs() < 1e-9);
+        // a single frame is the first keyframe
+        assert_eq!(path.pose(0, 1, 40.0).position.x, 0.0);
+        let still = CameraPath {
+            interpolation: Interpolation::Linear,
+            keyframes: vec![key(2.0, None)],
+        };
+        assert_eq!(still.pose(5, 9, 40.0).position.x, 2.0);
+    }
+}
@@ -0,0 +1,68 @@
+use math::{DVec3 as Vec3, Point3};
+use rand::Rng;
+
+// a distribution of directions to bounce in: `value` is its density over solid
+// angle, `generate` draws from it
+pub trait Pdf {
+    fn value(&self, direction: Vec3) -> f64;
+    fn generate(&self, rng: &mut impl Rng) -> Vec3;
+}
+
+// the area of a glowing parallelogram, registered apart from its geometry so
+// bounces can be aimed at it; the light itself comes from an `Emissive`
+// surface in the same place
+#[derive(Clone, Copy)]
+pub struct AreaLight {
+    pub corner: Point3,
+    pub u: Vec3,
+    pub v: Vec3,
+}
+
+impl AreaLight {
+    // density over solid angle seen from `origin` of leaving along
+    // `direction` through a uniformly picked point; zero if it misses
+    fn density(&self, origin: Point3, direction: Vec3) -> f64 {
+        let n = self.u.cross(&self.v);
+        let area = n.length();
+        let normal = n / area;
+        let denom = normal.dot(&direction);
+        if denom.abs() < 1e-12 {
+            return 0.0;
+        }
+        let t = (self.corner - origin).dot(&normal) / denom;
+        if t <= 0.001 {
+            return 0.0;
+        }
+        let d = origin + direction * t - self.corner;
+        let w = n / n.dot(&n);
+        let (a, b) = (w.dot(&d.cross(&self.v)), w.dot(&self.u.cross(&d)));
+        if !(0.0..=1.0).contains(&a) || !(0.0..=1.0).contains(&b) {
+            return 0.0;
+        }
+        let distance_squared = t * t * direction.length_squared();
+        let cos = denom.abs() / direction.length();
+        distance_squared / (cos * area)
+    }
+}
+
+// towards a uniformly picked point on one of the area lights, from `origin`
+pub struct LightPdf<'a> {
+    pub origin: Point3,
+    pub lights: &'a [AreaLight],
+}
+
+impl Pdf for LightPdf<'_> {
+    fn value(&self, direction: Vec3) -> f64 {
+        let total: f64 = self
+            .lights
+            .iter()
+            .map(|light| light.density(self.origin, direction))
+            .sum();
+        total / self.lights.len() as f64
+    }
+
+    fn generate(&self, rng: &mut impl Rng) -> Vec3 {
+        let light = &self.lights[rng.gen_range(0..self.lights.len())];
+        light.corner + light.u * rng.gen::<f64>() + light.v * rng.gen::<f64>() - self.origin
+    }
+}
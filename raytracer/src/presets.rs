@@ -0,0 +1,506 @@
+use std::sync::Arc;
+
+use clap::ValueEnum;
+use math::{Color, DVec3 as Vec3, Point3};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use crate::background::Sky;
+use crate::csg::{Csg, CsgOp};
+use crate::cuboid::Cuboid;
+use crate::cylinder::Cylinder;
+use crate::disk::Disk;
+use crate::hittable::Hittable;
+use crate::instance::{grid, RotateY, Translate};
+use crate::light::Light;
+use crate::material::{Cauchy, Material};
+use crate::mesh::Mesh;
+use crate::pdf::AreaLight;
+use crate::quad::Quad;
+use crate::sphere::{MovingSphere, Sphere};
+use crate::texture::{NormalMap, Texture};
+
+// built-in scenes for `--scene`; each is laid out for a camera at the origin
+// looking down -z
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+pub enum Preset {
+    // spheres, CSG, a disk and a post on a ground plane under the sky
+    Showcase,
+    // the classic enclosed test scene lit by one ceiling lamp
+    Cornell,
+    // the Cornell box with a flint glass ball for the short block, which
+    // `--spectral` splits the lamp's light through
+    Glass,
+    // a field of small spheres on the ground, as a heavy scene to time
+    Grid,
+    // the cover of Ray Tracing in One Weekend: small matte, metal and glass
+    // spheres scattered at random around three large ones, laid out by `--seed`
+    Random,
+    // a `--obj` model on a checkered floor; without one, a low-poly sphere
+    // shaded flat beside the same sphere shaded smooth from its vertex normals
+    Mesh,
+}
+
+// a scene's geometry and lights, and how it wants to be framed and lit
+pub struct Contents {
+    pub world: Vec<Box<dyn Hittable>>,
+    pub lights: Vec<Light>,
+    // emissive quads that diffuse bounces should aim for
+    pub area_lights: Vec<AreaLight>,
+    // used unless `--sky` or `--env` says otherwise
+    pub sky: Sky,
+    pub aspect_ratio: f64,
+    // vertical field of view in degrees, for the perspective camera
+    pub vfov: f64,
+}
+
+impl Preset {
+    // `normal_map` goes on the showcase's red sphere; `seed` lays out the
+    // random scene; `mesh` is the mesh scene's model
+    pub fn build(
+        self,
+        normal_map: Option<Arc<NormalMap>>,
+        seed: u64,
+        mesh: Option<Mesh>,
+    ) -> Contents {
+        match self {
+            Preset::Showcase => showcase(normal_map),
+            Preset::Cornell => cornell(false),
+            Preset::Glass => cornell(true),
+            Preset::Grid => sphere_grid(),
+            Preset::Random => random(seed),
+            Preset::Mesh => mesh_scene(mesh),
+        }
+    }
+}
+
+fn matte(albedo: Color) -> Material {
+    Material::Lambertian {
+        albedo: Texture::Solid(albedo),
+        normal_map: None,
+    }
+}
+
+fn showcase(normal_map: Option<Arc<NormalMap>>) -> Contents {
+    // ground + three spheres (the white one built with CSG: flattened
+    // underneath, its front corner cut away and a dimple drilled in its side),
+    // a small one falling in front, a mat under the red sphere and a post
+    // beside it
+    let world: Vec<Box<dyn Hittable>> = vec![
+        Box::new(Sphere {
+            center: Point3::new(0.0, 0.0, -1.0),
+            radius: 0.5,
+            material: Material::Lambertian {
+                albedo: Texture::Solid(Color::new(0.7, 0.3, 0.3)),
+                normal_map,
+            },
+        }),
+        Box::new(Sphere {
+            center: Point3::new(0.0, -100.5, -1.0),
+            radius: 100.0,
+            material: Material::Lambertian {
+                albedo: Texture::Solid(Color::new(0.5, 0.5, 0.45)),
+                normal_map: None,
+            },
+        }),
+        Box::new(Sphere {
+            center: Point3::new(1.0, 0.0, -1.5),
+            radius: 0.5,
+            material: Material::Lambertian {
+                albedo: Texture::Solid(Color::new(0.3, 0.5, 0.8)),
+                normal_map: None,
+            },
+        }),
+        Box::new(Csg {
+            op: CsgOp::Difference,
+            a: Box::new(Csg {
+                op: CsgOp::Intersection,
+                a: Box::new(Sphere {
+                    center: Point3::new(-1.0, 0.0, -1.5),
+                    radius: 0.5,
+                    material: Material::Lambertian {
+                        albedo: Texture::Solid(Color::new(0.8, 0.8, 0.8)),
+                        normal_map: None,
+                    },
+                }),
+                b: Box::new(Cuboid {
+                    min: Point3::new(-1.6, -0.45, -2.1),
+                    max: Point3::new(-0.4, 0.6, -0.9),
+                    material: Material::Lambertian {
+                        albedo: Texture::Solid(Color::new(0.8, 0.8, 0.8)),
+                        normal_map: None,
+                    },
+                }),
+            }),
+            b: Box::new(Csg {
+                op: CsgOp::Union,
+                a: Box::new(Cuboid {
+                    min: Point3::new(-1.0, 0.0, -1.5),
+                    max: Point3::new(-0.4, 0.6, -0.9),
+                    material: Material::Lambertian {
+                        albedo: Texture::Solid(Color::new(0.9, 0.7, 0.3)),
+                        normal_map: None,
+                    },
+                }),
+                b: Box::new(Sphere {
+                    center: Point3::new(-1.5, 0.05, -1.25),
+                    radius: 0.18,
+                    material: Material::Lambertian {
+                        albedo: Texture::Solid(Color::new(0.9, 0.7, 0.3)),
+                        normal_map: None,
+                    },
+                }),
+            }),
+        }),
+        Box::new(MovingSphere {
+            center0: Point3::new(0.6, 0.2, -0.6),
+            center1: Point3::new(0.6, -0.05, -0.6),
+            time0: 0.0,
+            time1: 1.0,
+            radius: 0.12,
+            material: Material::Lambertian {
+                albedo: Texture::Solid(Color::new(0.9, 0.6, 0.1)),
+                normal_map: None,
+            },
+        }),
+        Box::new(Disk {
+            center: Point3::new(0.0, -0.499, -1.0),
+            normal: Vec3::new(0.0, 1.0, 0.0),
+            radius: 0.7,
+            material: Material::Lambertian {
+                albedo: Texture::Solid(Color::new(0.2, 0.35, 0.25)),
+                normal_map: None,
+            },
+        }),
+        Box::new(Cylinder {
+            base: Point3::new(-0.45, -0.5, -0.7),
+            axis: Vec3::new(0.0, 1.0, 0.0),
+            radius: 0.08,
+            height: 0.35,
+            caps: true,
+            material: Material::Lambertian {
+                albedo: Texture::Solid(Color::new(0.8, 0.75, 0.6)),
+                normal_map: None,
+            },
+        }),
+    ];
+
+    // a warm key light up and to the left, and a small soft fill on the right
+    let lights = vec![
+        Light::Point {
+            position: Point3::new(-2.0, 3.0, 0.5),
+            intensity: Color::new(30.0, 27.0, 22.0),
+        },
+        Light::Sphere {
+            center: Point3::new(2.5, 1.5, 0.0),
+            radius: 0.5,
+            intensity: Color::new(8.0, 9.0, 12.0),
+        },
+    ];
+
+    Contents {
+        world,
+        lights,
+        area_lights: Vec::new(),
+        // the original white-to-blue sky
+        sky: Sky::Gradient {
+            bottom: Color::new(1.0, 1.0, 1.0),
+            top: Color::new(0.5, 0.7, 1.0),
+        },
+        aspect_ratio: 16.0 / 9.0,
+        // a viewport two units tall at the focal length of one
+        vfov: 90.0,
+    }
+}
+
+// spheres across the grid, each way
+const GRID_SIZE: usize = 12;
+
+// the showcase's sky over a square field of spheres shading from red to blue
+// across it and towards green along it, each on its own tile of a checkered
+// floor, seen from above its near edge and lit by one key light
+fn sphere_grid() -> Contents {
+    let (radius, ground) = (0.18, -1.5);
+    let mut world = grid(GRID_SIZE, GRID_SIZE, 0.5, |i, k| {
+        let (a, b) = (
+            i as f64 / (GRID_SIZE - 1) as f64,
+            k as f64 / (GRID_SIZE - 1) as f64,
+        );
+        Box::new(Sphere {
+            center: Point3::new(0.0, ground + radius, -5.5),
+            radius,
+            material: matte(Color::new(
+                0.1 + 0.7 * (1.0 - a),
+                0.1 + 0.6 * b,
+                0.1 + 0.7 * a,
+            )),
+        })
+    });
+    // 100 units square, so 200 tiles across make them as wide as the spacing
+    world.push(Box::new(Quad {
+        corner: Point3::new(-50.0, ground, 50.0),
+        u: Vec3::new(100.0, 0.0, 0.0),
+        v: Vec3::new(0.0, 0.0, -100.0),
+        material: Material::Lambertian {
+            albedo: Texture::Checker {
+                scale: 200.0,
+                even: Color::new(0.55, 0.55, 0.5),
+                odd: Color::new(0.3, 0.3, 0.28),
+            },
+            normal_map: None,
+        },
+    }));
+
+    Contents {
+        world,
+        lights: vec![Light::Point {
+            position: Point3::new(-3.0, 2.0, -2.0),
+            intensity: Color::new(40.0, 36.0, 30.0),
+        }],
+        area_lights: Vec::new(),
+        sky: Sky::Gradient {
+            bottom: Color::new(1.0, 1.0, 1.0),
+            top: Color::new(0.5, 0.7, 1.0),
+        },
+        aspect_ratio: 16.0 / 9.0,
+        vfov: 60.0,
+    }
+}
+
+// where the cover's camera stands, looking towards the origin; the scene is
+// built around the origin as in the book, then moved so that view is the one
+// down -z. The book's camera at y = 2 tilts down, which this one can't, so it
+// stands lower to keep the large spheres in the middle of the frame
+const COVER_EYE: Vec3 = Vec3::new(13.0, 1.2, 3.0);
+
+fn random(seed: u64) -> Contents {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let big = [
+        (
+            Point3::new(0.0, 1.0, 0.0),
+            Material::Dielectric { ior: Cauchy::CROWN },
+        ),
+        (
+            Point3::new(-4.0, 1.0, 0.0),
+            matte(Color::new(0.4, 0.2, 0.1)),
+        ),
+        (
+            Point3::new(4.0, 1.0, 0.0),
+            Material::Metal {
+                albedo: Color::new(0.7, 0.6, 0.5),
+                fuzz: 0.0,
+            },
+        ),
+    ];
+    let small_radius = 0.2;
+
+    let mut spheres = vec![(
+        Point3::new(0.0, -1000.0, 0.0),
+        1000.0,
+        matte(Color::new(0.5, 0.5, 0.5)),
+    )];
+    for a in -11..11 {
+        for b in -11..11 {
+            let center = Point3::new(
+                a as f64 + 0.9 * rng.gen::<f64>(),
+                small_radius,
+                b as f64 + 0.9 * rng.gen::<f64>(),
+            );
+            let choose: f64 = rng.gen();
+            let material = if choose < 0.8 {
+                let (c1, c2) = (random_color(&mut rng), random_color(&mut rng));
+                matte(Color::new(c1.x * c2.x, c1.y * c2.y, c1.z * c2.z))
+            } else if choose < 0.95 {
+                let c = random_color(&mut rng);
+                Material::Metal {
+                    albedo: Color::new(0.5 + 0.5 * c.x, 0.5 + 0.5 * c.y, 0.5 + 0.5 * c.z),
+                    fuzz: rng.gen_range(0.0..0.5),
+                }
+            } else {
+                Material::Dielectric { ior: Cauchy::CROWN }
+            };
+            // drawn before the check either way, so a seed's layout doesn't
+            // depend on which spheres are dropped
+            if big
+                .iter()
+                .any(|(c, _)| (center - *c).length() < 1.0 + small_radius)
+            {
+                continue;
+            }
+            spheres.push((center, small_radius, material));
+        }
+    }
+    spheres.extend(
+        big.into_iter()
+            .map(|(center, material)| (center, 1.0, material)),
+    );
+
+    // turned so the direction from the eye to the origin ends up along -z
+    let degrees = (-COVER_EYE.x).atan2(COVER_EYE.z).to_degrees();
+    let world = spheres
+        .into_iter()
+        .map(|(center, radius, material)| -> Box<dyn Hittable> {
+            Box::new(RotateY::new(
+                Box::new(Translate {
+                    object: Box::new(Sphere {
+                        center,
+                        radius,
+                        material,
+                    }),
+                    offset: -COVER_EYE,
+                }),
+                degrees,
+            ))
+        })
+        .collect();
+
+    Contents {
+        world,
+        lights: Vec::new(),
+        area_lights: Vec::new(),
+        sky: Sky::Gradient {
+            bottom: Color::new(1.0, 1.0, 1.0),
+            top: Color::new(0.5, 0.7, 1.0),
+        },
+        aspect_ratio: 3.0 / 2.0,
+        vfov: 20.0,
+    }
+}
+
+fn random_color(rng: &mut impl Rng) -> Color {
+    Color::new(rng.gen(), rng.gen(), rng.gen())
+}
+
+// radiance of the Cornell box's ceiling lamp
+const LAMP_RADIANCE: Color = Color::new(15.0, 15.0, 15.0);
+
+// a 2x2x2 box whose open front is at z = -2.75, just filling the view, with a
+// red left wall, a green right one, and a tall and a short block turned inside;
+// with `glass` a glass ball stands where the short block would
+fn cornell(glass: bool) -> Contents {
+    let red = matte(Color::new(0.65, 0.05, 0.05));
+    let white = matte(Color::new(0.73, 0.73, 0.73));
+    let green = matte(Color::new(0.12, 0.45, 0.15));
+    let (front, back) = (-2.75, -4.75);
+    let depth = Vec3::new(0.0, 0.0, back - front);
+    let wall = |corner: Point3, u: Vec3, v: Vec3, material: &Material| -> Box<dyn Hittable> {
+        Box::new(Quad {
+            corner,
+            u,
+            v,
+            material: material.clone(),
+        })
+    };
+    // just under the ceiling so it isn't hidden in it
+    let lamp = (
+        Point3::new(-0.25, 0.999, -3.55),
+        Vec3::new(0.5, 0.0, 0.0),
+        Vec3::new(0.0, 0.0, -0.4),
+    );
+    // blocks stand on the floor at their corner, turned about it, then moved
+    let block = |size: Vec3, degrees: f64, offset: Vec3| -> Box<dyn Hittable> {
+        Box::new(Translate {
+            object: Box::new(RotateY::new(
+                Box::new(Cuboid {
+                    min: Point3::ZERO,
+                    max: size,
+                    material: white.clone(),
+                }),
+                degrees,
+            )),
+            offset,
+        })
+    };
+
+    let (x, y) = (Vec3::new(2.0, 0.0, 0.0), Vec3::new(0.0, 2.0, 0.0));
+    let short: Box<dyn Hittable> = if glass {
+        Box::new(Sphere {
+            center: Point3::new(0.4, -0.6, -3.8),
+            radius: 0.4,
+            material: Material::Dielectric { ior: Cauchy::FLINT },
+        })
+    } else {
+        block(Vec3::new(0.6, 0.6, 0.6), -18.0, Vec3::new(0.2, -1.0, -3.7))
+    };
+    let world = vec![
+        wall(Point3::new(-1.0, -1.0, front), depth, y, &red),
+        wall(Point3::new(1.0, -1.0, front), depth, y, &green),
+        wall(Point3::new(-1.0, -1.0, front), x, depth, &white),
+        wall(Point3::new(-1.0, 1.0, front), x, depth, &white),
+        wall(Point3::new(-1.0, -1.0, back), x, y, &white),
+        wall(
+            lamp.0,
+            lamp.1,
+            lamp.2,
+            &Material::Emissive {
+                radiance: LAMP_RADIANCE,
+            },
+        ),
+        block(
+            Vec3::new(0.6, 1.2, 0.6),
+            15.0,
+            Vec3::new(-0.75, -1.0, -4.45),
+        ),
+        short,
+    ];
+    let area_lights = vec![AreaLight {
+        corner: lamp.0,
+        u: lamp.1,
+        v: lamp.2,
+    }];
+
+    Contents {
+        world,
+        lights: Vec::new(),
+        area_lights,
+        sky: Sky::Solid(Color::ZERO),
+        aspect_ratio: 1.0,
+        vfov: 40.0,
+    }
+}
+
+fn mesh_scene(mesh: Option<Mesh>) -> Contents {
+    let ground = -0.5;
+    let clay = matte(Color::new(0.75, 0.45, 0.3));
+    let mut world = match mesh {
+        Some(mesh) => mesh.triangles(Point3::new(0.0, ground, -2.2), 1.0, clay),
+        None => {
+            let smooth = Mesh::sphere(8, 12);
+            let mut world = smooth.triangles(Point3::new(0.6, ground, -2.2), 1.0, clay.clone());
+            world.extend(
+                smooth
+                    .faceted()
+                    .triangles(Point3::new(-0.6, ground, -2.2), 1.0, clay),
+            );
+            world
+        }
+    };
+    world.push(Box::new(Quad {
+        corner: Point3::new(-10.0, ground, 10.0),
+        u: Vec3::new(20.0, 0.0, 0.0),
+        v: Vec3::new(0.0, 0.0, -20.0),
+        material: Material::Lambertian {
+            albedo: Texture::Checker {
+                scale: 40.0,
+                even: Color::new(0.6, 0.6, 0.58),
+                odd: Color::new(0.35, 0.35, 0.33),
+            },
+            normal_map: None,
+        },
+    }));
+
+    Contents {
+        world,
+        lights: vec![Light::Point {
+            position: Point3::new(-2.0, 2.0, 0.0),
+            intensity: Color::new(12.0, 11.0, 10.0),
+        }],
+        area_lights: Vec::new(),
+        sky: Sky::Gradient {
+            bottom: Color::new(1.0, 1.0, 1.0),
+            top: Color::new(0.5, 0.7, 1.0),
+        },
+        aspect_ratio: 16.0 / 9.0,
+        vfov: 40.0,
+    }
+}
@@ -0,0 +1,342 @@
+// `--preview`: the render in a window as it goes. The threads drop each
+// finished tile into a `Framebuffer`, which the window polls, encodes like the
+// output file and uploads as a texture that's stretched over the window; the
+// image stays up once it's written, until the window is closed
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use math::Color;
+use winit::dpi::PhysicalSize;
+use winit::event::{Event, WindowEvent};
+use winit::event_loop::{ControlFlow, EventLoop};
+use winit::window::Window;
+
+// how often the window looks for new tiles
+const REFRESH: Duration = Duration::from_millis(50);
+
+const TITLE: &str = "Raytracer preview";
+
+const SHADER: &str = r#"
+@group(0) @binding(0)
+var image: texture_2d<f32>;
+@group(0) @binding(1)
+var image_sampler: sampler;
+
+struct VSOut {
+    @builtin(position) pos: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+};
+
+// one triangle covering the screen, with the texture's rows from the top
+@vertex
+fn vs_main(@builtin(vertex_index) vi: u32) -> VSOut {
+    let pos = vec2<f32>(f32(vi & 1u) * 4.0 - 1.0, f32(vi >> 1u) * 4.0 - 1.0);
+    var out: VSOut;
+    out.pos = vec4<f32>(pos, 0.0, 1.0);
+    out.uv = vec2<f32>(pos.x * 0.5 + 0.5, 0.5 - pos.y * 0.5);
+    return out;
+}
+
+@fragment
+fn fs_main(in: VSOut) -> @location(0) vec4<f32> {
+    return textureSample(image, image_sampler, in.uv);
+}
+"#;
+
+// the image as far as it's rendered, linear and from the top row down
+pub struct Framebuffer {
+    width: usize,
+    height: usize,
+    exposure: f64,
+    gamma: f64,
+    pixels: Mutex<Vec<Color>>,
+    // set when `pixels` changed since the window last took them
+    dirty: AtomicBool,
+}
+
+impl Framebuffer {
+    pub fn new(width: usize, height: usize, exposure: f64, gamma: f64) -> Self {
+        Self {
+            width,
+            height,
+            exposure,
+            gamma,
+            pixels: Mutex::new(vec![Color::ZERO; width * height]),
+            dirty: AtomicBool::new(true),
+        }
+    }
+
+    // a finished tile, `rows` of `tile_width` pixels with its top left at
+    // (`x0`, `y0`)
+    pub fn tile(&self, x0: usize, y0: usize, tile_width: usize, rows: &[Color]) {
+        let mut pixels = self.pixels.lock().unwrap();
+        for (y, row) in (y0..).zip(rows.chunks(tile_width)) {
+            let start = y * self.width + x0;
+            pixels[start..start + tile_width].copy_from_slice(row);
+        }
+        self.dirty.store(true, Ordering::Release);
+    }
+
+    // the whole image at once, such as after denoising
+    pub fn show(&self, image: &[Color]) {
+        self.pixels.lock().unwrap().copy_from_slice(image);
+        self.dirty.store(true, Ordering::Release);
+    }
+
+    // RGBA8 encoded like the output file, if anything changed
+    fn take(&self) -> Option<Vec<u8>> {
+        if !self.dirty.swap(false, Ordering::Acquire) {
+            return None;
+        }
+        let pixels = self.pixels.lock().unwrap();
+        Some(
+            pixels
+                .iter()
+                .flat_map(|&pixel| {
+                    let [r, g, b] = image_io::encode_rgb8(pixel, self.exposure, self.gamma);
+                    [r, g, b, 255]
+                })
+                .collect(),
+        )
+    }
+}
+
+// runs `work` on another thread while the window shows `framebuffer`, and
+// returns what it returns once both are done; without a display to open the
+// window on, `work` just runs
+pub fn run<R: Send>(framebuffer: &Framebuffer, work: impl FnOnce() -> R + Send) -> R {
+    std::thread::scope(|s| {
+        let worker = s.spawn(work);
+        if let Err(err) = show(framebuffer, || worker.is_finished()) {
+            eprintln!("Preview unavailable: {err}");
+        }
+        worker
+            .join()
+            .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+    })
+}
+
+// winit 0.30 deprecates the closure-based `run`/`create_window` in favour of
+// `ApplicationHandler`; like the playground, the closure form is the simpler fit
+#[allow(deprecated)]
+fn show(framebuffer: &Framebuffer, finished: impl Fn() -> bool) -> Result<(), String> {
+    let event_loop = EventLoop::new().map_err(|err| err.to_string())?;
+    let window = event_loop
+        .create_window(
+            Window::default_attributes()
+                .with_title(TITLE)
+                .with_inner_size(PhysicalSize::new(
+                    framebuffer.width as u32,
+                    framebuffer.height as u32,
+                )),
+        )
+        .map_err(|err| err.to_string())?;
+    let mut display = pollster::block_on(Display::new(
+        &window,
+        framebuffer.width as u32,
+        framebuffer.height as u32,
+    ))?;
+
+    let mut done = false;
+    event_loop
+        .run(|event, elwt| match event {
+            Event::WindowEvent { event, .. } => match event {
+                WindowEvent::CloseRequested => elwt.exit(),
+                WindowEvent::Resized(size) => {
+                    display.resize(size);
+                    window.request_redraw();
+                }
+                WindowEvent::RedrawRequested => display.draw(),
+                _ => {}
+            },
+            Event::AboutToWait => {
+                if let Some(rgba) = framebuffer.take() {
+                    display.upload(&rgba);
+                    window.request_redraw();
+                }
+                if !done && finished() {
+                    done = true;
+                    window.set_title(&format!("{TITLE} - done"));
+                }
+                elwt.set_control_flow(ControlFlow::WaitUntil(Instant::now() + REFRESH));
+            }
+            _ => {}
+        })
+        .map_err(|err| err.to_string())
+}
+
+// the surface and the texture the image is shown from
+struct Display<'window> {
+    surface: wgpu::Surface<'window>,
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    config: wgpu::SurfaceConfiguration,
+    texture: wgpu::Texture,
+    bind_group: wgpu::BindGroup,
+    pipeline: wgpu::RenderPipeline,
+}
+
+impl<'window> Display<'window> {
+    async fn new(window: &'window Window, width: u32, height: u32) -> Result<Self, String> {
+        let instance = wgpu::Instance::default();
+        let surface = instance
+            .create_surface(window)
+            .map_err(|err| err.to_string())?;
+        let adapter = instance
+            .request_adapter(&wgpu::RequestAdapterOptions {
+                power_preference: wgpu::PowerPreference::default(),
+                compatible_surface: Some(&surface),
+                force_fallback_adapter: false,
+            })
+            .await
+            .map_err(|err| err.to_string())?;
+        let (device, queue) = adapter
+            .request_device(&wgpu::DeviceDescriptor::default())
+            .await
+            .map_err(|err| err.to_string())?;
+
+        let size = window.inner_size();
+        let config = surface
+            .get_default_config(&adapter, size.width.max(1), size.height.max(1))
+            .ok_or("the window's surface isn't supported by the GPU")?;
+        surface.configure(&device, &config);
+
+        // the image is already encoded, so an sRGB surface gets an sRGB
+        // texture: sampling decodes it and the surface encodes it back
+        let format = if config.format.is_srgb() {
+            wgpu::TextureFormat::Rgba8UnormSrgb
+        } else {
+            wgpu::TextureFormat::Rgba8Unorm
+        };
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Preview Image"),
+            size: wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            view_formats: &[],
+        });
+        // nearest, so the pixels stay sharp when the window is enlarged
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor::default());
+
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Preview Shader"),
+            source: wgpu::ShaderSource::Wgsl(SHADER.into()),
+        });
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Preview Pipeline"),
+            layout: None,
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+                buffers: &[],
+            },
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some("fs_main"),
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+                targets: &[Some(config.format.into())],
+            }),
+            multiview: None,
+            cache: None,
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Preview BG"),
+            layout: &pipeline.get_bind_group_layout(0),
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(
+                        &texture.create_view(&wgpu::TextureViewDescriptor::default()),
+                    ),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&sampler),
+                },
+            ],
+        });
+
+        Ok(Self {
+            surface,
+            device,
+            queue,
+            config,
+            texture,
+            bind_group,
+            pipeline,
+        })
+    }
+
+    fn resize(&mut self, size: PhysicalSize<u32>) {
+        if size.width > 0 && size.height > 0 {
+            self.config.width = size.width;
+            self.config.height = size.height;
+            self.surface.configure(&self.device, &self.config);
+        }
+    }
+
+    fn upload(&self, rgba: &[u8]) {
+        let size = self.texture.size();
+        self.queue.write_texture(
+            self.texture.as_image_copy(),
+            rgba,
+            wgpu::TexelCopyBufferLayout {
+                offset: 0,
+                bytes_per_row: Some(4 * size.width),
+                rows_per_image: None,
+            },
+            size,
+        );
+    }
+
+    fn draw(&mut self) {
+        let frame = match self.surface.get_current_texture() {
+            Ok(frame) => frame,
+            Err(wgpu::SurfaceError::Lost | wgpu::SurfaceError::Outdated) => {
+                self.surface.configure(&self.device, &self.config);
+                return;
+            }
+            Err(_) => return,
+        };
+        let view = frame
+            .texture
+            .create_view(&wgpu::TextureViewDescriptor::default());
+        let mut encoder = self
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+        {
+            let mut rpass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Preview Pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: &view,
+                    depth_slice: None,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+            rpass.set_pipeline(&self.pipeline);
+            rpass.set_bind_group(0, &self.bind_group, &[]);
+            rpass.draw(0..3, 0..1);
+        }
+        self.queue.submit(Some(encoder.finish()));
+        frame.present();
+    }
+}
@@ -0,0 +1,44 @@
+use math::{DVec3 as Vec3, Point3};
+
+use crate::disk::facing;
+use crate::hittable::{HitRecord, Hittable};
+use crate::material::Material;
+use crate::ray::Ray;
+
+// a flat parallelogram spanned by the edges `u` and `v` from `corner`; uv runs
+// 0..1 along each edge
+pub struct Quad {
+    pub corner: Point3,
+    pub u: Vec3,
+    pub v: Vec3,
+    pub material: Material,
+}
+
+impl Hittable for Quad {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        let n = self.u.cross(&self.v);
+        let normal = n.unit();
+        let denom = normal.dot(&r.direction);
+        // parallel to the plane
+        if denom.abs() < 1e-12 {
+            return None;
+        }
+        let t = (self.corner - r.origin).dot(&normal) / denom;
+        if t <= t_min || t >= t_max {
+            return None;
+        }
+        // the hit's coordinates along the two edges, from the plane's basis
+        let p = r.at(t);
+        let d = p - self.corner;
+        let w = n / n.dot(&n);
+        let a = w.dot(&d.cross(&self.v));
+        let b = w.dot(&self.u.cross(&d));
+        if !(0.0..=1.0).contains(&a) || !(0.0..=1.0).contains(&b) {
+            return None;
+        }
+        let mut hit = HitRecord::new(t, p, facing(normal, r), self.material.clone());
+        hit.uv = (a, b);
+        hit.tangent = self.u.unit();
+        Some(hit)
+    }
+}
@@ -0,0 +1,24 @@
+use math::{DVec3 as Vec3, Point3};
+
+#[derive(Clone, Copy)]
+pub struct Ray {
+    pub origin: Point3,
+    pub direction: Vec3,
+    // moment within the shutter interval the ray was sent at; moving objects
+    // are placed for this time
+    pub time: f64,
+}
+
+impl Ray {
+    pub fn new(origin: Point3, direction: Vec3, time: f64) -> Self {
+        Self {
+            origin,
+            direction,
+            time,
+        }
+    }
+
+    pub fn at(&self, t: f64) -> Point3 {
+        self.origin + self.direction * t
+    }
+}
@@ -0,0 +1,175 @@
+use clap::ValueEnum;
+use math::DVec3 as Vec3;
+use rand::Rng;
+
+// rejection sampling: uniform inside the unit ball
+pub fn random_in_unit_sphere(rng: &mut impl Rng) -> Vec3 {
+    loop {
+        let p = Vec3::new(
+            rng.gen_range(-1.0..1.0),
+            rng.gen_range(-1.0..1.0),
+            rng.gen_range(-1.0..1.0),
+        );
+        if p.length_squared() < 1.0 {
+            return p;
+        }
+    }
+}
+
+// orthonormal basis with `w` along a given direction, for turning directions
+// sampled around +z into world space
+pub struct Onb {
+    u: Vec3,
+    v: Vec3,
+    w: Vec3,
+}
+
+impl Onb {
+    pub fn from_w(n: Vec3) -> Self {
+        let w = n.unit();
+        // any helper axis works as long as it isn't parallel to w
+        let a = if w.x.abs() > 0.9 {
+            Vec3::new(0.0, 1.0, 0.0)
+        } else {
+            Vec3::new(1.0, 0.0, 0.0)
+        };
+        let v = w.cross(&a).unit();
+        let u = w.cross(&v);
+        Self { u, v, w }
+    }
+
+    pub fn u(&self) -> Vec3 {
+        self.u
+    }
+
+    pub fn local(&self, a: Vec3) -> Vec3 {
+        self.u * a.x + self.v * a.y + self.w * a.z
+    }
+}
+
+// unit direction around +z with density cos(theta) / pi, from two uniform randoms
+pub fn random_cosine_direction(rng: &mut impl Rng) -> Vec3 {
+    let r1: f64 = rng.gen();
+    let r2: f64 = rng.gen();
+    let phi = 2.0 * std::f64::consts::PI * r1;
+    let (sin_phi, cos_phi) = phi.sin_cos();
+    let r = r2.sqrt();
+    Vec3::new(cos_phi * r, sin_phi * r, (1.0 - r2).sqrt())
+}
+
+// PCG's output permutation (RXS-M-XS) applied to one LCG step of `v`: a cheap
+// integer hash whose outputs for neighbouring inputs look unrelated
+fn pcg_hash(v: u64) -> u64 {
+    let state = v
+        .wrapping_mul(6364136223846793005)
+        .wrapping_add(1442695040888963407);
+    let word = ((state >> ((state >> 59) + 5)) ^ state).wrapping_mul(12605985483714917081);
+    (word >> 43) ^ word
+}
+
+// where a pixel's random numbers start, from its coordinates and the render's
+// seed; hashed rather than counted up, so neighbouring pixels' noise isn't
+// correlated, and the image doesn't depend on the order pixels are rendered in
+pub fn pixel_seed(x: usize, y: usize, seed: u64) -> u64 {
+    pcg_hash(pcg_hash((y as u64) << 32 | x as u64) ^ seed)
+}
+
+// how diffuse bounces pick their direction
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+pub enum Sampler {
+    // normal plus a random point in the unit ball; cheap but not exactly
+    // cosine-distributed
+    UnitSphere,
+    // importance-sampled by cos(theta) through an ONB around the normal
+    Cosine,
+}
+
+impl Sampler {
+    pub fn diffuse_direction(self, n: Vec3, rng: &mut impl Rng) -> Vec3 {
+        match self {
+            Sampler::UnitSphere => n + random_in_unit_sphere(rng),
+            Sampler::Cosine => Onb::from_w(n).local(random_cosine_direction(rng)),
+        }
+    }
+
+    // the density over solid angle of `diffuse_direction` picking `direction`
+    // around `n`, for weighting it against the lights' own samples. A ray along
+    // a direction at angle theta to the normal crosses the unit ball on it for
+    // a length of 2cos(theta), so the unit-sphere sampler's is 2cos^3(theta) / pi
+    pub fn pdf(self, n: Vec3, direction: Vec3) -> f64 {
+        let cos = n.unit().dot(&direction.unit()).max(0.0);
+        match self {
+            Sampler::UnitSphere => 2.0 * cos.powi(3) / std::f64::consts::PI,
+            Sampler::Cosine => cos / std::f64::consts::PI,
+        }
+    }
+}
+
+// how the samples in and around a pixel are weighted into it; each sample lands
+// within `radius` pixels of the pixel's centre on both axes
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+pub enum PixelFilter {
+    // every sample inside the pixel counts the same: a plain average
+    Box,
+    // weight falling linearly to zero one pixel out, so neighbours share samples
+    // and edges blend over about two pixels
+    Tent,
+    // a Gaussian with a standard deviation of half a pixel, cut off (and
+    // shifted down to reach zero) at one and a half
+    Gaussian,
+}
+
+impl PixelFilter {
+    pub fn radius(self) -> f64 {
+        match self {
+            PixelFilter::Box => 0.5,
+            PixelFilter::Tent => 1.0,
+            PixelFilter::Gaussian => 1.5,
+        }
+    }
+
+    // for a sample `dx`, `dy` pixels from the centre; separable, like all three
+    pub fn weight(self, dx: f64, dy: f64) -> f64 {
+        let radius = self.radius();
+        let axis = |d: f64| match self {
+            PixelFilter::Box => 1.0,
+            PixelFilter::Tent => (1.0 - d.abs() / radius).max(0.0),
+            PixelFilter::Gaussian => {
+                let gaussian = |x: f64| (-2.0 * x * x).exp();
+                (gaussian(d) - gaussian(radius)).max(0.0)
+            }
+        };
+        axis(dx) * axis(dy)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::SmallRng;
+    use rand::SeedableRng;
+
+    // the share of each sampler's directions within 60 degrees of the normal
+    // matches its pdf integrated over that cone
+    #[test]
+    fn sampler_pdfs_match_their_directions() {
+        let n = Vec3::new(0.0, 0.0, 1.0);
+        let steps = 1000;
+        let step = std::f64::consts::FRAC_PI_3 / steps as f64;
+        for sampler in [Sampler::UnitSphere, Sampler::Cosine] {
+            let expected: f64 = (0..steps)
+                .map(|i| {
+                    let theta = (i as f64 + 0.5) * step;
+                    let direction = Vec3::new(theta.sin(), 0.0, theta.cos());
+                    sampler.pdf(n, direction) * std::f64::consts::TAU * theta.sin() * step
+                })
+                .sum();
+            let mut rng = SmallRng::seed_from_u64(0);
+            let samples = 100_000;
+            let inside = (0..samples)
+                .filter(|_| sampler.diffuse_direction(n, &mut rng).unit().z > 0.5)
+                .count();
+            assert!((inside as f64 / samples as f64 - expected).abs() < 0.005);
+        }
+    }
+}
@@ -0,0 +1,58 @@
+use std::sync::OnceLock;
+
+use math::Color;
+use rand::Rng;
+
+// wavelengths in nanometres that spectral paths are picked from
+const VISIBLE: (f64, f64) = (380.0, 780.0);
+
+// CIE 1931 colour matching functions, from the multi-lobe Gaussian fit of
+// Wyman, Sloan and Shirley (2013)
+fn cie_xyz(wavelength: f64) -> Color {
+    // a Gaussian with a different width on either side of its peak
+    let g = |mu: f64, below: f64, above: f64| {
+        let sigma = if wavelength < mu { below } else { above };
+        let x = (wavelength - mu) / sigma;
+        (-0.5 * x * x).exp()
+    };
+    Color::new(
+        1.056 * g(599.8, 37.9, 31.0) + 0.362 * g(442.0, 16.0, 26.7) - 0.065 * g(501.1, 20.4, 26.2),
+        0.821 * g(568.8, 46.9, 40.5) + 0.286 * g(530.9, 16.3, 31.1),
+        1.217 * g(437.0, 11.8, 36.0) + 0.681 * g(459.0, 26.0, 13.8),
+    )
+}
+
+// linear sRGB of a single wavelength, with the colours outside the gamut
+// clipped to it
+fn linear_rgb(wavelength: f64) -> Color {
+    let Color { x, y, z } = cie_xyz(wavelength);
+    Color::new(
+        (3.2406 * x - 1.5372 * y - 0.4986 * z).max(0.0),
+        (-0.9689 * x + 1.8758 * y + 0.0415 * z).max(0.0),
+        (0.0557 * x - 0.2040 * y + 1.0570 * z).max(0.0),
+    )
+}
+
+// the average of `linear_rgb` over the visible range, so that dividing by it
+// makes the weights of uniformly picked wavelengths average to white
+fn mean_rgb() -> Color {
+    static MEAN: OnceLock<Color> = OnceLock::new();
+    *MEAN.get_or_init(|| {
+        const STEPS: usize = 1000;
+        let step = (VISIBLE.1 - VISIBLE.0) / STEPS as f64;
+        let sum = (0..STEPS).fold(Color::ZERO, |sum, i| {
+            sum + linear_rgb(VISIBLE.0 + (i as f64 + 0.5) * step)
+        });
+        sum * (1.0 / STEPS as f64)
+    })
+}
+
+// a uniformly picked wavelength, and the colour a path carrying only it is
+// weighted by; summing these accumulates the path's XYZ response converted to
+// RGB, which over many paths averages out to the light it would have had
+pub fn sample(rng: &mut impl Rng) -> (f64, Color) {
+    let wavelength = rng.gen_range(VISIBLE.0..VISIBLE.1);
+    let Color { x, y, z } = linear_rgb(wavelength);
+    let mean = mean_rgb();
+    (wavelength, Color::new(x / mean.x, y / mean.y, z / mean.z))
+}
@@ -0,0 +1,125 @@
+use std::f64::consts::PI;
+
+use math::{DVec3 as Vec3, Point3};
+
+use crate::hittable::{HitRecord, Hittable, Solid, Span};
+use crate::material::Material;
+use crate::ray::Ray;
+
+pub struct Sphere {
+    pub center: Point3,
+    pub radius: f64,
+    pub material: Material,
+}
+
+// nearest root of the ray/sphere intersection within (t_min, t_max)
+fn hit_sphere(center: Point3, radius: f64, r: &Ray, t_min: f64, t_max: f64) -> Option<f64> {
+    let oc = r.origin - center;
+    let a = r.direction.length_squared();
+    let half_b = oc.dot(&r.direction);
+    let c = oc.length_squared() - radius * radius;
+    let discriminant = half_b * half_b - a * c;
+    if discriminant < 0.0 {
+        return None;
+    }
+    let sqrtd = discriminant.sqrt();
+    [(-half_b - sqrtd) / a, (-half_b + sqrtd) / a]
+        .into_iter()
+        .find(|&t| t > t_min && t < t_max)
+}
+
+// latitude/longitude coordinates: u goes once around the y axis starting at
+// -x, v from the bottom pole to the top
+fn sphere_record(t: f64, p: Point3, center: Point3, radius: f64, material: &Material) -> HitRecord {
+    let n = (p - center) / radius;
+    let u = ((-n.z).atan2(n.x) + PI) / (2.0 * PI);
+    let v = (-n.y).clamp(-1.0, 1.0).acos() / PI;
+    // d/du of the point, which vanishes at the poles
+    let tangent = Vec3::new(n.z, 0.0, -n.x);
+    let tangent = if tangent.length_squared() > 1e-12 {
+        tangent.unit()
+    } else {
+        Vec3::new(1.0, 0.0, 0.0)
+    };
+    HitRecord {
+        t,
+        p,
+        normal: n,
+        uv: (u, v),
+        tangent,
+        material: material.clone(),
+    }
+}
+
+fn sphere_hit(
+    center: Point3,
+    radius: f64,
+    material: &Material,
+    r: &Ray,
+    t_min: f64,
+    t_max: f64,
+) -> Option<HitRecord> {
+    let t = hit_sphere(center, radius, r, t_min, t_max)?;
+    Some(sphere_record(t, r.at(t), center, radius, material))
+}
+
+impl Hittable for Sphere {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        sphere_hit(self.center, self.radius, &self.material, r, t_min, t_max)
+    }
+}
+
+impl Solid for Sphere {
+    fn spans(&self, r: &Ray) -> Vec<Span> {
+        let Some(t0) = hit_sphere(
+            self.center,
+            self.radius,
+            r,
+            f64::NEG_INFINITY,
+            f64::INFINITY,
+        ) else {
+            return Vec::new();
+        };
+        // the far root, past the near one
+        let Some(t1) = hit_sphere(self.center, self.radius, r, t0, f64::INFINITY) else {
+            return Vec::new();
+        };
+        let record = |t| sphere_record(t, r.at(t), self.center, self.radius, &self.material);
+        vec![Span {
+            enter: record(t0),
+            exit: record(t1),
+        }]
+    }
+}
+
+// a sphere moving in a straight line from `center0` at `time0` to `center1` at
+// `time1`, which streaks across a frame whose shutter overlaps that interval
+pub struct MovingSphere {
+    pub center0: Point3,
+    pub center1: Point3,
+    pub time0: f64,
+    pub time1: f64,
+    pub radius: f64,
+    pub material: Material,
+}
+
+impl MovingSphere {
+    // keeps moving at the same speed outside [time0, time1]
+    pub fn center(&self, time: f64) -> Point3 {
+        let s = (time - self.time0) / (self.time1 - self.time0);
+        self.center0 + (self.center1 - self.center0) * s
+    }
+}
+
+impl Hittable for MovingSphere {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        sphere_hit(
+            self.center(r.time),
+            self.radius,
+            &self.material,
+            r,
+            t_min,
+            t_max,
+        )
+    }
+}
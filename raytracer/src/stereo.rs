@@ -0,0 +1,28 @@
+use clap::ValueEnum;
+use math::{Color, DVec3 as Vec3};
+
+use crate::camera::Camera;
+
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+pub enum Stereo {
+    // the left eye's view in red and the right eye's in green and blue, for
+    // red-cyan glasses
+    Anaglyph,
+}
+
+// the left and right eye: moved `interocular` apart sideways and both turned
+// to look at the point `convergence` ahead of `camera`, which ends up at the
+// depth of the screen; nearer things stand out of it, farther ones sink in
+pub fn eyes(camera: &Camera, interocular: f64, convergence: f64) -> [Camera; 2] {
+    let target = camera.origin + camera.forward.unit() * convergence;
+    let offset = camera.horizontal.unit() * (interocular / 2.0);
+    [-1.0, 1.0].map(|side: f64| camera.looking_at(camera.origin + offset * side, target))
+}
+
+// red from the left image, green and blue from the right, still linear
+pub fn anaglyph(left: &[Color], right: &[Color]) -> Vec<Color> {
+    left.iter()
+        .zip(right)
+        .map(|(l, r)| Vec3::new(l.x, r.y, r.z))
+        .collect()
+}
@@ -0,0 +1,206 @@
+use std::path::Path;
+
+use clap::ValueEnum;
+use math::{Color, DVec3 as Vec3};
+
+use crate::hittable::HitRecord;
+
+// a surface's color, looked up by the hit's uv
+#[derive(Clone)]
+pub enum Texture {
+    Solid(Color),
+    // alternating squares, `scale` of them across each unit of u and of v
+    Checker { scale: f64, even: Color, odd: Color },
+}
+
+impl Texture {
+    pub fn value(&self, (u, v): (f64, f64)) -> Color {
+        match *self {
+            Texture::Solid(color) => color,
+            Texture::Checker { scale, even, odd } => {
+                let tile = (u * scale).floor() + (v * scale).floor();
+                if tile.rem_euclid(2.0) == 0.0 {
+                    even
+                } else {
+                    odd
+                }
+            }
+        }
+    }
+}
+
+// how texels are looked up between their centres
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+pub enum TextureFilter {
+    // the texel under the point; blocky up close
+    Nearest,
+    // the four surrounding texels, weighted by distance
+    Bilinear,
+}
+
+// a tangent-space normal map: red along the surface's u direction, green along
+// v's, blue out of the surface, each mapped from 0..1 to -1..1
+pub struct NormalMap {
+    width: u32,
+    height: u32,
+    normals: Vec<Vec3>,
+    filter: TextureFilter,
+}
+
+impl NormalMap {
+    pub fn load(path: &Path, filter: TextureFilter) -> image::ImageResult<Self> {
+        let image = image::open(path)?.into_rgb8();
+        let (width, height) = image.dimensions();
+        let channel = |c: u8| c as f64 / 255.0 * 2.0 - 1.0;
+        let normals = image
+            .pixels()
+            .map(|p| Vec3::new(channel(p[0]), channel(p[1]), channel(p[2])).unit())
+            .collect();
+        Ok(Self {
+            width,
+            height,
+            normals,
+            filter,
+        })
+    }
+
+    // u wraps around, v is clamped, and v = 1 is the image's top
+    fn sample(&self, (u, v): (f64, f64)) -> Vec3 {
+        let (w, h) = (self.width as f64, self.height as f64);
+        let x = u.rem_euclid(1.0) * w;
+        let y = (1.0 - v.clamp(0.0, 1.0)) * h;
+        match self.filter {
+            TextureFilter::Nearest => self.texel(x as i64, y as i64),
+            TextureFilter::Bilinear => {
+                // texel centres sit at half-integer coordinates
+                let (x, y) = (x - 0.5, y - 0.5);
+                let (x0, y0) = (x.floor(), y.floor());
+                let (fx, fy) = (x - x0, y - y0);
+                let (x0, y0) = (x0 as i64, y0 as i64);
+                let top = self.texel(x0, y0) * (1.0 - fx) + self.texel(x0 + 1, y0) * fx;
+                let bottom = self.texel(x0, y0 + 1) * (1.0 - fx) + self.texel(x0 + 1, y0 + 1) * fx;
+                // blended normals are shorter than unit
+                (top * (1.0 - fy) + bottom * fy).unit()
+            }
+        }
+    }
+
+    // wrapped across the u seam, clamped at the poles
+    fn texel(&self, x: i64, y: i64) -> Vec3 {
+        let x = x.rem_euclid(self.width as i64) as u32;
+        let y = y.clamp(0, self.height as i64 - 1) as u32;
+        self.normals[(y * self.width + x) as usize]
+    }
+
+    // the hit's normal tilted by the map, in world space
+    pub fn perturb(&self, hit: &HitRecord) -> Vec3 {
+        let n = hit.normal;
+        // re-orthogonalized, in case the tangent isn't quite perpendicular
+        let t = (hit.tangent - n * hit.tangent.dot(&n)).unit();
+        let b = n.cross(&t);
+        let m = self.sample(hit.uv);
+        (t * m.x + b * m.y + n * m.z).unit()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use math::Point3;
+
+    use super::*;
+    use crate::cuboid::Cuboid;
+    use crate::hittable::Hittable;
+    use crate::material::Material;
+    use crate::quad::Quad;
+    use crate::ray::Ray;
+
+    const EVEN: Color = Color::new(1.0, 1.0, 1.0);
+    const ODD: Color = Color::ZERO;
+
+    fn checker(scale: f64) -> Material {
+        Material::Lambertian {
+            albedo: Texture::Checker {
+                scale,
+                even: EVEN,
+                odd: ODD,
+            },
+            normal_map: None,
+        }
+    }
+
+    // renders one row of pixels looking down -z at `object`, from `from` to
+    // `to` just in front of it, and counts the runs of one color along it
+    fn tiles_along(object: &dyn Hittable, from: Point3, to: Point3) -> usize {
+        const PIXELS: usize = 1000;
+        let colors: Vec<Color> = (0..PIXELS)
+            .map(|i| {
+                let origin = from + (to - from) * ((i as f64 + 0.5) / PIXELS as f64);
+                let r = Ray::new(origin, Vec3::new(0.0, 0.0, -1.0), 0.0);
+                let hit = object.hit(&r, 0.001, f64::INFINITY).expect("row misses");
+                match &hit.material {
+                    Material::Lambertian { albedo, .. } => albedo.value(hit.uv),
+                    _ => unreachable!(),
+                }
+            })
+            .collect();
+        1 + colors.windows(2).filter(|pair| pair[0] != pair[1]).count()
+    }
+
+    #[test]
+    fn checker_plane_has_scale_tiles_each_way() {
+        for scale in [1.0, 4.0, 7.0] {
+            let plane = Quad {
+                corner: Point3::new(-1.0, -1.0, -3.0),
+                u: Vec3::new(2.0, 0.0, 0.0),
+                v: Vec3::new(0.0, 2.0, 0.0),
+                material: checker(scale),
+            };
+            // off the tile edges, so no row runs along a boundary
+            let across = tiles_along(
+                &plane,
+                Point3::new(-1.0, 0.013, 0.0),
+                Point3::new(1.0, 0.013, 0.0),
+            );
+            let up = tiles_along(
+                &plane,
+                Point3::new(0.013, -1.0, 0.0),
+                Point3::new(0.013, 1.0, 0.0),
+            );
+            assert_eq!((across, up), (scale as usize, scale as usize));
+        }
+    }
+
+    #[test]
+    fn checker_box_face_has_scale_tiles_each_way() {
+        let cuboid = Cuboid {
+            min: Point3::new(-0.5, -2.0, -4.0),
+            max: Point3::new(1.5, 1.0, -2.0),
+            material: checker(3.0),
+        };
+        let across = tiles_along(
+            &cuboid,
+            Point3::new(-0.5, 0.01, 0.0),
+            Point3::new(1.5, 0.01, 0.0),
+        );
+        let up = tiles_along(
+            &cuboid,
+            Point3::new(0.01, -2.0, 0.0),
+            Point3::new(0.01, 1.0, 0.0),
+        );
+        assert_eq!((across, up), (3, 3));
+    }
+
+    #[test]
+    fn checker_alternates_from_the_origin() {
+        let texture = Texture::Checker {
+            scale: 2.0,
+            even: EVEN,
+            odd: ODD,
+        };
+        assert_eq!(texture.value((0.25, 0.25)), EVEN);
+        assert_eq!(texture.value((0.75, 0.25)), ODD);
+        assert_eq!(texture.value((0.75, 0.75)), EVEN);
+        // negative coordinates keep alternating rather than mirroring
+        assert_eq!(texture.value((-0.25, 0.25)), ODD);
+    }
+}
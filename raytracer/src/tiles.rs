@@ -0,0 +1,85 @@
+// the order `render`'s threads take the tiles in. Each pixel is seeded from its
+// position, so the image comes out the same whichever; what changes is which
+// part of it a `--preview` window fills in first
+use clap::ValueEnum;
+
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+pub enum TileOrder {
+    // row by row from the top, like the image is stored
+    Scanline,
+    // ring by ring out from the middle of the image, where the subject
+    // usually is
+    Spiral,
+    // along a Hilbert curve from the top left, so the finished tiles stay in
+    // one compact patch rather than a long strip
+    Hilbert,
+}
+
+impl TileOrder {
+    // the (column, row) of every tile of a `columns` by `rows` grid, in the
+    // order they're rendered
+    pub fn tiles(self, columns: usize, rows: usize) -> Vec<(usize, usize)> {
+        let grid = (0..rows).flat_map(|row| (0..columns).map(move |column| (column, row)));
+        match self {
+            TileOrder::Scanline => grid.collect(),
+            TileOrder::Spiral => {
+                // rings are squares of tiles around the centre, and each goes
+                // round clockwise from straight up; a tile's centre is half a
+                // tile in from its corner
+                let centre = (columns as f64 / 2.0, rows as f64 / 2.0);
+                let mut tiles: Vec<(usize, usize, usize, f64)> = grid
+                    .map(|(column, row)| {
+                        let dx = column as f64 + 0.5 - centre.0;
+                        let dy = row as f64 + 0.5 - centre.1;
+                        let ring = dx.abs().max(dy.abs()) as usize;
+                        (
+                            column,
+                            row,
+                            ring,
+                            dx.atan2(-dy).rem_euclid(std::f64::consts::TAU),
+                        )
+                    })
+                    .collect();
+                tiles.sort_by(|a, b| a.2.cmp(&b.2).then(a.3.total_cmp(&b.3)));
+                tiles
+                    .into_iter()
+                    .map(|(column, row, _, _)| (column, row))
+                    .collect()
+            }
+            TileOrder::Hilbert => {
+                // the curve through the smallest power-of-two square that
+                // covers the grid, skipping the tiles off its edges
+                let side = columns.max(rows).next_power_of_two();
+                (0..side * side)
+                    .map(|d| hilbert_point(side, d))
+                    .filter(|&(column, row)| column < columns && row < rows)
+                    .collect()
+            }
+        }
+    }
+}
+
+// the `d`th cell along the Hilbert curve through a `side` by `side` square,
+// `side` a power of two
+fn hilbert_point(side: usize, d: usize) -> (usize, usize) {
+    let (mut x, mut y) = (0, 0);
+    let mut t = d;
+    let mut s = 1;
+    while s < side {
+        let rx = 1 & (t / 2);
+        let ry = 1 & (t ^ rx);
+        // each quadrant is the curve turned so its ends meet the next one's
+        if ry == 0 {
+            if rx == 1 {
+                x = s - 1 - x;
+                y = s - 1 - y;
+            }
+            std::mem::swap(&mut x, &mut y);
+        }
+        x += s * rx;
+        y += s * ry;
+        t /= 4;
+        s *= 2;
+    }
+    (x, y)
+}
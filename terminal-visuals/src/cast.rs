@@ -0,0 +1,95 @@
+use std::fs::File;
+use std::io::{BufWriter, Result, Write};
+use std::path::Path;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+// an asciicast v2 recording: a JSON header line, then one `[time, code, data]`
+// line per event
+pub struct Cast {
+    file: BufWriter<File>,
+    start: Instant,
+    // output since the last flush, written as one event
+    pending: Vec<u8>,
+}
+
+impl Cast {
+    pub fn create(path: &Path, width: u16, height: u16) -> Result<Self> {
+        let mut file = BufWriter::new(File::create(path)?);
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs());
+        writeln!(
+            file,
+            r#"{{"version": 2, "width": {width}, "height": {height}, "timestamp": {timestamp}}}"#
+        )?;
+        Ok(Self {
+            file,
+            start: Instant::now(),
+            pending: Vec::new(),
+        })
+    }
+
+    fn event(&mut self, code: &str, data: &str) -> Result<()> {
+        let data = serde_json::to_string(data)?;
+        let time = self.start.elapsed().as_secs_f64();
+        writeln!(self.file, "[{time:.6}, \"{code}\", {data}]")
+    }
+
+    fn flush_output(&mut self) -> Result<()> {
+        if self.pending.is_empty() {
+            return Ok(());
+        }
+        let data = String::from_utf8_lossy(&self.pending).into_owned();
+        self.pending.clear();
+        self.event("o", &data)
+    }
+}
+
+// passes everything through to `inner`, and with a cast also records each
+// flushed chunk of output; frames are flushed whole, so each becomes one event
+pub struct Tee<W: Write> {
+    inner: W,
+    cast: Option<Cast>,
+}
+
+impl<W: Write> Tee<W> {
+    pub fn new(inner: W, cast: Option<Cast>) -> Self {
+        Self { inner, cast }
+    }
+
+    // lets a player follow the terminal's size changes
+    pub fn resized(&mut self, width: u16, height: u16) -> Result<()> {
+        match &mut self.cast {
+            Some(cast) => cast.event("r", &format!("{width}x{height}")),
+            None => Ok(()),
+        }
+    }
+
+    pub fn finish(&mut self) -> Result<()> {
+        match &mut self.cast {
+            Some(cast) => {
+                cast.flush_output()?;
+                cast.file.flush()
+            }
+            None => Ok(()),
+        }
+    }
+}
+
+impl<W: Write> Write for Tee<W> {
+    fn write(&mut self, buf: &[u8]) -> Result<usize> {
+        let n = self.inner.write(buf)?;
+        if let Some(cast) = &mut self.cast {
+            cast.pending.extend_from_slice(&buf[..n]);
+        }
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        self.inner.flush()?;
+        match &mut self.cast {
+            Some(cast) => cast.flush_output(),
+            None => Ok(()),
+        }
+    }
+}
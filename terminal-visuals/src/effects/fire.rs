@@ -0,0 +1,110 @@
+use crossterm::event::KeyCode;
+use rand::Rng;
+
+use super::{whole_steps, Effect};
+use crate::palette::{fire, Theme};
+use crate::screen::{Cell, Frame};
+
+// heat lost per row climbed, as a fraction of the screen height; the flames
+// reach about two thirds of the way up
+const DECAY: f32 = 1.0;
+
+// the demoscene fire: the bottom row is re-lit randomly every step, and each
+// cell above takes the average heat of the cells below it, minus a little. The
+// heat buffer has two rows per cell, shown as half blocks or, with `h`
+// toggled off, one glyph per cell; it always uses the fire colors.
+pub struct Fire {
+    theme: Theme,
+    half_block: bool,
+    width: usize,
+    rows: usize,
+    heat: Vec<f32>,
+    // animation time towards the next step
+    pending: f32,
+}
+
+impl Fire {
+    pub fn new(theme: Theme) -> Self {
+        Self {
+            theme,
+            half_block: true,
+            width: 0,
+            rows: 0,
+            heat: Vec::new(),
+            pending: 0.0,
+        }
+    }
+
+    fn step(&mut self, rng: &mut impl Rng) {
+        let (w, rows) = (self.width, self.rows);
+        if w == 0 || rows < 2 {
+            return;
+        }
+        let bottom = (rows - 1) * w;
+        for heat in &mut self.heat[bottom..] {
+            *heat = if rng.gen_bool(0.6) {
+                rng.gen_range(0.7..1.0)
+            } else {
+                0.0
+            };
+        }
+        let decay = DECAY / rows as f32;
+        // top down, so each row still reads last step's rows below it
+        for y in 0..rows - 1 {
+            for x in 0..w {
+                let below = (y + 1) * w;
+                let farther = (y + 2).min(rows - 1) * w;
+                let sum = self.heat[below + x.saturating_sub(1)]
+                    + self.heat[below + x]
+                    + self.heat[below + (x + 1).min(w - 1)]
+                    + self.heat[farther + x];
+                self.heat[y * w + x] = (sum / 4.0 - decay).max(0.0);
+            }
+        }
+    }
+
+    fn heat_at(&self, x: u16, row: usize) -> f32 {
+        self.heat[row * self.width + x as usize]
+    }
+}
+
+impl Effect for Fire {
+    fn render(&mut self, frame: &mut Frame, t: f32, dt: f32, _palette_t: f32) {
+        let mut rng = rand::thread_rng();
+        for _ in 0..whole_steps(&mut self.pending, dt) {
+            self.step(&mut rng);
+        }
+
+        for y in 0..frame.height {
+            let (top, bottom) = (y as usize * 2, y as usize * 2 + 1);
+            for x in 0..frame.width {
+                let (top, bottom) = (self.heat_at(x, top), self.heat_at(x, bottom));
+                let cell = if self.half_block {
+                    Cell::half_block(fire(top, t), fire(bottom, t))
+                } else {
+                    let heat = (top + bottom) / 2.0;
+                    Cell::new(self.theme.glyph(heat), fire(heat, t))
+                };
+                frame.set(x, y, cell);
+            }
+        }
+    }
+
+    fn resize(&mut self, width: u16, height: u16) {
+        self.width = width as usize;
+        self.rows = height as usize * 2;
+        self.heat = vec![0.0; self.width * self.rows];
+        // let the flames climb before the first frame
+        let mut rng = rand::thread_rng();
+        for _ in 0..self.rows {
+            self.step(&mut rng);
+        }
+    }
+
+    fn key_pressed(&mut self, key: KeyCode) -> Option<String> {
+        if key == KeyCode::Char('h') {
+            self.half_block = !self.half_block;
+        }
+        None
+    }
+}
@@ -0,0 +1,455 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crossterm::event::{KeyCode, MouseButton, MouseEvent, MouseEventKind};
+use rayon::prelude::*;
+
+use super::Effect;
+use crate::palette::Theme;
+use crate::screen::{Cell, Frame};
+
+pub struct RenderSettings {
+    pub max_iter: i32,
+    // |z| past which a point counts as escaped; at least 2
+    pub bailout: f32,
+    pub smooth: bool,
+    // multiplies how fast the colors cycle; negative runs them backwards, 0
+    // holds them still
+    pub cycle_speed: f32,
+    // ordered dithering between neighbouring ramp glyphs in ASCII mode
+    pub dither: bool,
+    pub theme: Theme,
+}
+
+// palette phase per animation time unit at cycle speed 1
+const CYCLE_RATE: f32 = 0.1;
+// how much `[` and `]` change the cycle speed by
+const CYCLE_STEP: f32 = 0.25;
+
+#[derive(Clone, Copy, PartialEq)]
+enum Fractal {
+    Mandelbrot,
+    // c moves along a slow circle so the set morphs over time
+    Julia,
+}
+
+impl Fractal {
+    fn toggled(self) -> Self {
+        match self {
+            Fractal::Mandelbrot => Fractal::Julia,
+            Fractal::Julia => Fractal::Mandelbrot,
+        }
+    }
+
+    // (z0, c) for the pixel at `point` in the complex plane
+    fn seed(self, point: (f32, f32), t: f32) -> ((f32, f32), (f32, f32)) {
+        match self {
+            Fractal::Mandelbrot => ((0.0, 0.0), point),
+            Fractal::Julia => {
+                let angle = t * 0.05;
+                (point, (0.7885 * angle.cos(), 0.7885 * angle.sin()))
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Glyphs {
+    // one sample per cell, shaded through the ramp
+    Ascii,
+    // 2x4 samples per cell packed into a Unicode Braille pattern
+    Braille,
+    // two full-color samples per cell, one above the other
+    HalfBlock,
+}
+
+impl Glyphs {
+    // switches to `mode`, or back to ASCII if it's already on
+    fn toggled(self, mode: Glyphs) -> Self {
+        if self == mode {
+            Glyphs::Ascii
+        } else {
+            mode
+        }
+    }
+}
+
+// thresholds of the 4x4 Bayer matrix, in sixteenths
+const BAYER: [[u8; 4]; 4] = [[0, 8, 2, 10], [12, 4, 14, 6], [3, 11, 1, 9], [15, 7, 13, 5]];
+
+// the Bayer threshold under cell (x, y), in 0..1, for picking between the
+// ramp glyphs either side of a shade: a shade a third of the way from one
+// glyph to the next picks the next one in a third of the cells, spread out
+// evenly, so over an area the glyphs average out to the shade instead of
+// jumping between bands
+fn dither_threshold(x: u16, y: u16) -> f32 {
+    (BAYER[y as usize % 4][x as usize % 4] as f32 + 0.5) / 16.0
+}
+
+// dot bit for each (column, row) of a Braille cell, per the U+2800 block layout
+const BRAILLE_DOTS: [[u32; 4]; 2] = [[0x01, 0x02, 0x04, 0x40], [0x08, 0x10, 0x20, 0x80]];
+
+// how much `+`/`-` and each notch of the wheel zoom by
+const ZOOM_STEP: f32 = 1.25;
+
+// user-controlled window onto the complex plane
+#[derive(Clone, Copy)]
+struct View {
+    cx: f32,
+    cy: f32,
+    zoom: f32,
+}
+
+impl View {
+    // the Julia set is centred on the origin, the Mandelbrot set a bit left of it
+    fn home(fractal: Fractal) -> Self {
+        let cx = match fractal {
+            Fractal::Mandelbrot => -0.5,
+            Fractal::Julia => 0.0,
+        };
+        Self {
+            cx,
+            cy: 0.0,
+            zoom: 1.0,
+        }
+    }
+
+    // moves by a tenth of the visible height per step, whatever the zoom
+    fn pan(&mut self, dx: f32, dy: f32) {
+        self.cx += dx * 0.2 / self.zoom;
+        self.cy += dy * 0.2 / self.zoom;
+    }
+
+    // the point of the plane shown at (`x`, `y`) in cells, the same mapping
+    // `Scene::sample` makes, on a terminal of `size`
+    fn point(&self, x: f32, y: f32, (width, height): (u16, u16)) -> (f32, f32) {
+        let aspect = terminal_aspect(width, height);
+        (
+            (x / width as f32 - 0.5) * 3.5 * aspect / self.zoom + self.cx,
+            (y / height as f32 - 0.5) * 2.0 / self.zoom + self.cy,
+        )
+    }
+
+    // moves the view so the point under (`from`) ends up under (`to`), which
+    // drags the picture along with the cursor
+    fn drag(&mut self, from: (f32, f32), to: (f32, f32), size: (u16, u16)) {
+        let (x0, y0) = self.point(from.0, from.1, size);
+        let (x1, y1) = self.point(to.0, to.1, size);
+        self.cx += x0 - x1;
+        self.cy += y0 - y1;
+    }
+
+    // zooms by `factor` about (`x`, `y`) in cells, so the point shown there
+    // stays put
+    fn zoom_at(&mut self, x: f32, y: f32, factor: f32, size: (u16, u16)) {
+        let (px, py) = self.point(x, y, size);
+        self.zoom *= factor;
+        self.cx = px + (self.cx - px) / factor;
+        self.cy = py + (self.cy - py) / factor;
+    }
+}
+
+// number of iterations before |z| passes `bailout` (max_iter if it never does)
+// and |z|^2 at that point
+fn escape_time(
+    (mut zr, mut zi): (f32, f32),
+    (cr, ci): (f32, f32),
+    max_iter: i32,
+    bailout: f32,
+) -> (i32, f32) {
+    let bailout_sqr = bailout * bailout;
+    let mut iter = 0;
+    while zr * zr + zi * zi <= bailout_sqr && iter < max_iter {
+        let new_zr = zr * zr - zi * zi + cr;
+        let new_zi = 2.0 * zr * zi + ci;
+        zr = new_zr;
+        zi = new_zi;
+        iter += 1;
+    }
+    (iter, zr * zr + zi * zi)
+}
+
+// fractional iteration count, iter + 1 - log2(ln|z|), which varies continuously
+// across the iteration bands; it assumes z is already far out, so the larger the
+// bailout the less banding is left near the set's edge
+fn smooth_iter(iter: i32, norm_sqr: f32) -> f32 {
+    let log_z = 0.5 * norm_sqr.ln();
+    // ln(ln|z|) blows up as |z| approaches 1; fall back to the integer count
+    if log_z <= f32::EPSILON {
+        return iter as f32;
+    }
+    iter as f32 + 1.0 - log_z.ln() / std::f32::consts::LN_2
+}
+
+// everything needed to color a point of the fractal at one moment
+struct Scene<'a> {
+    fractal: Fractal,
+    view: View,
+    settings: &'a RenderSettings,
+    // drives the Julia morph
+    t: f32,
+    // how far the colors have cycled, apart from the morph
+    phase: f32,
+}
+
+impl Scene<'_> {
+    // shade and color at (u, v) in 0..1 screen space; `aspect` stretches the
+    // horizontal extent to match the output's shape
+    fn sample(&self, u: f32, v: f32, aspect: f32) -> (f32, (u8, u8, u8)) {
+        let View { cx, cy, zoom } = self.view;
+        let max_iter = self.settings.max_iter;
+
+        let imag = (v - 0.5) * 2.0 / zoom + cy;
+        let real = ((u - 0.5) * 3.5 * aspect) / zoom + cx;
+
+        let (z0, c) = self.fractal.seed((real, imag), self.t);
+        let (iter, norm_sqr) = escape_time(z0, c, max_iter, self.settings.bailout);
+
+        let shade = if iter == max_iter {
+            0.0
+        } else if self.settings.smooth {
+            (smooth_iter(iter, norm_sqr) / max_iter as f32).clamp(0.0, 1.0)
+        } else {
+            iter as f32 / max_iter as f32
+        };
+
+        // color based on iteration and position
+        let phase = u * 0.3 + v * 0.2 + self.phase;
+        (shade, self.settings.theme.color(shade, phase))
+    }
+}
+
+// terminal cells are usually taller than they are wide, so compensate a bit
+fn terminal_aspect(width: u16, height: u16) -> f32 {
+    if height > 0 {
+        (width as f32 / height as f32) * 0.5
+    } else {
+        1.0
+    }
+}
+
+fn draw_frame(frame: &mut Frame, glyphs: Glyphs, scene: &Scene) {
+    // ASCII fractal with color cycling
+    let (width, height) = (frame.width, frame.height);
+    let (w, h) = (width as f32, height as f32);
+    let aspect = terminal_aspect(width, height);
+
+    // shade and color at a point given in cell units
+    let sample = |x: f32, y: f32| scene.sample(x / w, y / h, aspect);
+
+    if width == 0 {
+        return;
+    }
+    // rows are independent, so they're computed in parallel
+    frame
+        .cells
+        .par_chunks_mut(width as usize)
+        .enumerate()
+        .for_each(|(y, row)| {
+            let y = y as u16;
+            for (x, cell) in (0..width).zip(row) {
+                *cell = match glyphs {
+                    Glyphs::Ascii => {
+                        let (shade, color) = sample(x as f32, y as f32);
+                        let theme = &scene.settings.theme;
+                        let glyph = if scene.settings.dither {
+                            theme.ramp.dithered_glyph(shade, dither_threshold(x, y))
+                        } else {
+                            theme.glyph(shade)
+                        };
+                        Cell::new(glyph, color)
+                    }
+                    Glyphs::Braille => braille_cell(x, y, &scene.settings.theme, &sample),
+                    Glyphs::HalfBlock => {
+                        let (_, top) = sample(x as f32, y as f32);
+                        let (_, bottom) = sample(x as f32, y as f32 + 0.5);
+                        Cell::half_block(top, bottom)
+                    }
+                };
+            }
+        });
+}
+
+// renders the region currently shown in a `cols` x `rows` terminal at `width`
+// pixels across, with square pixels, straight from the color computation
+fn save_png(scene: &Scene, cols: u16, rows: u16, width: u32) -> image::ImageResult<String> {
+    let aspect = terminal_aspect(cols, rows);
+    // the view spans 3.5 * aspect horizontally for every 2 vertically
+    let height = ((width as f32 * 2.0 / (3.5 * aspect)).round() as u32).max(1);
+    let mut pixels = Vec::with_capacity(width as usize * height as usize * 3);
+    for y in 0..height {
+        for x in 0..width {
+            let u = (x as f32 + 0.5) / width as f32;
+            let v = (y as f32 + 0.5) / height as f32;
+            let (_, (r, g, b)) = scene.sample(u, v, aspect);
+            pixels.extend([r, g, b]);
+        }
+    }
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis())
+        .unwrap_or(0);
+    let path = format!("fractal_{secs}.png");
+    image_io::write_png_rgb8(&path, width, height, &pixels)?;
+    Ok(path)
+}
+
+// a dot is raised where the ramp would draw something past its first (blank)
+// glyph; the cell takes the average color of its raised dots
+fn braille_cell(
+    x: u16,
+    y: u16,
+    theme: &Theme,
+    sample: &impl Fn(f32, f32) -> (f32, (u8, u8, u8)),
+) -> Cell {
+    let threshold = theme.ramp.inked_level();
+    let mut bits = 0;
+    let mut sum = [0u32; 3];
+    let mut lit = 0;
+    for (col, dots) in BRAILLE_DOTS.iter().enumerate() {
+        for (row, &dot) in dots.iter().enumerate() {
+            let (shade, (r, g, b)) =
+                sample(x as f32 + col as f32 / 2.0, y as f32 + row as f32 / 4.0);
+            if shade >= threshold {
+                bits |= dot;
+                sum[0] += r as u32;
+                sum[1] += g as u32;
+                sum[2] += b as u32;
+                lit += 1;
+            }
+        }
+    }
+    // an empty cell stays black
+    let n = lit.max(1);
+    let color = ((sum[0] / n) as u8, (sum[1] / n) as u8, (sum[2] / n) as u8);
+    Cell::new(char::from_u32(0x2800 + bits).unwrap(), color)
+}
+
+// Mandelbrot/Julia explorer: `j` switches sets, `b` Braille, `h` half blocks,
+// arrows/`+`/`-`/`r` move the view, as do dragging and the wheel, and `s`
+// saves a PNG; `[`/`]` slow down and speed up the color cycling, `c` reverses
+// it and `m` freezes the Julia morph
+pub struct FractalEffect {
+    settings: RenderSettings,
+    png_width: u32,
+    fractal: Fractal,
+    glyphs: Glyphs,
+    view: View,
+    morph: bool,
+    // the palette phase is the palette time at the cycle speed plus this,
+    // which changing the speed shifts so the colors don't jump
+    phase_offset: f32,
+    // what was last drawn, so a PNG captures exactly that; `t` is the morph's
+    // own clock, which only runs while it's on
+    t: f32,
+    palette_t: f32,
+    phase: f32,
+    size: (u16, u16),
+    // the cell the left button was last seen over while held
+    drag_from: Option<(u16, u16)>,
+}
+
+impl FractalEffect {
+    pub fn new(settings: RenderSettings, png_width: u32) -> Self {
+        let fractal = Fractal::Mandelbrot;
+        Self {
+            settings,
+            png_width,
+            fractal,
+            glyphs: Glyphs::Ascii,
+            view: View::home(fractal),
+            morph: true,
+            phase_offset: 0.0,
+            t: 0.0,
+            palette_t: 0.0,
+            phase: 0.0,
+            size: (0, 0),
+            drag_from: None,
+        }
+    }
+
+    fn scene(&self) -> Scene<'_> {
+        Scene {
+            fractal: self.fractal,
+            view: self.view,
+            settings: &self.settings,
+            t: self.t,
+            phase: self.phase,
+        }
+    }
+
+    fn set_cycle_speed(&mut self, speed: f32) {
+        self.phase_offset += self.palette_t * (self.settings.cycle_speed - speed) * CYCLE_RATE;
+        self.settings.cycle_speed = speed;
+    }
+}
+
+impl Effect for FractalEffect {
+    fn render(&mut self, frame: &mut Frame, _t: f32, dt: f32, palette_t: f32) {
+        if self.morph {
+            self.t += dt;
+        }
+        self.palette_t = palette_t;
+        self.phase = palette_t * self.settings.cycle_speed * CYCLE_RATE + self.phase_offset;
+        self.size = (frame.width, frame.height);
+        draw_frame(frame, self.glyphs, &self.scene());
+    }
+
+    fn key_pressed(&mut self, key: KeyCode) -> Option<String> {
+        match key {
+            KeyCode::Char('j') => {
+                self.fractal = self.fractal.toggled();
+                self.view = View::home(self.fractal);
+            }
+            KeyCode::Char('b') => self.glyphs = self.glyphs.toggled(Glyphs::Braille),
+            KeyCode::Char('h') => self.glyphs = self.glyphs.toggled(Glyphs::HalfBlock),
+            KeyCode::Left => self.view.pan(-1.0, 0.0),
+            KeyCode::Right => self.view.pan(1.0, 0.0),
+            KeyCode::Up => self.view.pan(0.0, -1.0),
+            KeyCode::Down => self.view.pan(0.0, 1.0),
+            KeyCode::Char('+') | KeyCode::Char('=') => self.view.zoom *= ZOOM_STEP,
+            KeyCode::Char('-') => self.view.zoom /= ZOOM_STEP,
+            KeyCode::Char('r') => self.view = View::home(self.fractal),
+            KeyCode::Char('[') => self.set_cycle_speed(self.settings.cycle_speed - CYCLE_STEP),
+            KeyCode::Char(']') => self.set_cycle_speed(self.settings.cycle_speed + CYCLE_STEP),
+            KeyCode::Char('c') => self.set_cycle_speed(-self.settings.cycle_speed),
+            KeyCode::Char('m') => self.morph = !self.morph,
+            KeyCode::Char('s') => {
+                let (cols, rows) = self.size;
+                return Some(match save_png(&self.scene(), cols, rows, self.png_width) {
+                    Ok(path) => format!("Saved {path}"),
+                    Err(err) => format!("Failed to save PNG: {err}"),
+                });
+            }
+            _ => {}
+        }
+        None
+    }
+
+    fn mouse(&mut self, event: MouseEvent) {
+        let at = (event.column, event.row);
+        let cell = |(x, y): (u16, u16)| (x as f32, y as f32);
+        match event.kind {
+            MouseEventKind::Down(MouseButton::Left) => self.drag_from = Some(at),
+            MouseEventKind::Drag(MouseButton::Left) => {
+                if let Some(from) = self.drag_from {
+                    self.view.drag(cell(from), cell(at), self.size);
+                }
+                self.drag_from = Some(at);
+            }
+            MouseEventKind::Up(MouseButton::Left) => self.drag_from = None,
+            MouseEventKind::ScrollUp => {
+                self.view
+                    .zoom_at(at.0 as f32, at.1 as f32, ZOOM_STEP, self.size)
+            }
+            MouseEventKind::ScrollDown => {
+                self.view
+                    .zoom_at(at.0 as f32, at.1 as f32, 1.0 / ZOOM_STEP, self.size)
+            }
+            _ => {}
+        }
+    }
+
+    fn reversible(&self) -> bool {
+        true
+    }
+}
@@ -0,0 +1,110 @@
+use crossterm::event::KeyCode;
+use rand::Rng;
+
+use super::{whole_steps, Effect};
+use crate::screen::{Cell, Frame};
+
+// fraction of cells alive after a random seed
+const SEED_DENSITY: f64 = 0.3;
+// generations for a cell to fade from its birth color to the old-age color
+const FADE_GENERATIONS: f32 = 40.0;
+
+// Conway's Game of Life on a torus the size of the terminal; one generation per
+// animation step, so pausing freezes it and `--speed` sets the pace. `r` reseeds.
+#[derive(Default)]
+pub struct Life {
+    width: u16,
+    height: u16,
+    alive: Vec<bool>,
+    // generations each live cell has survived
+    age: Vec<u16>,
+    next: Vec<bool>,
+    // animation time towards the next generation
+    pending: f32,
+}
+
+impl Life {
+    fn reseed(&mut self, width: u16, height: u16) {
+        let mut rng = rand::thread_rng();
+        let len = width as usize * height as usize;
+        self.width = width;
+        self.height = height;
+        self.alive = (0..len).map(|_| rng.gen_bool(SEED_DENSITY)).collect();
+        self.age = vec![0; len];
+        self.next = vec![false; len];
+    }
+
+    fn live_neighbours(&self, x: u16, y: u16) -> usize {
+        let (w, h) = (self.width as i32, self.height as i32);
+        let mut count = 0;
+        for dy in -1..=1 {
+            for dx in -1..=1 {
+                if (dx, dy) == (0, 0) {
+                    continue;
+                }
+                let nx = (x as i32 + dx).rem_euclid(w);
+                let ny = (y as i32 + dy).rem_euclid(h);
+                if self.alive[(ny * w + nx) as usize] {
+                    count += 1;
+                }
+            }
+        }
+        count
+    }
+
+    fn step(&mut self) {
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let i = y as usize * self.width as usize + x as usize;
+                let n = self.live_neighbours(x, y);
+                self.next[i] = n == 3 || (n == 2 && self.alive[i]);
+            }
+        }
+        std::mem::swap(&mut self.alive, &mut self.next);
+        for (age, &alive) in self.age.iter_mut().zip(&self.alive) {
+            *age = if alive { age.saturating_add(1) } else { 0 };
+        }
+        // start over rather than showing an empty screen
+        if !self.alive.contains(&true) {
+            self.reseed(self.width, self.height);
+        }
+    }
+}
+
+// bright yellow-white at birth, fading to a dim green
+fn age_color(age: u16) -> (u8, u8, u8) {
+    let f = (age as f32 / FADE_GENERATIONS).min(1.0);
+    let lerp = |a: f32, b: f32| (a + (b - a) * f) as u8;
+    (lerp(255.0, 20.0), lerp(255.0, 120.0), lerp(160.0, 40.0))
+}
+
+impl Effect for Life {
+    fn render(&mut self, frame: &mut Frame, _t: f32, dt: f32, _palette_t: f32) {
+        for _ in 0..whole_steps(&mut self.pending, dt) {
+            self.step();
+        }
+
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let i = y as usize * self.width as usize + x as usize;
+                let cell = if self.alive[i] {
+                    Cell::new('#', age_color(self.age[i]))
+                } else {
+                    Cell::default()
+                };
+                frame.set(x, y, cell);
+            }
+        }
+    }
+
+    fn resize(&mut self, width: u16, height: u16) {
+        self.reseed(width, height);
+    }
+
+    fn key_pressed(&mut self, key: KeyCode) -> Option<String> {
+        if key == KeyCode::Char('r') {
+            self.reseed(self.width, self.height);
+        }
+        None
+    }
+}
@@ -0,0 +1,64 @@
+use crossterm::event::{KeyCode, MouseEvent};
+
+use crate::screen::Frame;
+
+pub mod fire;
+pub mod fractal;
+pub mod life;
+pub mod plasma;
+pub mod rain;
+pub mod starfield;
+pub mod tunnel;
+
+pub use fire::Fire;
+pub use fractal::FractalEffect;
+pub use life::Life;
+pub use plasma::Plasma;
+pub use rain::Rain;
+pub use starfield::Starfield;
+pub use tunnel::Tunnel;
+
+// animation time per step of the effects that simulate in whole steps, one
+// 60 fps frame's worth; and the most steps one frame catches up on, so a frame
+// that stalled doesn't make the next spend as long again catching up
+pub const STEP: f32 = 0.1;
+const MAX_STEPS: u32 = 8;
+
+// how many whole steps `dt` more animation time makes, with what's left over
+// carried in `pending` for the next frame
+pub fn whole_steps(pending: &mut f32, dt: f32) -> u32 {
+    *pending += dt.max(0.0);
+    let steps = (*pending / STEP) as u32;
+    *pending -= steps as f32 * STEP;
+    steps.min(MAX_STEPS)
+}
+
+// one selectable visual; the main loop owns timing, the screen and the shared keys
+pub trait Effect {
+    // `t` drives the motion and `palette_t` the color cycling; both are in
+    // animation time units, and the same clock unless `--palette-sync wall`.
+    // `dt` is how far `t` moved since the last frame, 0 while paused and
+    // negative stepping back, for effects that move by it. Only the active
+    // effect is rendered, so one moved by `dt` doesn't advance while hidden
+    // and carries on from where it was when switched back to
+    fn render(&mut self, frame: &mut Frame, t: f32, dt: f32, palette_t: f32);
+
+    // called before the first frame and whenever the terminal changes size, so
+    // per-cell state can be reallocated
+    fn resize(&mut self, _width: u16, _height: u16) {}
+
+    // keys the main loop doesn't handle itself; a returned message is printed
+    // once the terminal is restored
+    fn key_pressed(&mut self, _key: KeyCode) -> Option<String> {
+        None
+    }
+
+    // clicks, drags and the wheel, in cells from the top left
+    fn mouse(&mut self, _event: MouseEvent) {}
+
+    // whether a negative `dt` steps it back: true for effects drawn straight
+    // from `t`, not for simulations, which only go forward
+    fn reversible(&self) -> bool {
+        false
+    }
+}
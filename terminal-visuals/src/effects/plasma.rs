@@ -0,0 +1,69 @@
+use crossterm::event::KeyCode;
+
+use super::Effect;
+use crate::palette::Theme;
+use crate::screen::{Cell, Frame};
+
+// classic demoscene plasma: a sum of sines over position and time; `h` toggles
+// half blocks
+pub struct Plasma {
+    theme: Theme,
+    half_block: bool,
+}
+
+impl Plasma {
+    pub fn new(theme: Theme) -> Self {
+        Self {
+            theme,
+            half_block: false,
+        }
+    }
+}
+
+// 0..1 at (px, py) in units of a cell's width, around the centre (cx, cy)
+fn shade_at(px: f32, py: f32, (cx, cy): (f32, f32), t: f32) -> f32 {
+    let dist = ((px - cx).powi(2) + (py - cy).powi(2)).sqrt();
+    let v =
+        (px * 0.1).sin() + (py * 0.1).sin() + ((px + py) * 0.1 + t).sin() + (dist * 0.1 - t).sin();
+    (v + 4.0) / 8.0
+}
+
+impl Effect for Plasma {
+    fn render(&mut self, frame: &mut Frame, t: f32, _dt: f32, palette_t: f32) {
+        let centre = (frame.width as f32 / 2.0, frame.height as f32);
+        for y in 0..frame.height {
+            for x in 0..frame.width {
+                // cells are about twice as tall as wide
+                let (px, py) = (x as f32, y as f32 * 2.0);
+                let cell = if self.half_block {
+                    // each half of the cell is about a cell's width tall
+                    let top = shade_at(px, py, centre, t);
+                    let bottom = shade_at(px, py + 1.0, centre, t);
+                    Cell::half_block(
+                        self.theme.color(top, palette_t * 0.1),
+                        self.theme.color(bottom, palette_t * 0.1),
+                    )
+                } else {
+                    let shade = shade_at(px, py, centre, t);
+                    // skip the blank glyph so the plasma fills every cell
+                    Cell::new(
+                        self.theme.ramp.inked_glyph(shade),
+                        self.theme.color(shade, palette_t * 0.1),
+                    )
+                };
+                frame.set(x, y, cell);
+            }
+        }
+    }
+
+    fn key_pressed(&mut self, key: KeyCode) -> Option<String> {
+        if key == KeyCode::Char('h') {
+            self.half_block = !self.half_block;
+        }
+        None
+    }
+
+    fn reversible(&self) -> bool {
+        true
+    }
+}
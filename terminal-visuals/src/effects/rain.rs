@@ -0,0 +1,115 @@
+use rand::Rng;
+
+use super::Effect;
+use crate::screen::{Cell, Frame};
+
+// half-width katakana and digits, all one cell wide
+const GLYPHS: &[char] = &[
+    'ｦ', 'ｧ', 'ｨ', 'ｩ', 'ｪ', 'ｫ', 'ｬ', 'ｭ', 'ｮ', 'ｯ', 'ｱ', 'ｲ', 'ｳ', 'ｴ', 'ｵ', 'ｶ', 'ｷ', 'ｸ', 'ｹ',
+    'ｺ', 'ｻ', 'ｼ', 'ｽ', 'ｾ', 'ｿ', 'ﾀ', 'ﾁ', 'ﾂ', 'ﾃ', 'ﾄ', 'ﾅ', 'ﾆ', 'ﾇ', 'ﾈ', 'ﾉ', 'ﾊ', 'ﾋ', 'ﾌ',
+    'ﾍ', 'ﾎ', 'ﾏ', 'ﾐ', 'ﾑ', 'ﾒ', 'ﾓ', 'ﾔ', 'ﾕ', 'ﾖ', 'ﾗ', 'ﾘ', 'ﾙ', 'ﾚ', 'ﾛ', 'ﾜ', 'ﾝ', '0', '1',
+    '2', '3', '4', '5', '6', '7', '8', '9',
+];
+
+// rows per animation time unit
+const MIN_SPEED: f32 = 0.15;
+const MAX_SPEED: f32 = 0.6;
+// fraction of glyphs swapped for another per animation time unit
+const FLICKER_RATE: f64 = 0.02;
+
+// one falling stream; `head` is the row of its leading glyph, negative while it
+// waits to start
+struct Stream {
+    head: f32,
+    speed: f32,
+    length: f32,
+}
+
+impl Stream {
+    fn random(height: u16, rng: &mut impl Rng) -> Self {
+        let height = height.max(1) as f32;
+        Self {
+            head: -rng.gen_range(0.0..height),
+            speed: rng.gen_range(MIN_SPEED..MAX_SPEED),
+            length: rng.gen_range(height * 0.25..height * 0.9).max(3.0),
+        }
+    }
+}
+
+// "digital rain": per-column streams of glyphs falling at their own speed, with
+// a bright head and a trail fading to dark green
+#[derive(Default)]
+pub struct Rain {
+    width: u16,
+    height: u16,
+    streams: Vec<Stream>,
+    glyphs: Vec<char>,
+}
+
+impl Rain {
+    fn step(&mut self, dt: f32, rng: &mut impl Rng) {
+        for stream in &mut self.streams {
+            stream.head += stream.speed * dt;
+            if stream.head - stream.length > self.height as f32 {
+                *stream = Stream::random(self.height, rng);
+            }
+        }
+        let swaps = (self.glyphs.len() as f64 * FLICKER_RATE * dt as f64).ceil() as usize;
+        for _ in 0..swaps.min(self.glyphs.len()) {
+            let i = rng.gen_range(0..self.glyphs.len());
+            self.glyphs[i] = GLYPHS[rng.gen_range(0..GLYPHS.len())];
+        }
+    }
+}
+
+// white-green at the head, then dimming to black over the trail
+fn trail_color(behind: f32, length: f32) -> (u8, u8, u8) {
+    if behind < 1.0 {
+        return (200, 255, 200);
+    }
+    let f = 1.0 - behind / length;
+    (
+        (20.0 * f) as u8,
+        (40.0 + 200.0 * f) as u8,
+        (20.0 + 50.0 * f) as u8,
+    )
+}
+
+impl Effect for Rain {
+    fn render(&mut self, frame: &mut Frame, _t: f32, dt: f32, _palette_t: f32) {
+        if dt > 0.0 {
+            self.step(dt, &mut rand::thread_rng());
+        }
+
+        for x in 0..self.width {
+            let stream = &self.streams[x as usize];
+            for y in 0..self.height {
+                let behind = stream.head - y as f32;
+                let cell = if (0.0..stream.length).contains(&behind) {
+                    let glyph = self.glyphs[y as usize * self.width as usize + x as usize];
+                    Cell::new(glyph, trail_color(behind, stream.length))
+                } else {
+                    Cell::default()
+                };
+                frame.set(x, y, cell);
+            }
+        }
+    }
+
+    fn resize(&mut self, width: u16, height: u16) {
+        let mut rng = rand::thread_rng();
+        self.width = width;
+        self.height = height;
+        // already mid-fall, so the first frame isn't empty
+        self.streams = (0..width)
+            .map(|_| {
+                let mut stream = Stream::random(height, &mut rng);
+                stream.head += height as f32;
+                stream
+            })
+            .collect();
+        self.glyphs = (0..width as usize * height as usize)
+            .map(|_| GLYPHS[rng.gen_range(0..GLYPHS.len())])
+            .collect();
+    }
+}
@@ -0,0 +1,103 @@
+use rand::Rng;
+
+use super::Effect;
+use crate::palette::Theme;
+use crate::screen::{Cell, Frame};
+
+// depth units covered per animation time unit; stars start at depth 1
+const SPEED: f32 = 0.05;
+// stars closer than this have passed the camera
+const NEAR: f32 = 0.01;
+// one star per this many cells
+const CELLS_PER_STAR: usize = 6;
+
+struct Star {
+    x: f32,
+    y: f32,
+    z: f32,
+}
+
+impl Star {
+    fn random(z: f32, rng: &mut impl Rng) -> Self {
+        Self {
+            x: rng.gen_range(-1.0..1.0),
+            y: rng.gen_range(-1.0..1.0),
+            z,
+        }
+    }
+}
+
+// flying through a field of stars: each one approaches the viewer, is projected
+// with a perspective divide and brightens as it nears, then respawns far away
+pub struct Starfield {
+    theme: Theme,
+    width: u16,
+    height: u16,
+    stars: Vec<Star>,
+}
+
+impl Starfield {
+    pub fn new(theme: Theme) -> Self {
+        Self {
+            theme,
+            width: 0,
+            height: 0,
+            stars: Vec::new(),
+        }
+    }
+
+    // cell coordinates, or `None` once the star has left the screen
+    fn project(&self, star: &Star) -> Option<(u16, u16)> {
+        let (cx, cy) = (self.width as f32 / 2.0, self.height as f32 / 2.0);
+        // cells are about twice as tall as wide
+        let sx = cx + star.x / star.z * cx;
+        let sy = cy + star.y / star.z * cx / 2.0;
+        let on_screen =
+            (0.0..self.width as f32).contains(&sx) && (0.0..self.height as f32).contains(&sy);
+        on_screen.then_some((sx as u16, sy as u16))
+    }
+}
+
+impl Effect for Starfield {
+    fn render(&mut self, frame: &mut Frame, _t: f32, dt: f32, _palette_t: f32) {
+        let mut rng = rand::thread_rng();
+        for i in 0..self.stars.len() {
+            self.stars[i].z -= SPEED * dt.max(0.0);
+            if self.stars[i].z < NEAR || self.project(&self.stars[i]).is_none() {
+                self.stars[i] = Star::random(1.0, &mut rng);
+            }
+        }
+
+        // the brightest star wins where several land in one cell
+        let mut brightness = vec![0.0f32; self.width as usize * self.height as usize];
+        for star in &self.stars {
+            if let Some((x, y)) = self.project(star) {
+                let i = y as usize * self.width as usize + x as usize;
+                brightness[i] = brightness[i].max(1.0 - star.z);
+            }
+        }
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let b = brightness[y as usize * self.width as usize + x as usize];
+                let cell = if b > 0.0 {
+                    // skip the blank glyph so even the farthest stars show
+                    let v = (60.0 + 195.0 * b) as u8;
+                    Cell::new(self.theme.ramp.inked_glyph(b), (v, v, v.max(150)))
+                } else {
+                    Cell::default()
+                };
+                frame.set(x, y, cell);
+            }
+        }
+    }
+
+    fn resize(&mut self, width: u16, height: u16) {
+        let mut rng = rand::thread_rng();
+        self.width = width;
+        self.height = height;
+        let count = width as usize * height as usize / CELLS_PER_STAR;
+        self.stars = (0..count)
+            .map(|_| Star::random(rng.gen_range(NEAR..1.0), &mut rng))
+            .collect();
+    }
+}
@@ -0,0 +1,93 @@
+use std::f32::consts::PI;
+
+use crossterm::event::KeyCode;
+
+use super::Effect;
+use crate::palette::Theme;
+use crate::screen::{Cell, Frame};
+
+// texture coordinates scrolled per animation time unit: into the tunnel, and
+// around it
+const ZOOM_SPEED: f32 = 0.05;
+const SPIN_SPEED: f32 = 0.02;
+// texture repeats along the depth and around the circumference
+const DEPTH_TILES: f32 = 4.0;
+const AROUND_TILES: f32 = 8.0;
+
+// the infinite tunnel: each cell's polar coordinates around the centre index a
+// procedural texture, depth by the inverse radius, so the walls rush towards the
+// viewer; `h` toggles half blocks, `t` switches between checkers and stripes
+pub struct Tunnel {
+    theme: Theme,
+    half_block: bool,
+    stripes: bool,
+}
+
+impl Tunnel {
+    pub fn new(theme: Theme) -> Self {
+        Self {
+            theme,
+            half_block: false,
+            stripes: false,
+        }
+    }
+
+    // 0..1 at (px, py) in units of a cell's width, around the centre (cx, cy);
+    // `scale` is the distance to the nearest screen edge
+    fn shade_at(&self, px: f32, py: f32, (cx, cy): (f32, f32), scale: f32, t: f32) -> f32 {
+        let (dx, dy) = ((px - cx) / scale, (py - cy) / scale);
+        let radius = (dx * dx + dy * dy).sqrt().max(1e-3);
+        let u = (1.0 / radius + t * ZOOM_SPEED) * DEPTH_TILES;
+        let v = (dy.atan2(dx) / PI + t * SPIN_SPEED) * AROUND_TILES;
+        let texel = if self.stripes {
+            0.5 + 0.25 * (u * PI).sin() + 0.25 * (v * PI).sin()
+        } else if (u.floor() + v.floor()) as i32 % 2 == 0 {
+            0.85
+        } else {
+            0.35
+        };
+        // the far end fades into the dark
+        texel * radius.min(1.0)
+    }
+}
+
+impl Effect for Tunnel {
+    fn render(&mut self, frame: &mut Frame, t: f32, _dt: f32, palette_t: f32) {
+        let centre = (frame.width as f32 / 2.0, frame.height as f32);
+        let scale = centre.0.min(centre.1).max(1.0);
+        for y in 0..frame.height {
+            for x in 0..frame.width {
+                // cells are about twice as tall as wide
+                let (px, py) = (x as f32, y as f32 * 2.0);
+                let cell = if self.half_block {
+                    let top = self.shade_at(px, py, centre, scale, t);
+                    let bottom = self.shade_at(px, py + 1.0, centre, scale, t);
+                    Cell::half_block(
+                        self.theme.color(top, palette_t * 0.1),
+                        self.theme.color(bottom, palette_t * 0.1),
+                    )
+                } else {
+                    let shade = self.shade_at(px, py, centre, scale, t);
+                    Cell::new(
+                        self.theme.glyph(shade),
+                        self.theme.color(shade, palette_t * 0.1),
+                    )
+                };
+                frame.set(x, y, cell);
+            }
+        }
+    }
+
+    fn key_pressed(&mut self, key: KeyCode) -> Option<String> {
+        match key {
+            KeyCode::Char('h') => self.half_block = !self.half_block,
+            KeyCode::Char('t') => self.stripes = !self.stripes,
+            _ => {}
+        }
+        None
+    }
+
+    fn reversible(&self) -> bool {
+        true
+    }
+}
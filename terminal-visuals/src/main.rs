@@ -1,118 +1,339 @@
-use std::io::{stdout, Write, Result};
-use std::thread;
+mod cast;
+mod effects;
+mod palette;
+mod ramp;
+mod screen;
+
+use std::io::{stdout, Result};
+use std::path::PathBuf;
 use std::time::{Duration, Instant};
 
+use clap::{error::ErrorKind, CommandFactory, Parser, ValueEnum};
 use crossterm::{
     cursor,
+    event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode},
     execute,
-    style::{Color, Print, ResetColor, SetForegroundColor},
-    terminal::{self, Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen},
+    style::ResetColor,
+    terminal::{self, EnterAlternateScreen, LeaveAlternateScreen},
 };
 
-fn draw_frame<W: Write>(out: &mut W, t: f32, width: u16, height: u16) -> Result<()> {
-    // animated ASCII Mandelbrot zoom with color cycling
-    let (w, h) = (width as f32, height as f32);
-    // terminal cells are usually taller than they are wide, so compensate a bit
-    let aspect = if h > 0.0 { (w / h) * 0.5 } else { 1.0 };
-
-    let zoom = 1.0 + 0.5 * (t * 0.2).sin();
-    let cx = -0.5 + 0.3 * (t * 0.05).cos();
-    let cy = 0.0 + 0.3 * (t * 0.05).sin();
-
-    let max_iter: i32 = 64;
+use cast::{Cast, Tee};
+use effects::{
+    fractal::RenderSettings, Effect, Fire, FractalEffect, Life, Plasma, Rain, Starfield, Tunnel,
+    STEP,
+};
+use palette::{Coloring, Gradient, Palette, Theme};
+use ramp::{Ramp, DEFAULT_RAMP};
+use screen::{write_lines, ColorMode, Frame, Screen};
 
-    for y in 0..height {
-        execute!(out, cursor::MoveTo(0, y))?;
-        let imag = ((y as f32 / h) - 0.5) * 2.0 / zoom + cy;
+#[derive(Parser)]
+#[command(about = "Animated visuals in the terminal")]
+struct Args {
+    /// Escape-time iteration cap; lower it to keep slow terminals smooth
+    #[arg(long, default_value_t = 64, value_parser = clap::value_parser!(i32).range(1..))]
+    max_iter: i32,
+    /// Escape radius of the fractal iteration, at least 2; larger values make `--smooth` band-free near the set
+    #[arg(long, default_value_t = 2.0)]
+    bailout: f32,
+    /// Frame rate cap
+    #[arg(long, default_value_t = 60, value_parser = clap::value_parser!(u32).range(1..))]
+    fps: u32,
+    /// Animation speed multiplier: every effect moves by the real time since the last frame times this, whatever the frame rate; 0 freezes the motion
+    #[arg(long, default_value_t = 1.0)]
+    speed: f32,
+    /// Continuous escape-time coloring instead of the banded iteration count
+    #[arg(long)]
+    smooth: bool,
+    /// How fast the fractal's colors cycle, apart from its animation; negative reverses them, 0 stops them
+    #[arg(long, default_value_t = 1.0, allow_negative_numbers = true)]
+    cycle_speed: f32,
+    /// Dither the fractal's ASCII shading with a Bayer matrix, so glyph bands blend into each other
+    #[arg(long)]
+    dither: bool,
+    /// Color palette
+    #[arg(long, value_enum, default_value_t = Palette::Rainbow)]
+    palette: Palette,
+    /// Color with a gradient instead of `--palette`: `position:rrggbb` stops from 0 to 1, e.g. `0:000000,0.5:ff4000,1:ffffff`
+    #[arg(long, value_name = "STOPS", conflicts_with = "gradient_file")]
+    gradient: Option<Gradient>,
+    /// Read the `--gradient` stops from this file, one or more per line
+    #[arg(long, value_name = "PATH")]
+    gradient_file: Option<PathBuf>,
+    /// Glyphs that shades are mapped onto, each at the shade its ink coverage looks like; glyphs without a known coverage go from empty to dense in even steps
+    #[arg(long, default_value = DEFAULT_RAMP)]
+    ramp: Ramp,
+    /// Coverages for the `--ramp` glyphs, one glyph and the share of the cell it inks per line, e.g. `. 0.025`; they take the place of the built-in ones
+    #[arg(long, value_name = "PATH")]
+    ramp_weights: Option<PathBuf>,
+    /// Width in pixels of the PNG saved with `s`; the height follows the terminal's shape
+    #[arg(long, default_value_t = 1920, value_parser = clap::value_parser!(u32).range(1..))]
+    png_width: u32,
+    /// Color output; detected from `COLORTERM` when omitted
+    #[arg(long, value_enum)]
+    color: Option<ColorMode>,
+    /// Effect to start with, numbered like the effect keys
+    #[arg(long, default_value_t = 1, value_parser = clap::value_parser!(u64).range(1..))]
+    effect: u64,
+    /// Print a single frame to stdout and exit, without touching the terminal mode
+    #[arg(long)]
+    once: bool,
+    /// Animation time in seconds for `--once`, and where `--bench` starts
+    #[arg(long, default_value_t = 0.0)]
+    time: f32,
+    /// Render this many frames offscreen as fast as possible, print the timings to stderr and exit
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u32).range(1..))]
+    bench: Option<u32>,
+    /// Keys to press in the starting effect before the first frame, e.g. `b` for the Braille fractal
+    #[arg(long, default_value = "")]
+    keys: String,
+    /// Record the session to this file as an asciinema (v2) cast
+    #[arg(long)]
+    record: Option<PathBuf>,
+    /// Clock the colors cycle by: `anim` with the animation, or `wall` by the time since start, unaffected by pausing and stepping
+    #[arg(long, value_enum, default_value_t = PaletteSync::Anim)]
+    palette_sync: PaletteSync,
+}
 
-        for x in 0..width {
-            let real = (((x as f32 / w) - 0.5) * 3.5 * aspect) / zoom + cx;
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+enum PaletteSync {
+    // the palette follows the animation time, stopping when it's paused
+    Anim,
+    // the palette follows the real time since the start, so instances started
+    // together stay in phase whatever happens to their animations
+    Wall,
+}
 
-            let mut zr = 0.0f32;
-            let mut zi = 0.0f32;
-            let mut iter = 0;
+// animation time units per second at `--speed 1`; the original fixed 0.1 per
+// frame at 60 fps
+const TIME_RATE: f32 = 6.0;
 
-            while zr * zr + zi * zi <= 4.0 && iter < max_iter {
-                let new_zr = zr * zr - zi * zi + real;
-                let new_zi = 2.0 * zr * zi + imag;
-                zr = new_zr;
-                zi = new_zi;
-                iter += 1;
+fn main() -> Result<()> {
+    let args = Args::parse();
+    // any smaller and points that never escape would be counted as escaped
+    if !(2.0..).contains(&args.bailout) {
+        Args::command()
+            .error(ErrorKind::InvalidValue, "--bailout must be at least 2")
+            .exit();
+    }
+    // the effects that simulate in steps can't run backwards
+    if !(0.0..=f32::MAX).contains(&args.speed) {
+        Args::command()
+            .error(ErrorKind::InvalidValue, "--speed must be 0 or more")
+            .exit();
+    }
+    // animation time units per real second
+    let rate = TIME_RATE * args.speed;
+    let palette = match (args.gradient, &args.gradient_file) {
+        (Some(gradient), _) => Coloring::Gradient(gradient),
+        (None, Some(path)) => match Gradient::load(path) {
+            Ok(gradient) => Coloring::Gradient(gradient),
+            Err(err) => {
+                eprintln!("Failed to load gradient: {err}");
+                std::process::exit(1);
             }
-
-            let shade = if iter == max_iter {
-                0.0
-            } else {
-                iter as f32 / max_iter as f32
-            };
-
-            let idx = (shade * (ASCII_LUT.len() - 1) as f32) as usize;
-            let ch = ASCII_LUT[idx];
-
-            // color based on iteration and position
-            let hue = shade + (x as f32 / w) * 0.3 + (y as f32 / h) * 0.2 + t * 0.1;
-            let r = (0.5 + 0.5 * (hue * 6.2831).sin()) * 255.0;
-            let g = (0.5 + 0.5 * (hue * 6.2831 + 2.094).sin()) * 255.0;
-            let b = (0.5 + 0.5 * (hue * 6.2831 + 4.188).sin()) * 255.0;
-            execute!(
-                out,
-                SetForegroundColor(Color::Rgb {
-                    r: r as u8,
-                    g: g as u8,
-                    b: b as u8
-                }),
-                Print(ch)
-            )?;
+        },
+        (None, None) => args.palette.coloring(),
+    };
+    let ramp = match &args.ramp_weights {
+        Some(path) => args.ramp.with_weights_file(path).unwrap_or_else(|err| {
+            eprintln!("Failed to load ramp weights: {err}");
+            std::process::exit(1);
+        }),
+        None => args.ramp.clone(),
+    };
+    let theme = Theme { palette, ramp };
+    // selected with the number keys, in this order
+    let mut effects: Vec<Box<dyn Effect>> = vec![
+        Box::new(FractalEffect::new(
+            RenderSettings {
+                max_iter: args.max_iter,
+                bailout: args.bailout,
+                smooth: args.smooth,
+                cycle_speed: args.cycle_speed,
+                dither: args.dither,
+                theme: theme.clone(),
+            },
+            args.png_width,
+        )),
+        Box::new(Plasma::new(theme.clone())),
+        Box::new(Life::default()),
+        Box::new(Rain::default()),
+        Box::new(Starfield::new(theme.clone())),
+        Box::new(Fire::new(theme.clone())),
+        Box::new(Tunnel::new(theme)),
+    ];
+    if args.effect as usize > effects.len() {
+        Args::command()
+            .error(
+                ErrorKind::InvalidValue,
+                format!("--effect must be between 1 and {}", effects.len()),
+            )
+            .exit();
+    }
+    let mut active = args.effect as usize - 1;
+    for c in args.keys.chars() {
+        if let Some(message) = effects[active].key_pressed(KeyCode::Char(c)) {
+            eprintln!("{message}");
         }
     }
+    let color_mode = args.color.unwrap_or_else(ColorMode::detect);
 
-    Ok(())
-}
+    if let Some(frames) = args.bench {
+        let (width, height) = terminal::size().unwrap_or((80, 24));
+        bench(
+            effects[active].as_mut(),
+            width,
+            height,
+            args.time * rate,
+            STEP * args.speed,
+            frames,
+        );
+        return Ok(());
+    }
 
-static ASCII_LUT: &[char] = &[' ', '.', ':', '-', '=', '+', '*', '#', '%', '@'];
+    if args.once {
+        // no terminal to ask when output is redirected
+        let (width, height) = terminal::size().unwrap_or((80, 24));
+        let mut frame = Frame::new(width, height);
+        effects[active].resize(width, height);
+        // a fresh effect has come all the way from 0
+        let t = args.time * rate;
+        effects[active].render(&mut frame, t, t, t);
+        return write_lines(&mut stdout(), &frame, color_mode);
+    }
 
-fn main() -> Result<()> {
-    let mut stdout = stdout();
+    let frame_budget = Duration::from_secs_f64(1.0 / args.fps as f64);
+    let cast = match &args.record {
+        Some(path) => {
+            let (width, height) = terminal::size()?;
+            Some(Cast::create(path, width, height)?)
+        }
+        None => None,
+    };
+    let mut stdout = Tee::new(stdout(), cast);
 
-    execute!(stdout, EnterAlternateScreen)?;
+    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
     terminal::enable_raw_mode()?;
 
+    // reported once the terminal is restored
+    let mut messages = Vec::new();
+
     let res = (|| -> Result<()> {
+        let started = Instant::now();
         let mut t: f32 = 0.0;
-        loop {
+        // how far `t` moved since the last frame
+        let mut dt: f32 = 0.0;
+        let mut paused = false;
+        let mut screen = Screen::new(color_mode);
+        let mut frame = Frame::new(0, 0);
+        execute!(stdout, cursor::Hide)?;
+        let mut last_frame = Instant::now();
+        'frames: loop {
             let (width, height) = terminal::size()?;
-            execute!(stdout, Clear(ClearType::All), cursor::Hide)?;
-            execute!(stdout, SetForegroundColor(Color::Cyan))?;
+            if (width, height) != (frame.width, frame.height) {
+                // the cast header already has the starting size
+                if frame.width != 0 {
+                    stdout.resized(width, height)?;
+                }
+                frame = Frame::new(width, height);
+                for effect in &mut effects {
+                    effect.resize(width, height);
+                }
+            }
 
             let start = Instant::now();
-            draw_frame(&mut stdout, t, width, height)?;
-            execute!(stdout, ResetColor)?;
-            stdout.flush().ok();
-
-            t += 0.1;
+            let palette_t = match args.palette_sync {
+                PaletteSync::Anim => t,
+                PaletteSync::Wall => (start - started).as_secs_f32() * TIME_RATE,
+            };
+            effects[active].render(&mut frame, t, dt, palette_t);
+            dt = 0.0;
+            screen.present(&mut stdout, &frame)?;
 
-            // ~60 FPS cap
-            let frame_time = start.elapsed();
-            if frame_time < Duration::from_millis(16) {
-                thread::sleep(Duration::from_millis(16) - frame_time);
+            // wait out the rest of the frame budget in the input poll, so the frame
+            // time covers rendering, output and input handling alike
+            let deadline = start + frame_budget;
+            loop {
+                let remaining = deadline.saturating_duration_since(Instant::now());
+                if remaining.is_zero() || !event::poll(remaining)? {
+                    break;
+                }
+                match event::read()? {
+                    Event::Key(key) => match key.code {
+                        KeyCode::Char('q') => break 'frames,
+                        KeyCode::Char(' ') => paused = !paused,
+                        KeyCode::Char('.') if paused => {
+                            t += STEP;
+                            dt += STEP;
+                        }
+                        KeyCode::Char(',') if paused && effects[active].reversible() => {
+                            t -= STEP;
+                            dt -= STEP;
+                        }
+                        KeyCode::Char(c @ '1'..='9') => {
+                            let n = c as usize - '1' as usize;
+                            if n < effects.len() {
+                                active = n;
+                            }
+                        }
+                        code => messages.extend(effects[active].key_pressed(code)),
+                    },
+                    Event::Mouse(mouse) => effects[active].mouse(mouse),
+                    _ => {}
+                }
             }
 
-            // simple escape: check for 'q' key without blocking
-            if crossterm::event::poll(Duration::from_millis(1))? {
-                if let crossterm::event::Event::Key(key) = crossterm::event::read()? {
-                    if key.code == crossterm::event::KeyCode::Char('q') {
-                        break;
-                    }
-                }
+            // advance by the real elapsed time so the tempo doesn't depend on how
+            // fast frames come
+            let now = Instant::now();
+            if !paused {
+                let elapsed = (now - last_frame).as_secs_f32() * rate;
+                t += elapsed;
+                dt += elapsed;
             }
+            last_frame = now;
         }
         Ok(())
     })();
 
     terminal::disable_raw_mode().ok();
-    execute!(stdout, LeaveAlternateScreen, cursor::Show).ok();
+    execute!(
+        stdout,
+        ResetColor,
+        DisableMouseCapture,
+        LeaveAlternateScreen,
+        cursor::Show
+    )
+    .ok();
+    for message in &messages {
+        println!("{message}");
+    }
 
-    res
+    res.and(stdout.finish())
 }
 
-
+// the same rendering the interactive loop does, `step` apart like frames at
+// 60 fps, but into a frame that is never written out
+fn bench(effect: &mut dyn Effect, width: u16, height: u16, start: f32, step: f32, frames: u32) {
+    let mut frame = Frame::new(width, height);
+    effect.resize(width, height);
+    let mut times = Vec::with_capacity(frames as usize);
+    for i in 0..frames {
+        let begin = Instant::now();
+        let t = start + i as f32 * step;
+        effect.render(&mut frame, t, if i == 0 { start } else { step }, t);
+        times.push(begin.elapsed());
+    }
+    let total: Duration = times.iter().sum();
+    let mean = total / frames;
+    let fastest = times.iter().min().unwrap();
+    let slowest = times.iter().max().unwrap();
+    eprintln!(
+        "{frames} frames at {width}x{height} in {:.3} s: {:.3} ms per frame ({:.1} fps), fastest {:.3} ms, slowest {:.3} ms",
+        total.as_secs_f64(),
+        mean.as_secs_f64() * 1e3,
+        frames as f64 / total.as_secs_f64(),
+        fastest.as_secs_f64() * 1e3,
+        slowest.as_secs_f64() * 1e3,
+    );
+}
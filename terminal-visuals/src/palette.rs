@@ -0,0 +1,300 @@
+use std::f32::consts::TAU;
+use std::path::Path;
+use std::str::FromStr;
+
+use clap::ValueEnum;
+
+use crate::ramp::Ramp;
+
+// maps a shade in 0..1 and a cycling phase to a color
+pub type PaletteFn = fn(shade: f32, t: f32) -> (u8, u8, u8);
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum Palette {
+    Rainbow,
+    Fire,
+    Ice,
+    Grayscale,
+    // built-in gradients
+    Sunset,
+    Ocean,
+}
+
+// deep violet through red and orange to pale yellow
+const SUNSET: &[(f32, (u8, u8, u8))] = &[
+    (0.0, (20, 6, 40)),
+    (0.35, (150, 30, 80)),
+    (0.65, (240, 110, 40)),
+    (1.0, (255, 235, 170)),
+];
+
+// near black through navy and teal to foam white
+const OCEAN: &[(f32, (u8, u8, u8))] = &[
+    (0.0, (2, 8, 20)),
+    (0.4, (10, 60, 130)),
+    (0.75, (30, 170, 170)),
+    (1.0, (230, 250, 245)),
+];
+
+impl Palette {
+    pub fn coloring(self) -> Coloring {
+        let gradient = |stops: &[(f32, (u8, u8, u8))]| {
+            Coloring::Gradient(Gradient::new(stops.to_vec()).expect("built-in gradients are valid"))
+        };
+        match self {
+            Palette::Rainbow => Coloring::Function(rainbow),
+            Palette::Fire => Coloring::Function(fire),
+            Palette::Ice => Coloring::Function(ice),
+            Palette::Grayscale => Coloring::Function(grayscale),
+            Palette::Sunset => gradient(SUNSET),
+            Palette::Ocean => gradient(OCEAN),
+        }
+    }
+}
+
+// a palette computed per shade, or a gradient's precomputed table
+#[derive(Clone)]
+pub enum Coloring {
+    Function(PaletteFn),
+    Gradient(Gradient),
+}
+
+// color stops over 0..1, blended in OKLab, where halfway between two colors
+// looks halfway in lightness and hue too, rather than muddy as RGB blends get
+const GRADIENT_STEPS: usize = 256;
+
+#[derive(Clone)]
+pub struct Gradient {
+    table: Vec<(u8, u8, u8)>,
+}
+
+impl Gradient {
+    // the stops must go up from 0 to 1, with at least two of them
+    pub fn new(stops: Vec<(f32, (u8, u8, u8))>) -> Result<Self, String> {
+        if stops.len() < 2 {
+            return Err("a gradient needs at least two stops".to_string());
+        }
+        if stops.iter().any(|stop| !stop.0.is_finite()) {
+            return Err("gradient stop positions must be finite numbers".to_string());
+        }
+        if stops.windows(2).any(|pair| pair[0].0 > pair[1].0) {
+            return Err("gradient stops must be in increasing order".to_string());
+        }
+        if stops[0].0 != 0.0 || stops[stops.len() - 1].0 != 1.0 {
+            return Err("gradient stops must start at 0 and end at 1".to_string());
+        }
+        let table = (0..GRADIENT_STEPS)
+            .map(|i| {
+                let shade = i as f32 / (GRADIENT_STEPS - 1) as f32;
+                // the last pair whose span holds the shade; a stop repeated at
+                // the same position makes a hard edge there
+                let pair = stops
+                    .windows(2)
+                    .rfind(|pair| pair[0].0 <= shade)
+                    .unwrap_or(&stops[..2]);
+                let (a, b) = (pair[0], pair[1]);
+                let span = b.0 - a.0;
+                let f = if span > 0.0 {
+                    ((shade - a.0) / span).min(1.0)
+                } else {
+                    1.0
+                };
+                let (la, lb) = (oklab(a.1), oklab(b.1));
+                from_oklab([0, 1, 2].map(|c| la[c] + (lb[c] - la[c]) * f))
+            })
+            .collect();
+        Ok(Gradient { table })
+    }
+
+    // `stop:color` entries separated by commas or newlines, each color as
+    // `rrggbb` hex with an optional `#`; blank lines and lines starting with
+    // `//` are skipped, for palette files
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text =
+            std::fs::read_to_string(path).map_err(|err| format!("{}: {err}", path.display()))?;
+        text.parse()
+            .map_err(|err| format!("{}: {err}", path.display()))
+    }
+
+    fn color(&self, shade: f32) -> (u8, u8, u8) {
+        let i = (shade.clamp(0.0, 1.0) * (GRADIENT_STEPS - 1) as f32).round() as usize;
+        self.table[i]
+    }
+}
+
+impl FromStr for Gradient {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let stops = s
+            .lines()
+            .filter(|line| !line.trim_start().starts_with("//"))
+            .flat_map(|line| line.split(','))
+            .map(str::trim)
+            .filter(|entry| !entry.is_empty())
+            .map(parse_stop)
+            .collect::<Result<Vec<_>, _>>()?;
+        Gradient::new(stops)
+    }
+}
+
+fn parse_stop(entry: &str) -> Result<(f32, (u8, u8, u8)), String> {
+    let invalid = || format!("invalid stop `{entry}`, expected `position:rrggbb`");
+    let (position, color) = entry.split_once(':').ok_or_else(invalid)?;
+    let position: f32 = position
+        .trim()
+        .parse()
+        .ok()
+        .filter(|position: &f32| position.is_finite())
+        .ok_or_else(invalid)?;
+    let hex = color.trim().trim_start_matches('#');
+    if hex.len() != 6 {
+        return Err(invalid());
+    }
+    let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).map_err(|_| invalid());
+    Ok((position, (channel(0)?, channel(2)?, channel(4)?)))
+}
+
+// Björn Ottosson's OKLab, from and back to 8-bit sRGB
+fn oklab((r, g, b): (u8, u8, u8)) -> [f32; 3] {
+    let linear = |c: u8| {
+        let c = c as f32 / 255.0;
+        if c <= 0.04045 {
+            c / 12.92
+        } else {
+            ((c + 0.055) / 1.055).powf(2.4)
+        }
+    };
+    let (r, g, b) = (linear(r), linear(g), linear(b));
+    let l = (0.412_221_46 * r + 0.536_332_55 * g + 0.051_445_995 * b).cbrt();
+    let m = (0.211_903_5 * r + 0.680_699_5 * g + 0.107_396_96 * b).cbrt();
+    let s = (0.088_302_46 * r + 0.281_718_85 * g + 0.629_978_7 * b).cbrt();
+    [
+        0.210_454_26 * l + 0.793_617_8 * m - 0.004_072_047 * s,
+        1.977_998_5 * l - 2.428_592_2 * m + 0.450_593_7 * s,
+        0.025_904_037 * l + 0.782_771_77 * m - 0.808_675_77 * s,
+    ]
+}
+
+fn from_oklab([lightness, a, b]: [f32; 3]) -> (u8, u8, u8) {
+    let l = (lightness + 0.396_337_78 * a + 0.215_803_76 * b).powi(3);
+    let m = (lightness - 0.105_561_346 * a - 0.063_854_17 * b).powi(3);
+    let s = (lightness - 0.089_484_18 * a - 1.291_485_5 * b).powi(3);
+    let encode = |c: f32| {
+        let c = c.clamp(0.0, 1.0);
+        let c = if c <= 0.003_130_8 {
+            c * 12.92
+        } else {
+            1.055 * c.powf(1.0 / 2.4) - 0.055
+        };
+        (c * 255.0).round() as u8
+    };
+    (
+        encode(4.076_741_7 * l - 3.307_711_6 * m + 0.230_969_94 * s),
+        encode(-1.268_438 * l + 2.609_757_4 * m - 0.341_319_38 * s),
+        encode(-0.004_196_086_3 * l - 0.703_418_6 * m + 1.707_614_7 * s),
+    )
+}
+
+// the only palette that cycles: hue follows both the shade and the phase
+fn rainbow(shade: f32, t: f32) -> (u8, u8, u8) {
+    let hue = shade + t;
+    let r = (0.5 + 0.5 * (hue * TAU).sin()) * 255.0;
+    let g = (0.5 + 0.5 * (hue * TAU + TAU / 3.0).sin()) * 255.0;
+    let b = (0.5 + 0.5 * (hue * TAU + 2.0 * TAU / 3.0).sin()) * 255.0;
+    (r as u8, g as u8, b as u8)
+}
+
+// black -> red -> yellow -> white
+pub fn fire(shade: f32, _t: f32) -> (u8, u8, u8) {
+    let s = shade.clamp(0.0, 1.0) * 3.0;
+    let r = s.min(1.0);
+    let g = (s - 1.0).clamp(0.0, 1.0);
+    let b = (s - 2.0).clamp(0.0, 1.0);
+    ((r * 255.0) as u8, (g * 255.0) as u8, (b * 255.0) as u8)
+}
+
+// black -> blue -> cyan -> white
+fn ice(shade: f32, _t: f32) -> (u8, u8, u8) {
+    let s = shade.clamp(0.0, 1.0) * 3.0;
+    let b = s.min(1.0);
+    let g = (s - 1.0).clamp(0.0, 1.0);
+    let r = (s - 2.0).clamp(0.0, 1.0);
+    ((r * 255.0) as u8, (g * 255.0) as u8, (b * 255.0) as u8)
+}
+
+fn grayscale(shade: f32, _t: f32) -> (u8, u8, u8) {
+    let v = (shade * 255.0) as u8;
+    (v, v, v)
+}
+
+// how effects turn shades into glyphs and colors
+#[derive(Clone)]
+pub struct Theme {
+    pub palette: Coloring,
+    pub ramp: Ramp,
+}
+
+impl Theme {
+    pub fn glyph(&self, shade: f32) -> char {
+        self.ramp.glyph(shade)
+    }
+
+    pub fn color(&self, shade: f32, t: f32) -> (u8, u8, u8) {
+        match &self.palette {
+            Coloring::Function(palette) => palette(shade, t),
+            // gradients hold still, like the fire and ice palettes
+            Coloring::Gradient(gradient) => gradient.color(shade),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BLACK: (u8, u8, u8) = (0, 0, 0);
+    const WHITE: (u8, u8, u8) = (255, 255, 255);
+
+    #[test]
+    fn stops_parse() {
+        assert_eq!(parse_stop("0.5:#ff8000"), Ok((0.5, (255, 128, 0))));
+        assert_eq!(parse_stop("1 : 00FF0a"), Ok((1.0, (0, 255, 10))));
+        for entry in [
+            "0.5",
+            "x:ff0000",
+            "0.5:ff00",
+            "0.5:gg0000",
+            "NaN:ff0000",
+            "inf:ff0000",
+        ] {
+            assert!(parse_stop(entry).is_err(), "{entry:?}");
+        }
+    }
+
+    #[test]
+    fn gradients_check_their_stops() {
+        assert!(Gradient::new(vec![(0.0, BLACK), (1.0, WHITE)]).is_ok());
+        // a repeated position is a hard edge
+        assert!(
+            Gradient::new(vec![(0.0, BLACK), (0.5, BLACK), (0.5, WHITE), (1.0, WHITE)]).is_ok()
+        );
+        assert!(Gradient::new(vec![(0.0, BLACK)]).is_err());
+        assert!(
+            Gradient::new(vec![(0.0, BLACK), (0.7, WHITE), (0.3, BLACK), (1.0, WHITE)]).is_err()
+        );
+        assert!(Gradient::new(vec![(0.1, BLACK), (1.0, WHITE)]).is_err());
+        assert!(Gradient::new(vec![(0.0, BLACK), (0.9, WHITE)]).is_err());
+        assert!(Gradient::new(vec![(0.0, BLACK), (f32::NAN, WHITE), (1.0, WHITE)]).is_err());
+    }
+
+    #[test]
+    fn gradients_blend_between_stops() {
+        let gradient: Gradient = "// black to white\n0:000000, 1:ffffff".parse().unwrap();
+        assert_eq!(gradient.color(-1.0), BLACK);
+        assert_eq!(gradient.color(0.0), BLACK);
+        assert_eq!(gradient.color(1.0), WHITE);
+        let (r, g, b) = gradient.color(0.5);
+        assert!(r == g && g == b && 0 < r && r < 255);
+    }
+}
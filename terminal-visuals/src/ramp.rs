@@ -0,0 +1,373 @@
+// glyph ramps: the characters shades are drawn with, in the order given, each
+// standing for the shade its ink coverage looks like, so a ramp with irregular
+// steps between its glyphs still brightens evenly
+use std::path::Path;
+use std::str::FromStr;
+
+pub const DEFAULT_RAMP: &str = " .:-=+*#%@";
+
+// the share of a cell each glyph inks, measured from DejaVu Sans Mono; other
+// monospace fonts differ in the details but rarely in the order
+const ASCII_COVERAGE: &[(char, f32)] = &[
+    (' ', 0.000),
+    ('!', 0.081),
+    ('"', 0.062),
+    ('#', 0.230),
+    ('$', 0.199),
+    ('%', 0.176),
+    ('&', 0.218),
+    ('\'', 0.031),
+    ('(', 0.107),
+    (')', 0.107),
+    ('*', 0.091),
+    ('+', 0.106),
+    (',', 0.042),
+    ('-', 0.028),
+    ('.', 0.025),
+    ('/', 0.104),
+    ('0', 0.233),
+    ('1', 0.151),
+    ('2', 0.173),
+    ('3', 0.181),
+    ('4', 0.188),
+    ('5', 0.189),
+    ('6', 0.219),
+    ('7', 0.138),
+    ('8', 0.237),
+    ('9', 0.219),
+    (':', 0.049),
+    (';', 0.067),
+    ('<', 0.112),
+    ('=', 0.117),
+    ('>', 0.112),
+    ('?', 0.118),
+    ('@', 0.262),
+    ('A', 0.207),
+    ('B', 0.260),
+    ('C', 0.156),
+    ('D', 0.232),
+    ('E', 0.209),
+    ('F', 0.169),
+    ('G', 0.205),
+    ('H', 0.226),
+    ('I', 0.167),
+    ('J', 0.150),
+    ('K', 0.219),
+    ('L', 0.137),
+    ('M', 0.261),
+    ('N', 0.260),
+    ('O', 0.224),
+    ('P', 0.200),
+    ('Q', 0.240),
+    ('R', 0.238),
+    ('S', 0.190),
+    ('T', 0.149),
+    ('U', 0.209),
+    ('V', 0.179),
+    ('W', 0.255),
+    ('X', 0.190),
+    ('Y', 0.147),
+    ('Z', 0.187),
+    ('[', 0.131),
+    ('\\', 0.104),
+    (']', 0.131),
+    ('^', 0.067),
+    ('_', 0.032),
+    ('`', 0.021),
+    ('a', 0.182),
+    ('b', 0.207),
+    ('c', 0.122),
+    ('d', 0.207),
+    ('e', 0.177),
+    ('f', 0.138),
+    ('g', 0.226),
+    ('h', 0.181),
+    ('i', 0.127),
+    ('j', 0.136),
+    ('k', 0.182),
+    ('l', 0.121),
+    ('m', 0.205),
+    ('n', 0.155),
+    ('o', 0.169),
+    ('p', 0.206),
+    ('q', 0.206),
+    ('r', 0.102),
+    ('s', 0.143),
+    ('t', 0.134),
+    ('u', 0.155),
+    ('v', 0.131),
+    ('w', 0.178),
+    ('x', 0.139),
+    ('y', 0.163),
+    ('z', 0.135),
+    ('{', 0.145),
+    ('|', 0.114),
+    ('}', 0.143),
+    ('~', 0.058),
+];
+
+// the block elements cover their share of the cell exactly, and the shades
+// stipple a quarter, half and three quarters of it
+const BLOCK_COVERAGE: &[(char, f32)] = &[
+    ('░', 0.25),
+    ('▒', 0.5),
+    ('▓', 0.75),
+    ('▁', 0.125),
+    ('▂', 0.25),
+    ('▃', 0.375),
+    ('▄', 0.5),
+    ('▅', 0.625),
+    ('▆', 0.75),
+    ('▇', 0.875),
+    ('█', 1.0),
+];
+
+// glyphs for shade 0 up to 1, usually from empty to dense, and the shade each
+// is drawn for
+#[derive(Clone)]
+pub struct Ramp {
+    glyphs: Vec<char>,
+    // going up from 0 for the first glyph to 1 for the last
+    levels: Vec<f32>,
+}
+
+impl Ramp {
+    // `glyphs` in the order given, spaced by their coverage from `weights` or
+    // else the built-in tables: each one's level is how far its coverage is
+    // from the first glyph's towards the last's, so a ramp from dense to empty
+    // works as well. Fails if any glyph is in neither, or if the coverages
+    // don't keep going the same way along the ramp
+    fn weighted(glyphs: &[char], weights: &[(char, f32)]) -> Result<Self, String> {
+        let coverage = |glyph: char| {
+            weights
+                .iter()
+                .chain(ASCII_COVERAGE)
+                .chain(BLOCK_COVERAGE)
+                .find(|&&(g, _)| g == glyph)
+                .map(|&(_, coverage)| coverage)
+                .ok_or_else(|| format!("no coverage for `{glyph}`"))
+        };
+        let coverages = glyphs
+            .iter()
+            .map(|&glyph| coverage(glyph))
+            .collect::<Result<Vec<_>, String>>()?;
+        let (first, last) = (coverages[0], coverages[coverages.len() - 1]);
+        if first == last {
+            return Err("the ramp's first and last glyphs have the same coverage".to_string());
+        }
+        let levels: Vec<f32> = coverages
+            .iter()
+            .map(|&coverage| (coverage - first) / (last - first))
+            .collect();
+        if let Some(i) = levels.windows(2).position(|pair| pair[1] < pair[0]) {
+            return Err(format!(
+                "`{}` after `{}` goes back the other way in coverage ({} then {})",
+                glyphs[i + 1],
+                glyphs[i],
+                coverages[i],
+                coverages[i + 1]
+            ));
+        }
+        Ok(Ramp {
+            glyphs: glyphs.to_vec(),
+            levels,
+        })
+    }
+
+    // `glyphs` in the order given, one even step apart
+    fn even(glyphs: Vec<char>) -> Self {
+        let steps = (glyphs.len() - 1) as f32;
+        let levels = (0..glyphs.len()).map(|i| i as f32 / steps).collect();
+        Ramp { glyphs, levels }
+    }
+
+    // the same glyphs with the coverages in a weights file taking the place of
+    // the built-in ones, and needed for any glyph those don't have: one glyph
+    // per line, then the share of the cell it inks, e.g. `. 0.025`; blank lines
+    // and lines starting with `//` are skipped
+    pub fn with_weights_file(&self, path: &Path) -> Result<Self, String> {
+        let text =
+            std::fs::read_to_string(path).map_err(|err| format!("{}: {err}", path.display()))?;
+        let weights = parse_weights(&text).map_err(|err| format!("{}: {err}", path.display()))?;
+        Ramp::weighted(&self.glyphs, &weights).map_err(|err| format!("{}: {err}", path.display()))
+    }
+
+    // the densest glyph whose level is at most `shade`
+    pub fn glyph(&self, shade: f32) -> char {
+        self.glyphs[self.step(shade).saturating_sub(1)]
+    }
+
+    // the glyph on either side of `shade`, the denser one where `threshold`
+    // in 0..1 is past the rest of the way up to it: over cells with thresholds
+    // spread evenly, the glyphs average out to the shade
+    pub fn dithered_glyph(&self, shade: f32, threshold: f32) -> char {
+        let upper = self.step(shade).clamp(1, self.levels.len() - 1);
+        let (low, high) = (self.levels[upper - 1], self.levels[upper]);
+        let part = if high > low {
+            ((shade - low) / (high - low)).clamp(0.0, 1.0)
+        } else {
+            1.0
+        };
+        self.glyphs[if part + threshold >= 1.0 {
+            upper
+        } else {
+            upper - 1
+        }]
+    }
+
+    // a glyph past the blank first one, for effects that fill every cell they
+    // draw: `shade` spreads over the rest of the ramp and takes the glyph nearest
+    pub fn inked_glyph(&self, shade: f32) -> char {
+        let target = self.levels[1] + shade.clamp(0.0, 1.0) * (1.0 - self.levels[1]);
+        let nearest = (1..self.levels.len())
+            .min_by(|&a, &b| {
+                (self.levels[a] - target)
+                    .abs()
+                    .total_cmp(&(self.levels[b] - target).abs())
+            })
+            .expect("ramps have at least two glyphs");
+        self.glyphs[nearest]
+    }
+
+    // the shade the first glyph past the blank one is drawn from
+    pub fn inked_level(&self) -> f32 {
+        self.levels[1]
+    }
+
+    // how many glyphs have levels at most `shade`
+    fn step(&self, shade: f32) -> usize {
+        self.levels.partition_point(|&level| level <= shade)
+    }
+}
+
+// glyphs the built-in tables cover are spaced by coverage where it keeps going
+// one way along the ramp; a ramp with any other glyph, or whose coverages go
+// back and forth (the default's `:-` and `=+*`), is evenly spaced instead, and
+// only `--ramp-weights` turns those into errors
+impl FromStr for Ramp {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let chars: Vec<char> = s.chars().collect();
+        if chars.len() < 2 {
+            return Err("the ramp needs at least two characters".to_string());
+        }
+        Ok(Ramp::weighted(&chars, &[]).unwrap_or_else(|_| Ramp::even(chars)))
+    }
+}
+
+fn parse_weights(text: &str) -> Result<Vec<(char, f32)>, String> {
+    text.lines()
+        .filter(|line| !line.trim().is_empty() && !line.starts_with("//"))
+        .map(|line| {
+            let invalid = || format!("invalid weight `{line}`, expected a glyph and its coverage");
+            let mut chars = line.chars();
+            let glyph = chars.next().ok_or_else(invalid)?;
+            let coverage: f32 = chars.as_str().trim().parse().map_err(|_| invalid())?;
+            if !coverage.is_finite() || coverage < 0.0 {
+                return Err(invalid());
+            }
+            Ok((glyph, coverage))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ramp(s: &str) -> Ramp {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn default_ramp_keeps_its_order() {
+        let ramp = ramp(DEFAULT_RAMP);
+        assert_eq!(ramp.glyphs, DEFAULT_RAMP.chars().collect::<Vec<_>>());
+        // its coverages go back and forth, so it's evenly spaced as it always was
+        let steps = (ramp.levels.len() - 1) as f32;
+        for (i, &level) in ramp.levels.iter().enumerate() {
+            assert!((level - i as f32 / steps).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn reversed_ramp_inverts_the_shading() {
+        let ramp = ramp("@%#*+=-:. ");
+        assert_eq!(ramp.glyph(0.0), '@');
+        assert_eq!(ramp.glyph(1.0), ' ');
+    }
+
+    #[test]
+    fn coverage_places_the_levels() {
+        let ramp = ramp(" .#");
+        assert_eq!(ramp.glyphs, vec![' ', '.', '#']);
+        assert!((ramp.levels[1] - 0.025 / 0.23).abs() < 1e-6);
+        assert_eq!(ramp.glyph(0.05), ' ');
+        assert_eq!(ramp.glyph(0.2), '.');
+        assert_eq!(ramp.glyph(1.0), '#');
+        // dense to empty measures from the other end
+        let ramp = self::ramp("#. ");
+        assert_eq!(ramp.glyphs, vec!['#', '.', ' ']);
+        assert_eq!(ramp.glyph(0.5), '#');
+        assert_eq!(ramp.glyph(0.95), '.');
+        assert_eq!(ramp.glyph(1.0), ' ');
+    }
+
+    #[test]
+    fn weights_must_keep_going_one_way() {
+        assert!(Ramp::weighted(&[' ', ':', '-'], &[]).is_err());
+        assert!(Ramp::weighted(&['a', 'b'], &[('a', 0.5), ('b', 0.5)]).is_err());
+        // a weight takes the place of the table's
+        let ramp = Ramp::weighted(&[' ', ':', '-'], &[('-', 0.1)]).unwrap();
+        assert!((ramp.levels[1] - 0.049 / 0.1).abs() < 1e-6);
+        // glyphs the tables don't have need weights
+        assert!(Ramp::weighted(&[' ', '★'], &[]).is_err());
+        // and are evenly spaced without them
+        assert_eq!(self::ramp(" ★").levels, vec![0.0, 1.0]);
+    }
+
+    #[test]
+    fn glyph_lookup_clamps() {
+        let ramp = ramp(DEFAULT_RAMP);
+        assert_eq!(ramp.glyph(-1.0), ' ');
+        assert_eq!(ramp.glyph(0.0), ' ');
+        assert_eq!(ramp.glyph(0.5), '=');
+        assert_eq!(ramp.glyph(1.0), '@');
+        assert_eq!(ramp.glyph(2.0), '@');
+        assert_eq!(ramp.inked_glyph(0.0), '.');
+        assert_eq!(ramp.inked_glyph(1.0), '@');
+        assert!((ramp.inked_level() - 1.0 / 9.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn dithering_mixes_neighbouring_glyphs_by_the_shade() {
+        let ramp = ramp(DEFAULT_RAMP);
+        // a third of the way from `.` to `:`
+        let shade = (1.0 + 1.0 / 3.0) / 9.0;
+        let glyphs: Vec<char> = (0..16)
+            .map(|i| ramp.dithered_glyph(shade, (i as f32 + 0.5) / 16.0))
+            .collect();
+        assert!(glyphs.iter().all(|&g| g == '.' || g == ':'));
+        assert_eq!(glyphs.iter().filter(|&&g| g == ':').count(), 5);
+        // on a glyph's own level and at the ends, there's nothing to mix
+        assert_eq!(ramp.dithered_glyph(2.0 / 9.0, 0.01), ':');
+        assert_eq!(ramp.dithered_glyph(0.0, 0.99), ' ');
+        assert_eq!(ramp.dithered_glyph(1.0, 0.01), '@');
+    }
+
+    #[test]
+    fn weights_file_lines() {
+        let weights = parse_weights("// coverages\n. 0.025\n\n  0\n# 0.23\n").unwrap();
+        assert_eq!(weights, vec![('.', 0.025), (' ', 0.0), ('#', 0.23)]);
+        // a line of just `/` and a number is a glyph, not a comment
+        assert_eq!(parse_weights("/ 0.1").unwrap(), vec![('/', 0.1)]);
+    }
+
+    #[test]
+    fn weights_file_rejects_bad_coverage() {
+        for text in ["x -0.1", "x NaN", "x inf", "x", "x lots"] {
+            assert!(parse_weights(text).is_err(), "{text:?}");
+        }
+    }
+}
@@ -0,0 +1,243 @@
+use std::io::{Result, Write};
+
+use clap::ValueEnum;
+use crossterm::{
+    cursor, queue,
+    style::{Color, Print, ResetColor, SetBackgroundColor, SetForegroundColor},
+    terminal::{Clear, ClearType},
+};
+
+#[derive(Clone, Copy, PartialEq)]
+pub struct Cell {
+    pub ch: char,
+    pub color: (u8, u8, u8),
+    // the terminal's own background when None
+    pub bg: Option<(u8, u8, u8)>,
+}
+
+impl Cell {
+    pub fn new(ch: char, color: (u8, u8, u8)) -> Self {
+        Self {
+            ch,
+            color,
+            bg: None,
+        }
+    }
+
+    // upper half block: `top` in the foreground over `bottom` in the background,
+    // two vertically stacked pixels per cell
+    pub fn half_block(top: (u8, u8, u8), bottom: (u8, u8, u8)) -> Self {
+        Self {
+            ch: '▀',
+            color: top,
+            bg: Some(bottom),
+        }
+    }
+}
+
+impl Default for Cell {
+    fn default() -> Self {
+        Self::new(' ', (0, 0, 0))
+    }
+}
+
+// row-major grid of what should be on screen this frame
+#[derive(Clone)]
+pub struct Frame {
+    pub width: u16,
+    pub height: u16,
+    pub cells: Vec<Cell>,
+}
+
+impl Frame {
+    pub fn new(width: u16, height: u16) -> Self {
+        Self {
+            width,
+            height,
+            cells: vec![Cell::default(); width as usize * height as usize],
+        }
+    }
+
+    // anything outside the grid is dropped
+    pub fn set(&mut self, x: u16, y: u16, cell: Cell) {
+        if x < self.width && y < self.height {
+            self.cells[y as usize * self.width as usize + x as usize] = cell;
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+pub enum ColorMode {
+    Truecolor,
+    // nearest entry of the xterm 6x6x6 color cube
+    #[value(name = "256")]
+    Ansi256,
+    // no color escapes at all, only the ASCII ramp
+    Mono,
+}
+
+// xterm's cube levels aren't evenly spaced
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+fn cube_index(v: u8) -> u8 {
+    (0..CUBE_LEVELS.len())
+        .min_by_key(|&i| CUBE_LEVELS[i].abs_diff(v))
+        .unwrap() as u8
+}
+
+impl ColorMode {
+    // truecolor when the terminal advertises it, the much more widely supported
+    // 256-color palette otherwise
+    pub fn detect() -> Self {
+        match std::env::var("COLORTERM") {
+            Ok(v) if v == "truecolor" || v == "24bit" => ColorMode::Truecolor,
+            _ => ColorMode::Ansi256,
+        }
+    }
+
+    fn color(self, (r, g, b): (u8, u8, u8)) -> Option<Color> {
+        match self {
+            ColorMode::Truecolor => Some(Color::Rgb { r, g, b }),
+            ColorMode::Ansi256 => Some(Color::AnsiValue(
+                16 + 36 * cube_index(r) + 6 * cube_index(g) + cube_index(b),
+            )),
+            ColorMode::Mono => None,
+        }
+    }
+}
+
+// the frame as plain lines of text with color escapes, for output that isn't a
+// live screen (a pipe, a file, `less -R`)
+pub fn write_lines<W: Write>(out: &mut W, frame: &Frame, mode: ColorMode) -> Result<()> {
+    let mut buf = Vec::new();
+    for row in frame.cells.chunks(frame.width.max(1) as usize) {
+        let mut colors = Colors::new(mode);
+        for cell in row {
+            colors.apply(&mut buf, cell)?;
+            queue!(buf, Print(cell.ch))?;
+        }
+        colors.finish(&mut buf)?;
+        buf.push(b'\n');
+    }
+    out.write_all(&buf)?;
+    out.flush()
+}
+
+// tracks the colors last sent to the terminal so escapes are only emitted on a
+// change; assumes the default colors at the start and restores them in finish()
+struct Colors {
+    mode: ColorMode,
+    fg: Option<Color>,
+    bg: Option<Color>,
+    changed: bool,
+}
+
+impl Colors {
+    fn new(mode: ColorMode) -> Self {
+        Self {
+            mode,
+            fg: None,
+            bg: None,
+            changed: false,
+        }
+    }
+
+    fn apply(&mut self, buf: &mut Vec<u8>, cell: &Cell) -> Result<()> {
+        if let Some(fg) = self
+            .mode
+            .color(cell.color)
+            .filter(|&fg| self.fg != Some(fg))
+        {
+            queue!(buf, SetForegroundColor(fg))?;
+            self.fg = Some(fg);
+            self.changed = true;
+        }
+        let bg = cell.bg.and_then(|bg| self.mode.color(bg));
+        if bg != self.bg {
+            queue!(buf, SetBackgroundColor(bg.unwrap_or(Color::Reset)))?;
+            self.bg = bg;
+            self.changed = true;
+        }
+        Ok(())
+    }
+
+    fn finish(self, buf: &mut Vec<u8>) -> Result<()> {
+        if self.changed {
+            queue!(buf, ResetColor)?;
+        }
+        Ok(())
+    }
+}
+
+// what a cell looks like once it reaches the terminal in `mode`
+fn displayed(cell: &Cell, mode: ColorMode) -> (char, Option<Color>, Option<Color>) {
+    (
+        cell.ch,
+        mode.color(cell.color),
+        cell.bg.and_then(|bg| mode.color(bg)),
+    )
+}
+
+// remembers the last presented frame so only changed cells are redrawn
+pub struct Screen {
+    mode: ColorMode,
+    prev: Option<Frame>,
+    buf: Vec<u8>,
+}
+
+impl Screen {
+    pub fn new(mode: ColorMode) -> Self {
+        Self {
+            mode,
+            prev: None,
+            buf: Vec::new(),
+        }
+    }
+
+    // overwrites the changed cells in place and writes the whole update at once;
+    // color escapes are only emitted when the color actually changes
+    pub fn present<W: Write>(&mut self, out: &mut W, frame: &Frame) -> Result<()> {
+        self.buf.clear();
+        let resized = self
+            .prev
+            .as_ref()
+            .is_some_and(|prev| prev.width != frame.width || prev.height != frame.height);
+        // after a resize whatever the terminal reflowed is garbage; wipe it once
+        // and redraw everything
+        if resized {
+            queue!(self.buf, Clear(ClearType::All))?;
+        }
+        let prev = self.prev.as_ref().filter(|_| !resized);
+
+        let mut cursor_at = None;
+        let mut colors = Colors::new(self.mode);
+        for y in 0..frame.height {
+            for x in 0..frame.width {
+                let i = y as usize * frame.width as usize + x as usize;
+                let cell = &frame.cells[i];
+                // compare what actually reaches the terminal, so e.g. mono mode
+                // doesn't redraw cells whose color merely changed
+                if prev.is_some_and(|prev| {
+                    displayed(&prev.cells[i], self.mode) == displayed(cell, self.mode)
+                }) {
+                    continue;
+                }
+                if cursor_at != Some((x, y)) {
+                    queue!(self.buf, cursor::MoveTo(x, y))?;
+                }
+                colors.apply(&mut self.buf, cell)?;
+                queue!(self.buf, Print(cell.ch))?;
+                cursor_at = Some((x + 1, y));
+            }
+        }
+        colors.finish(&mut self.buf)?;
+
+        out.write_all(&self.buf)?;
+        out.flush()?;
+        match &mut self.prev {
+            Some(prev) => prev.clone_from(frame),
+            None => self.prev = Some(frame.clone()),
+        }
+        Ok(())
+    }
+}
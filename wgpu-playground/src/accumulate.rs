@@ -0,0 +1,453 @@
+// temporal accumulation: while nothing the shader reads changes, each frame
+// renders the SDF with the camera rays nudged by a different sub-pixel offset
+// and folds it into a running average in a float texture, which is what's
+// shown; edges and fine detail converge as if many samples were taken per
+// pixel, like the raytracer's. Any change to the uniforms starts over.
+
+use crate::{create_render_pipeline, Globals};
+
+// what the scene is rendered into each frame
+const FRAME_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba16Float;
+// what the average is kept in; 32-bit floats keep late frames, weighted by
+// 1/n, from rounding away. wgpu doesn't guarantee rendering into them, only
+// storage writes, so the averaging is a compute pass
+const HISTORY_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba32Float;
+const WORKGROUP_SIZE: u32 = 8;
+
+const ACCUMULATE_SHADER: &str = r#"
+// how much of the new frame goes into the average: 1/n for the nth frame
+@group(0) @binding(0)
+var<uniform> weight: f32;
+@group(0) @binding(1)
+var frame: texture_2d<f32>;
+@group(0) @binding(2)
+var history: texture_2d<f32>;
+@group(0) @binding(3)
+var next: texture_storage_2d<rgba32float, write>;
+
+// the display pass reads the average through its own bind group
+@group(0) @binding(4)
+var average: texture_2d<f32>;
+
+// fullscreen triangle
+@vertex
+fn vs_fullscreen(@builtin(vertex_index) vi: u32) -> @builtin(position) vec4<f32> {
+    let uv = vec2<f32>(f32((vi << 1u) & 2u), f32(vi & 2u));
+    return vec4<f32>(uv * 2.0 - 1.0, 0.0, 1.0);
+}
+
+@compute @workgroup_size(8, 8)
+fn cs_accumulate(@builtin(global_invocation_id) id: vec3<u32>) {
+    if any(id.xy >= textureDimensions(frame)) {
+        return;
+    }
+    let blended = mix(textureLoad(history, id.xy, 0), textureLoad(frame, id.xy, 0), weight);
+    textureStore(next, id.xy, blended);
+}
+
+@fragment
+fn fs_display(@builtin(position) pos: vec4<f32>) -> @location(0) vec4<f32> {
+    return textureLoad(average, vec2<u32>(pos.xy), 0);
+}
+"#;
+
+// the textures at the window's size, and the bind groups between them; the two
+// history textures take turns being read and written
+struct Targets {
+    frame: wgpu::TextureView,
+    size: (u32, u32),
+    // [i] reads history i and writes the other
+    accumulate: [wgpu::BindGroup; 2],
+    // [i] shows history i
+    display: [wgpu::BindGroup; 2],
+}
+
+pub struct Accumulator {
+    pub enabled: bool,
+    format: wgpu::TextureFormat,
+    sample_count: u32,
+    shader: wgpu::ShaderModule,
+    weight: wgpu::Buffer,
+    accumulate_layout: wgpu::BindGroupLayout,
+    display_layout: wgpu::BindGroupLayout,
+    display_pipeline_layout: wgpu::PipelineLayout,
+    // the SDF pipeline again, for the float frame texture
+    scene_pipeline: wgpu::RenderPipeline,
+    accumulate_pipeline: wgpu::ComputePipeline,
+    display_pipeline: wgpu::RenderPipeline,
+    targets: Targets,
+    // frames in the average so far, and the history texture holding it
+    frames: u32,
+    current: usize,
+    // the last frame's uniforms without the jitter, to notice changes by
+    last: Option<Globals>,
+}
+
+impl Accumulator {
+    // `sample_count` is that of the pass the average is drawn into
+    pub fn new(
+        device: &wgpu::Device,
+        scene_layout: &wgpu::PipelineLayout,
+        scene_shader: &wgpu::ShaderModule,
+        format: wgpu::TextureFormat,
+        sample_count: u32,
+        width: u32,
+        height: u32,
+    ) -> Self {
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Accumulate Shader"),
+            source: wgpu::ShaderSource::Wgsl(ACCUMULATE_SHADER.into()),
+        });
+        let weight = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Accumulate Weight"),
+            size: 16,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let texture_entry = |binding, visibility| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility,
+            ty: wgpu::BindingType::Texture {
+                sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                view_dimension: wgpu::TextureViewDimension::D2,
+                multisampled: false,
+            },
+            count: None,
+        };
+        let accumulate_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Accumulate BGL"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                texture_entry(1, wgpu::ShaderStages::COMPUTE),
+                texture_entry(2, wgpu::ShaderStages::COMPUTE),
+                wgpu::BindGroupLayoutEntry {
+                    binding: 3,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::StorageTexture {
+                        access: wgpu::StorageTextureAccess::WriteOnly,
+                        format: HISTORY_FORMAT,
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                    },
+                    count: None,
+                },
+            ],
+        });
+        let display_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Accumulate Display BGL"),
+            entries: &[texture_entry(4, wgpu::ShaderStages::FRAGMENT)],
+        });
+        let accumulate_pipeline_layout =
+            device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("Accumulate Pipeline Layout"),
+                bind_group_layouts: &[&accumulate_layout],
+                push_constant_ranges: &[],
+            });
+        let display_pipeline_layout =
+            device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("Accumulate Display Pipeline Layout"),
+                bind_group_layouts: &[&display_layout],
+                push_constant_ranges: &[],
+            });
+        let accumulate_pipeline =
+            device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                label: Some("Accumulate Pipeline"),
+                layout: Some(&accumulate_pipeline_layout),
+                module: &shader,
+                entry_point: Some("cs_accumulate"),
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+                cache: None,
+            });
+        let display_pipeline = create_display_pipeline(
+            device,
+            &display_pipeline_layout,
+            &shader,
+            format,
+            sample_count,
+        );
+        let targets = create_targets(
+            device,
+            &weight,
+            &accumulate_layout,
+            &display_layout,
+            width,
+            height,
+        );
+        Self {
+            enabled: false,
+            format,
+            sample_count,
+            scene_pipeline: create_render_pipeline(
+                device,
+                scene_layout,
+                scene_shader,
+                FRAME_FORMAT,
+                1,
+            ),
+            shader,
+            weight,
+            accumulate_layout,
+            display_layout,
+            display_pipeline_layout,
+            accumulate_pipeline,
+            display_pipeline,
+            targets,
+            frames: 0,
+            current: 0,
+            last: None,
+        }
+    }
+
+    pub fn toggle(&mut self) {
+        self.enabled = !self.enabled;
+        self.reset();
+    }
+
+    // the next frame starts a new average
+    pub fn reset(&mut self) {
+        self.frames = 0;
+        self.last = None;
+    }
+
+    pub fn frames(&self) -> u32 {
+        self.frames
+    }
+
+    // follows a shader reload
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn set_shader(
+        &mut self,
+        device: &wgpu::Device,
+        scene_layout: &wgpu::PipelineLayout,
+        scene_shader: &wgpu::ShaderModule,
+    ) {
+        self.scene_pipeline =
+            create_render_pipeline(device, scene_layout, scene_shader, FRAME_FORMAT, 1);
+        self.reset();
+    }
+
+    pub fn set_sample_count(&mut self, device: &wgpu::Device, sample_count: u32) {
+        self.sample_count = sample_count;
+        self.display_pipeline = create_display_pipeline(
+            device,
+            &self.display_pipeline_layout,
+            &self.shader,
+            self.format,
+            sample_count,
+        );
+    }
+
+    pub fn resize(&mut self, device: &wgpu::Device, width: u32, height: u32) {
+        self.targets = create_targets(
+            device,
+            &self.weight,
+            &self.accumulate_layout,
+            &self.display_layout,
+            width,
+            height,
+        );
+        self.reset();
+    }
+
+    // counts this frame into the average, starting over if any of the
+    // uniforms changed since it was last called, the time included, and gives
+    // `globals` its sub-pixel offset; the uniforms must then be uploaded for
+    // `render`
+    pub fn advance(&mut self, queue: &wgpu::Queue, globals: &mut Globals) {
+        let unchanged = self
+            .last
+            .is_some_and(|last| bytemuck::bytes_of(&last) == bytemuck::bytes_of(globals));
+        if !unchanged {
+            self.frames = 0;
+        }
+        self.last = Some(*globals);
+        self.frames += 1;
+        // the first frame isn't offset, so it matches the view without
+        // accumulation
+        globals.jitter = [halton(self.frames, 2) - 0.5, halton(self.frames, 3) - 0.5];
+        globals.frame = self.frames;
+        let weight = [1.0 / self.frames as f32, 0.0, 0.0, 0.0];
+        queue.write_buffer(&self.weight, 0, bytemuck::cast_slice(&weight));
+    }
+
+    // renders the scene with `globals_bind_group` and folds it into the average
+    pub fn render(
+        &mut self,
+        encoder: &mut wgpu::CommandEncoder,
+        globals_bind_group: &wgpu::BindGroup,
+    ) {
+        {
+            let mut rpass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Accumulate Frame Pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: &self.targets.frame,
+                    depth_slice: None,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+            rpass.set_pipeline(&self.scene_pipeline);
+            rpass.set_bind_group(0, globals_bind_group, &[]);
+            rpass.draw(0..3, 0..1);
+        }
+        let mut cpass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+            label: Some("Accumulate Pass"),
+            timestamp_writes: None,
+        });
+        cpass.set_pipeline(&self.accumulate_pipeline);
+        cpass.set_bind_group(0, &self.targets.accumulate[self.current], &[]);
+        let (width, height) = self.targets.size;
+        cpass.dispatch_workgroups(
+            width.div_ceil(WORKGROUP_SIZE),
+            height.div_ceil(WORKGROUP_SIZE),
+            1,
+        );
+        self.current = 1 - self.current;
+    }
+
+    pub fn draw(&self, rpass: &mut wgpu::RenderPass) {
+        rpass.set_pipeline(&self.display_pipeline);
+        rpass.set_bind_group(0, &self.targets.display[self.current], &[]);
+        rpass.draw(0..3, 0..1);
+    }
+}
+
+// the radical inverse of `index` in `base`: successive indices spread evenly
+// over 0..1, so any run of frames covers the pixel well
+fn halton(mut index: u32, base: u32) -> f32 {
+    let mut result = 0.0;
+    let mut f = 1.0;
+    while index > 0 {
+        f /= base as f32;
+        result += f * (index % base) as f32;
+        index /= base;
+    }
+    result
+}
+
+fn create_targets(
+    device: &wgpu::Device,
+    weight: &wgpu::Buffer,
+    accumulate_layout: &wgpu::BindGroupLayout,
+    display_layout: &wgpu::BindGroupLayout,
+    width: u32,
+    height: u32,
+) -> Targets {
+    let texture = |label, format, usage| {
+        device
+            .create_texture(&wgpu::TextureDescriptor {
+                label: Some(label),
+                size: wgpu::Extent3d {
+                    width,
+                    height,
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format,
+                usage: usage | wgpu::TextureUsages::TEXTURE_BINDING,
+                view_formats: &[],
+            })
+            .create_view(&wgpu::TextureViewDescriptor::default())
+    };
+    let frame = texture(
+        "Accumulate Frame",
+        FRAME_FORMAT,
+        wgpu::TextureUsages::RENDER_ATTACHMENT,
+    );
+    let history = ["Accumulate History A", "Accumulate History B"]
+        .map(|label| texture(label, HISTORY_FORMAT, wgpu::TextureUsages::STORAGE_BINDING));
+    let accumulate = [0, 1].map(|i| {
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Accumulate BG"),
+            layout: accumulate_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: weight.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::TextureView(&frame),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: wgpu::BindingResource::TextureView(&history[i]),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 3,
+                    resource: wgpu::BindingResource::TextureView(&history[1 - i]),
+                },
+            ],
+        })
+    });
+    let display = [0, 1].map(|i| {
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Accumulate Display BG"),
+            layout: display_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 4,
+                resource: wgpu::BindingResource::TextureView(&history[i]),
+            }],
+        })
+    });
+    Targets {
+        frame,
+        size: (width, height),
+        accumulate,
+        display,
+    }
+}
+
+fn create_display_pipeline(
+    device: &wgpu::Device,
+    layout: &wgpu::PipelineLayout,
+    shader: &wgpu::ShaderModule,
+    format: wgpu::TextureFormat,
+    sample_count: u32,
+) -> wgpu::RenderPipeline {
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some("Accumulate Display Pipeline"),
+        layout: Some(layout),
+        vertex: wgpu::VertexState {
+            module: shader,
+            entry_point: Some("vs_fullscreen"),
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+            buffers: &[],
+        },
+        primitive: wgpu::PrimitiveState::default(),
+        depth_stencil: None,
+        multisample: wgpu::MultisampleState {
+            count: sample_count,
+            mask: !0,
+            alpha_to_coverage_enabled: false,
+        },
+        fragment: Some(wgpu::FragmentState {
+            module: shader,
+            entry_point: Some("fs_display"),
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+            targets: &[Some(wgpu::ColorTargetState {
+                format,
+                blend: Some(wgpu::BlendState::REPLACE),
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+        }),
+        multiview: None,
+        cache: None,
+    })
+}
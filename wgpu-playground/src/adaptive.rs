@@ -0,0 +1,126 @@
+// adaptive march steps (`G`): a prepass marches one ray through the middle of
+// each cell of a coarse grid over the screen and records how many steps it
+// took; the full-resolution pass then caps each pixel's march at what its
+// neighbourhood of cells needed, with some to spare, instead of at
+// `max_steps`, so the rays in regions that finish quickly give up early
+// rather than crawl along near misses
+use crate::create_fragment_pipeline;
+
+// cells across and down, whatever the resolution; the prepass is a few
+// thousand rays, next to nothing against the full frame
+const GRID_SIZE: u32 = 64;
+// the steps each cell took, as a fraction of `max_steps`; half floats are
+// plenty, and unlike 32-bit ones they take the scene pipelines' blend state
+const GRID_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::R16Float;
+
+pub struct AdaptiveSteps {
+    pub enabled: bool,
+    // the prepass renders here and is copied into `grid`, which the globals
+    // bind group holds, so the pass never writes what it has bound
+    target: wgpu::Texture,
+    target_view: wgpu::TextureView,
+    grid: wgpu::Texture,
+    grid_view: wgpu::TextureView,
+    // none when the shader has no `fs_steps`, which custom shaders needn't
+    pipeline: Option<wgpu::RenderPipeline>,
+}
+
+impl AdaptiveSteps {
+    pub fn new(device: &wgpu::Device) -> Self {
+        let create_texture = |label, usage| {
+            device.create_texture(&wgpu::TextureDescriptor {
+                label: Some(label),
+                size: wgpu::Extent3d {
+                    width: GRID_SIZE,
+                    height: GRID_SIZE,
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: GRID_FORMAT,
+                usage,
+                view_formats: &[],
+            })
+        };
+        let target = create_texture(
+            "Step Prepass Target",
+            wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+        );
+        let grid = create_texture(
+            "Step Grid",
+            wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+        );
+        Self {
+            enabled: false,
+            target_view: target.create_view(&wgpu::TextureViewDescriptor::default()),
+            target,
+            grid_view: grid.create_view(&wgpu::TextureViewDescriptor::default()),
+            grid,
+            pipeline: None,
+        }
+    }
+
+    // for the globals bind group
+    pub fn grid_view(&self) -> &wgpu::TextureView {
+        &self.grid_view
+    }
+
+    // builds the prepass from the scene shader, at startup and after a reload
+    pub fn set_shader(
+        &mut self,
+        device: &wgpu::Device,
+        pipeline_layout: &wgpu::PipelineLayout,
+        shader: &wgpu::ShaderModule,
+        source: &str,
+    ) {
+        self.pipeline = source.contains("fn fs_steps").then(|| {
+            create_fragment_pipeline(device, pipeline_layout, shader, "fs_steps", GRID_FORMAT, 1)
+        });
+    }
+
+    // whether the frame's march is capped from the grid
+    pub fn active(&self) -> bool {
+        self.enabled && self.pipeline.is_some()
+    }
+
+    // fills the grid from the frame's uniforms; run before the scene passes
+    pub fn prepass(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        globals_bind_group: &wgpu::BindGroup,
+    ) {
+        let Some(pipeline) = self.pipeline.as_ref().filter(|_| self.enabled) else {
+            return;
+        };
+        {
+            let mut rpass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Step Prepass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: &self.target_view,
+                    depth_slice: None,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color::WHITE),
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+            rpass.set_pipeline(pipeline);
+            rpass.set_bind_group(0, globals_bind_group, &[]);
+            rpass.draw(0..3, 0..1);
+        }
+        encoder.copy_texture_to_texture(
+            self.target.as_image_copy(),
+            self.grid.as_image_copy(),
+            wgpu::Extent3d {
+                width: GRID_SIZE,
+                height: GRID_SIZE,
+                depth_or_array_layers: 1,
+            },
+        );
+    }
+}
@@ -0,0 +1,115 @@
+use std::sync::{Arc, Mutex};
+
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use cpal::{FromSample, Sample, SizedSample};
+use rustfft::{num_complex::Complex, FftPlanner};
+
+// samples per FFT block; ~21 ms at 48 kHz
+const BLOCK: usize = 1024;
+
+// (low, high) edges in Hz of the bass, mid and treble bands
+const BANDS: [(f32, f32); 3] = [(20.0, 250.0), (250.0, 2000.0), (2000.0, 8000.0)];
+
+// envelope follower rates per block: jump up on a hit, decay slowly after it
+const ATTACK: f32 = 0.6;
+const RELEASE: f32 = 0.08;
+
+// band amplitudes are tiny for a typical mic; this brings loud music near 1
+const GAIN: f32 = 40.0;
+
+// captures the default input device and keeps smoothed band levels for the
+// shader; the stream stops when this is dropped
+pub struct Audio {
+    _stream: cpal::Stream,
+    levels: Arc<Mutex<[f32; 3]>>,
+}
+
+impl Audio {
+    pub fn start() -> Result<Self, String> {
+        let device = cpal::default_host()
+            .default_input_device()
+            .ok_or("no input device")?;
+        let config = device
+            .default_input_config()
+            .map_err(|err| err.to_string())?;
+        let levels = Arc::new(Mutex::new([0.0; 3]));
+        let stream = match config.sample_format() {
+            cpal::SampleFormat::F32 => build_stream::<f32>(&device, &config, &levels),
+            cpal::SampleFormat::I16 => build_stream::<i16>(&device, &config, &levels),
+            cpal::SampleFormat::U16 => build_stream::<u16>(&device, &config, &levels),
+            other => return Err(format!("unsupported sample format {other}")),
+        }?;
+        stream.play().map_err(|err| err.to_string())?;
+        if let Ok(description) = device.description() {
+            println!("Audio input: {}", description.name());
+        }
+        Ok(Self {
+            _stream: stream,
+            levels,
+        })
+    }
+
+    // bass, mid and treble, roughly 0..1
+    pub fn levels(&self) -> [f32; 3] {
+        *self.levels.lock().unwrap()
+    }
+}
+
+fn build_stream<T>(
+    device: &cpal::Device,
+    config: &cpal::SupportedStreamConfig,
+    levels: &Arc<Mutex<[f32; 3]>>,
+) -> Result<cpal::Stream, String>
+where
+    T: SizedSample,
+    f32: FromSample<T>,
+{
+    let channels = config.channels() as usize;
+    let sample_rate = config.sample_rate() as f32;
+    let levels = Arc::clone(levels);
+    let fft = FftPlanner::new().plan_fft_forward(BLOCK);
+    let mut block = Vec::with_capacity(BLOCK);
+    let mut envelope = [0.0f32; 3];
+
+    device
+        .build_input_stream(
+            config.config(),
+            move |data: &[T], _: &cpal::InputCallbackInfo| {
+                // mixed down to mono
+                for frame in data.chunks(channels) {
+                    let sum: f32 = frame.iter().map(|&s| f32::from_sample(s)).sum();
+                    block.push(sum / channels as f32);
+                    if block.len() < BLOCK {
+                        continue;
+                    }
+                    // Hann window against spectral leakage between the bands
+                    let mut spectrum: Vec<Complex<f32>> = block
+                        .drain(..)
+                        .enumerate()
+                        .map(|(i, s)| {
+                            let w =
+                                0.5 - 0.5 * (std::f32::consts::TAU * i as f32 / BLOCK as f32).cos();
+                            Complex::new(s * w, 0.0)
+                        })
+                        .collect();
+                    fft.process(&mut spectrum);
+
+                    let bin_hz = sample_rate / BLOCK as f32;
+                    for (env, &(lo, hi)) in envelope.iter_mut().zip(&BANDS) {
+                        let bins = (lo / bin_hz) as usize..((hi / bin_hz) as usize).min(BLOCK / 2);
+                        let count = bins.len().max(1) as f32;
+                        let mean: f32 =
+                            spectrum[bins].iter().map(|c| c.norm()).sum::<f32>() / count;
+                        // a windowed sine of amplitude A peaks at about A * BLOCK / 4
+                        let level = (mean * 4.0 / BLOCK as f32 * GAIN).min(1.0);
+                        let rate = if level > *env { ATTACK } else { RELEASE };
+                        *env += (level - *env) * rate;
+                    }
+                    *levels.lock().unwrap() = envelope;
+                }
+            },
+            |err| eprintln!("Audio input error: {err}"),
+            None,
+        )
+        .map_err(|err| err.to_string())
+}
@@ -0,0 +1,253 @@
+// auto exposure: every so often the SDF scene is also rendered, unexposed and
+// linear, into a tiny offscreen texture that is read back without stalling the
+// frame; its log-average luminance sets the exposure that would bring it to
+// `KEY`, which the real exposure then eases towards
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+// web-time for the browser, like the main loop
+use web_time::Instant;
+
+use crate::{create_render_pipeline, Globals};
+
+// the metered view: a square from the middle of the screen, so the meter
+// weighs the centre most and doesn't need resizing with the window
+const METER_SIZE: u32 = 64;
+// rgba16float, 8 bytes a texel; 64 of them make a row of 512, a multiple of
+// the 256-byte copy alignment
+const METER_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba16Float;
+const BYTES_PER_ROW: u32 = METER_SIZE * 8;
+// average luminance the exposure aims for; the default nugget view meters about
+// this at exposure 1
+const KEY: f32 = 0.033;
+// seconds for the exposure to close about two thirds of the gap to its target
+const ADAPT_TIME: f32 = 0.5;
+// like the manual exposure keys
+const EXPOSURE_RANGE: std::ops::RangeInclusive<f32> = 1.0 / 16.0..=16.0;
+
+pub struct AutoExposure {
+    pub enabled: bool,
+    view: wgpu::TextureView,
+    texture: wgpu::Texture,
+    readback: wgpu::Buffer,
+    globals: wgpu::Buffer,
+    bind_group: wgpu::BindGroup,
+    pipeline: wgpu::RenderPipeline,
+    // a meter pass was submitted and its readback hasn't been consumed yet
+    in_flight: bool,
+    // set from the map callback once the readback can be read
+    mapped: Arc<AtomicBool>,
+    target: Option<f32>,
+    last_update: Instant,
+}
+
+impl AutoExposure {
+    pub fn new(
+        device: &wgpu::Device,
+        globals_layout: &wgpu::BindGroupLayout,
+        step_grid: &wgpu::TextureView,
+        pipeline_layout: &wgpu::PipelineLayout,
+        shader: &wgpu::ShaderModule,
+    ) -> Self {
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Exposure Meter"),
+            size: wgpu::Extent3d {
+                width: METER_SIZE,
+                height: METER_SIZE,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: METER_FORMAT,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let readback = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Exposure Readback"),
+            size: (BYTES_PER_ROW * METER_SIZE) as u64,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+        // the meter's own copy of the uniforms, so it can render unexposed
+        let globals = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Exposure Meter Globals"),
+            size: std::mem::size_of::<Globals>() as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Exposure Meter BG"),
+            layout: globals_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: globals.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::TextureView(step_grid),
+                },
+            ],
+        });
+        Self {
+            enabled: false,
+            view,
+            texture,
+            readback,
+            globals,
+            bind_group,
+            pipeline: create_render_pipeline(device, pipeline_layout, shader, METER_FORMAT, 1),
+            in_flight: false,
+            mapped: Arc::new(AtomicBool::new(false)),
+            target: None,
+            last_update: Instant::now(),
+        }
+    }
+
+    // follows a shader reload, which only the native build has
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn set_shader(
+        &mut self,
+        device: &wgpu::Device,
+        pipeline_layout: &wgpu::PipelineLayout,
+        shader: &wgpu::ShaderModule,
+    ) {
+        self.pipeline = create_render_pipeline(device, pipeline_layout, shader, METER_FORMAT, 1);
+    }
+
+    pub fn toggle(&mut self) {
+        self.enabled = !self.enabled;
+        // a stale target would pull towards a view that's long gone
+        self.target = None;
+        self.last_update = Instant::now();
+    }
+
+    // renders the frame's uniforms into the meter, unless the last reading is
+    // still on its way; returns whether it did, so `map` follows the submit
+    pub fn meter(
+        &mut self,
+        queue: &wgpu::Queue,
+        encoder: &mut wgpu::CommandEncoder,
+        globals: &Globals,
+    ) -> bool {
+        if !self.enabled || self.in_flight {
+            return false;
+        }
+        let globals = Globals {
+            resolution: [METER_SIZE as f32; 2],
+            exposure: 1.0,
+            gamma: 1.0,
+            // measured before any curve, like before exposure and gamma
+            tonemap: 0,
+            // the grid covers the screen, not the metered square
+            adaptive_steps: 0,
+            ..*globals
+        };
+        queue.write_buffer(&self.globals, 0, bytemuck::bytes_of(&globals));
+        {
+            let mut rpass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Exposure Meter Pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: &self.view,
+                    depth_slice: None,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+            rpass.set_pipeline(&self.pipeline);
+            rpass.set_bind_group(0, &self.bind_group, &[]);
+            rpass.draw(0..3, 0..1);
+        }
+        encoder.copy_texture_to_buffer(
+            self.texture.as_image_copy(),
+            wgpu::TexelCopyBufferInfo {
+                buffer: &self.readback,
+                layout: wgpu::TexelCopyBufferLayout {
+                    offset: 0,
+                    bytes_per_row: Some(BYTES_PER_ROW),
+                    rows_per_image: None,
+                },
+            },
+            wgpu::Extent3d {
+                width: METER_SIZE,
+                height: METER_SIZE,
+                depth_or_array_layers: 1,
+            },
+        );
+        self.in_flight = true;
+        true
+    }
+
+    // asks for the readback once the meter pass is submitted
+    pub fn map(&self) {
+        let mapped = self.mapped.clone();
+        self.readback
+            .slice(..)
+            .map_async(wgpu::MapMode::Read, move |result| {
+                mapped.store(result.is_ok(), Ordering::Release);
+            });
+    }
+
+    // takes in a finished reading if there is one and eases `exposure` towards
+    // its target; called every frame, it never waits on the GPU
+    pub fn update(&mut self, device: &wgpu::Device, exposure: &mut f32) {
+        let now = Instant::now();
+        let dt = (now - self.last_update).as_secs_f32();
+        self.last_update = now;
+        if !self.enabled {
+            return;
+        }
+        if self.in_flight {
+            device.poll(wgpu::PollType::Poll).ok();
+            if self.mapped.swap(false, Ordering::Acquire) {
+                let average = log_average_luminance(&self.readback.slice(..).get_mapped_range());
+                self.readback.unmap();
+                self.in_flight = false;
+                self.target =
+                    Some((KEY / average).clamp(*EXPOSURE_RANGE.start(), *EXPOSURE_RANGE.end()));
+            }
+        }
+        if let Some(target) = self.target {
+            // eased in stops, so brightening and darkening take as long
+            let blend = 1.0 - (-dt / ADAPT_TIME).exp();
+            *exposure = (exposure.ln() + (target.ln() - exposure.ln()) * blend).exp();
+        }
+    }
+}
+
+// exp of the mean log luminance, which a few bright pixels can't dominate
+// the way they would a plain mean
+fn log_average_luminance(texels: &[u8]) -> f32 {
+    // keeps black pixels from sending the log to minus infinity
+    const DELTA: f32 = 1e-4;
+    let sum: f32 = texels
+        .chunks_exact(8)
+        .map(|texel| {
+            let channel = |i: usize| f16_to_f32(u16::from_le_bytes([texel[i], texel[i + 1]]));
+            let luminance = 0.2126 * channel(0) + 0.7152 * channel(2) + 0.0722 * channel(4);
+            (luminance.max(0.0) + DELTA).ln()
+        })
+        .sum();
+    (sum / (METER_SIZE * METER_SIZE) as f32).exp()
+}
+
+// IEEE half precision, as the meter texture stores it
+fn f16_to_f32(bits: u16) -> f32 {
+    let sign = if bits & 0x8000 != 0 { -1.0 } else { 1.0 };
+    let exponent = ((bits >> 10) & 0x1f) as i32;
+    let mantissa = (bits & 0x3ff) as f32;
+    sign * match exponent {
+        0 => mantissa * 2f32.powi(-24),
+        0x1f if mantissa == 0.0 => f32::INFINITY,
+        0x1f => f32::NAN,
+        _ => (1.0 + mantissa / 1024.0) * 2f32.powi(exponent - 15),
+    }
+}
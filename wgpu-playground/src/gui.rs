@@ -0,0 +1,212 @@
+use winit::{event::WindowEvent, window::Window};
+
+use crate::{
+    Palette, SceneParams, DISTANCE_RANGE, FOV_RANGE, LIGHT_ELEVATION_RANGE, MAX_AA_SAMPLES,
+    SUBSURFACE_DEPTH_RANGE, SUBSURFACE_STRENGTH_RANGE,
+};
+
+// egui overlay for live uniform tweaking. It draws in its own pass on top of the
+// resolved frame, after the fullscreen-triangle pass, so the SDF pipeline (and its
+// MSAA target) never has to know about it.
+pub struct Gui {
+    ctx: egui::Context,
+    state: egui_winit::State,
+    renderer: egui_wgpu::Renderer,
+    pub visible: bool,
+}
+
+impl Gui {
+    pub fn new(window: &Window, device: &wgpu::Device, format: wgpu::TextureFormat) -> Self {
+        let ctx = egui::Context::default();
+        let state = egui_winit::State::new(
+            ctx.clone(),
+            egui::ViewportId::ROOT,
+            window,
+            Some(window.scale_factor() as f32),
+            None,
+            Some(device.limits().max_texture_dimension_2d as usize),
+        );
+        let renderer =
+            egui_wgpu::Renderer::new(device, format, egui_wgpu::RendererOptions::default());
+        Self {
+            ctx,
+            state,
+            renderer,
+            visible: true,
+        }
+    }
+
+    // true when egui wants the event for itself and the scene should ignore it
+    pub fn on_window_event(&mut self, window: &Window, event: &WindowEvent) -> bool {
+        self.visible && self.state.on_window_event(window, event).consumed
+    }
+
+    // builds the panel and records its draw into `encoder`; the returned command
+    // buffers (egui's buffer uploads) must be submitted before the encoder
+    #[allow(clippy::too_many_arguments)]
+    pub fn draw(
+        &mut self,
+        window: &Window,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        encoder: &mut wgpu::CommandEncoder,
+        view: &wgpu::TextureView,
+        // the surface's, which can be ahead of the window's own during a resize
+        size_in_pixels: [u32; 2],
+        params: &mut SceneParams,
+        time_scale: &mut f32,
+        shader_error: Option<&str>,
+    ) -> Vec<wgpu::CommandBuffer> {
+        let input = self.state.take_egui_input(window);
+        let visible = self.visible;
+        let output = self.ctx.run(input, |ctx| {
+            if visible {
+                panel(ctx, params, time_scale);
+            }
+            if let Some(err) = shader_error {
+                error_window(ctx, err);
+            }
+        });
+        self.state
+            .handle_platform_output(window, output.platform_output);
+
+        let jobs = self.ctx.tessellate(output.shapes, output.pixels_per_point);
+        let screen = egui_wgpu::ScreenDescriptor {
+            size_in_pixels,
+            pixels_per_point: output.pixels_per_point,
+        };
+
+        for (id, delta) in &output.textures_delta.set {
+            self.renderer.update_texture(device, queue, *id, delta);
+        }
+        let uploads = self
+            .renderer
+            .update_buffers(device, queue, encoder, &jobs, &screen);
+
+        {
+            let mut rpass = encoder
+                .begin_render_pass(&wgpu::RenderPassDescriptor {
+                    label: Some("egui"),
+                    color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                        view,
+                        depth_slice: None,
+                        resolve_target: None,
+                        ops: wgpu::Operations {
+                            load: wgpu::LoadOp::Load,
+                            store: wgpu::StoreOp::Store,
+                        },
+                    })],
+                    depth_stencil_attachment: None,
+                    timestamp_writes: None,
+                    occlusion_query_set: None,
+                })
+                .forget_lifetime();
+            self.renderer.render(&mut rpass, &jobs, &screen);
+        }
+
+        for id in &output.textures_delta.free {
+            self.renderer.free_texture(id);
+        }
+        uploads
+    }
+}
+
+fn panel(ctx: &egui::Context, params: &mut SceneParams, time_scale: &mut f32) {
+    egui::Window::new("Uniforms")
+        .default_pos([12.0, 12.0])
+        .resizable(false)
+        .show(ctx, |ui| {
+            ui.add(egui::Slider::new(time_scale, 0.0..=4.0).text("time scale"));
+            ui.separator();
+            ui.add(
+                egui::Slider::new(&mut params.light_azimuth, -180.0..=180.0)
+                    .text("light azimuth (°)"),
+            );
+            ui.add(
+                egui::Slider::new(&mut params.light_elevation, LIGHT_ELEVATION_RANGE)
+                    .text("light elevation (°)"),
+            );
+            ui.add(egui::Slider::new(&mut params.shadow_k, 1.0..=64.0).text("shadow k"));
+            ui.add(egui::Slider::new(&mut params.ao_strength, 0.0..=4.0).text("AO strength"));
+            ui.add(
+                egui::Slider::new(&mut params.subsurface_strength, SUBSURFACE_STRENGTH_RANGE)
+                    .text("subsurface strength"),
+            );
+            ui.add(
+                egui::Slider::new(&mut params.subsurface_depth, SUBSURFACE_DEPTH_RANGE)
+                    .logarithmic(true)
+                    .text("subsurface depth"),
+            );
+            ui.separator();
+            ui.add(egui::Slider::new(&mut params.max_steps, 16..=512).text("march steps"));
+            ui.add(
+                egui::Slider::new(&mut params.aa_samples, 1..=MAX_AA_SAMPLES)
+                    .text("samples per pixel"),
+            );
+            ui.add(
+                egui::Slider::new(&mut params.exposure, 1.0 / 16.0..=16.0)
+                    .logarithmic(true)
+                    .text("exposure"),
+            );
+            ui.add(egui::Slider::new(&mut params.gamma, 0.5..=3.0).text("gamma"));
+            ui.add(
+                egui::Slider::new(&mut params.camera.distance, DISTANCE_RANGE)
+                    .text("camera distance"),
+            );
+            ui.add(egui::Slider::new(&mut params.fov, FOV_RANGE).text("field of view (°)"));
+            ui.collapsing("Mandelbulb", |ui| {
+                ui.add(egui::Slider::new(&mut params.bulb_power, 2.0..=16.0).text("power"));
+                ui.add(egui::Slider::new(&mut params.bulb_iterations, 1..=16).text("iterations"));
+            });
+            ui.collapsing("Menger sponge", |ui| {
+                ui.add(egui::Slider::new(&mut params.sponge_iterations, 0..=6).text("iterations"));
+            });
+            ui.collapsing("Repetition", |ui| {
+                ui.add(egui::Slider::new(&mut params.repeat_period, 0.5..=3.0).text("grid period"));
+                ui.add(egui::Slider::new(&mut params.ring_count, 1..=24).text("ring copies"));
+            });
+            ui.add(egui::Slider::new(&mut params.fog_density, 0.0..=1.0).text("fog density"));
+            ui.collapsing("Colors", |ui| palette_editor(ui, &mut params.palette));
+            ui.label("F1 hides this panel");
+        });
+}
+
+fn palette_editor(ui: &mut egui::Ui, palette: &mut Palette) {
+    egui::Grid::new("palette").show(ui, |ui| {
+        for (label, color) in [
+            ("base", &mut palette.base),
+            ("rim", &mut palette.rim),
+            ("sky top", &mut palette.bg_top),
+            ("sky bottom", &mut palette.bg_bottom),
+            ("fog", &mut palette.fog),
+            ("subsurface", &mut palette.subsurface),
+        ] {
+            ui.label(label);
+            ui.color_edit_button_rgb(color);
+            ui.end_row();
+        }
+    });
+    if ui
+        .add_enabled(*palette != Palette::default(), egui::Button::new("Reset"))
+        .clicked()
+    {
+        *palette = Palette::default();
+    }
+}
+
+// shown whether or not the panel is, until a reload compiles cleanly
+fn error_window(ctx: &egui::Context, err: &str) {
+    egui::Window::new("Shader error")
+        .anchor(egui::Align2::CENTER_BOTTOM, [0.0, -12.0])
+        .resizable(false)
+        .collapsible(false)
+        .frame(egui::Frame::window(&ctx.style()).fill(egui::Color32::from_rgb(90, 10, 10)))
+        .show(ctx, |ui| {
+            ui.label(
+                egui::RichText::new(err)
+                    .monospace()
+                    .color(egui::Color32::WHITE),
+            );
+            ui.label("Fix the shader and press R to reload; the last good pipeline keeps running.");
+        });
+}
@@ -0,0 +1,313 @@
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use crate::accumulate::Accumulator;
+use crate::adaptive::AdaptiveSteps;
+use crate::ssaa::Supersampler;
+use crate::{
+    build_pipeline, create_globals_binding, create_pipeline_layout, request_device, GlobalsBinding,
+    SceneParams,
+};
+
+// sRGB like the swapchain format the window picks, so stills match the live view
+const FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8UnormSrgb;
+
+// renders the scene into an offscreen texture and reads it back, no window or
+// surface involved
+pub struct HeadlessRenderer {
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    globals: GlobalsBinding,
+    pipeline: wgpu::RenderPipeline,
+    // with `--adaptive-steps`
+    adaptive: AdaptiveSteps,
+    ssaa: Supersampler,
+    // with `--accumulate`, and how many frames it averages
+    accumulator: Option<(Accumulator, u32)>,
+    texture: wgpu::Texture,
+    readback: wgpu::Buffer,
+    width: u32,
+    height: u32,
+    padded_bytes_per_row: u32,
+}
+
+impl HeadlessRenderer {
+    pub async fn new(
+        width: u32,
+        height: u32,
+        shader_source: &str,
+        ssaa_scale: u32,
+        accumulate: u32,
+        adaptive_steps: bool,
+    ) -> Result<Self, String> {
+        let instance = wgpu::Instance::default();
+        let adapter = instance
+            .request_adapter(&wgpu::RequestAdapterOptions {
+                power_preference: wgpu::PowerPreference::HighPerformance,
+                compatible_surface: None,
+                force_fallback_adapter: false,
+            })
+            .await
+            .expect("No suitable GPU adapters found on the system!");
+        let (device, queue) = request_device(&adapter).await;
+
+        let mut adaptive = AdaptiveSteps::new(&device);
+        let globals = create_globals_binding(&device, adaptive.grid_view());
+        let pipeline_layout = create_pipeline_layout(&device, &globals.layout);
+        let (shader, pipeline) =
+            build_pipeline(&device, &pipeline_layout, shader_source, FORMAT, 1).await?;
+        adaptive.set_shader(&device, &pipeline_layout, &shader, shader_source);
+        adaptive.enabled = adaptive_steps;
+        if !Supersampler::fits(&device, ssaa_scale, width, height) {
+            return Err(format!(
+                "{width}x{height} at {ssaa_scale}x SSAA exceeds the GPU's texture size limit"
+            ));
+        }
+        let ssaa = Supersampler::new(&device, FORMAT, 1, ssaa_scale, width, height);
+        let accumulator = (accumulate > 1).then(|| {
+            let accumulator =
+                Accumulator::new(&device, &pipeline_layout, &shader, FORMAT, 1, width, height);
+            (accumulator, accumulate)
+        });
+
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Headless Target"),
+            size: wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: FORMAT,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[],
+        });
+
+        let padded_bytes_per_row = image_io::padded_bytes_per_row(width);
+        let readback = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Headless Readback"),
+            size: (padded_bytes_per_row * height) as u64,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+
+        Ok(Self {
+            device,
+            queue,
+            globals,
+            pipeline,
+            adaptive,
+            ssaa,
+            accumulator,
+            texture,
+            readback,
+            width,
+            height,
+            padded_bytes_per_row,
+        })
+    }
+
+    // tightly packed RGBA8 rows, already sRGB encoded
+    pub fn render(&mut self, params: &SceneParams, time: f32) -> Vec<u8> {
+        // accumulating takes the place of supersampling
+        let scale = match self.accumulator {
+            Some(_) => 1.0,
+            None => self.ssaa.scale() as f32,
+        };
+        let mut globals = params.globals(
+            time,
+            [self.width as f32 * scale, self.height as f32 * scale],
+        );
+        globals.adaptive_steps = self.adaptive.active() as u32;
+        // each accumulated frame has its own jitter, so its own submit
+        if let Some((accumulator, frames)) = &mut self.accumulator {
+            accumulator.reset();
+            for _ in 0..*frames {
+                let mut jittered = globals;
+                accumulator.advance(&self.queue, &mut jittered);
+                self.queue
+                    .write_buffer(&self.globals.buffer, 0, bytemuck::bytes_of(&jittered));
+                let mut encoder = self
+                    .device
+                    .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+                self.adaptive
+                    .prepass(&mut encoder, &self.globals.bind_group);
+                accumulator.render(&mut encoder, &self.globals.bind_group);
+                self.queue.submit(Some(encoder.finish()));
+            }
+        }
+        self.queue
+            .write_buffer(&self.globals.buffer, 0, bytemuck::bytes_of(&globals));
+
+        let view = self
+            .texture
+            .create_view(&wgpu::TextureViewDescriptor::default());
+        let mut encoder = self
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+        if self.accumulator.is_none() {
+            self.adaptive
+                .prepass(&mut encoder, &self.globals.bind_group);
+        }
+
+        // supersampled, the scene goes to the large target and is filtered
+        // down into the output; accumulated, the average is copied over
+        let scene_view = match self.accumulator {
+            Some(_) => &view,
+            None => self.ssaa.view().unwrap_or(&view),
+        };
+        {
+            let mut rpass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: None,
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: scene_view,
+                    depth_slice: None,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+            match &self.accumulator {
+                Some((accumulator, _)) => accumulator.draw(&mut rpass),
+                None => {
+                    rpass.set_pipeline(&self.pipeline);
+                    rpass.set_bind_group(0, &self.globals.bind_group, &[]);
+                    rpass.draw(0..3, 0..1);
+                }
+            }
+        }
+        if self.ssaa.view().is_some() && self.accumulator.is_none() {
+            let mut rpass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Downsample Pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: &view,
+                    depth_slice: None,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+            self.ssaa.draw(&mut rpass);
+        }
+
+        encoder.copy_texture_to_buffer(
+            self.texture.as_image_copy(),
+            wgpu::TexelCopyBufferInfo {
+                buffer: &self.readback,
+                layout: wgpu::TexelCopyBufferLayout {
+                    offset: 0,
+                    bytes_per_row: Some(self.padded_bytes_per_row),
+                    rows_per_image: None,
+                },
+            },
+            wgpu::Extent3d {
+                width: self.width,
+                height: self.height,
+                depth_or_array_layers: 1,
+            },
+        );
+        self.queue.submit(Some(encoder.finish()));
+
+        let slice = self.readback.slice(..);
+        let (tx, rx) = std::sync::mpsc::channel();
+        slice.map_async(wgpu::MapMode::Read, move |result| {
+            tx.send(result).ok();
+        });
+        self.device
+            .poll(wgpu::PollType::wait_indefinitely())
+            .expect("Failed to wait for the GPU");
+        rx.recv()
+            .expect("Readback callback dropped")
+            .expect("Failed to map readback buffer");
+
+        let pixels = image_io::unpad_rows(
+            &slice.get_mapped_range(),
+            self.width,
+            self.padded_bytes_per_row,
+        );
+        self.readback.unmap();
+        pixels
+    }
+
+    pub fn save(&mut self, params: &SceneParams, time: f32, path: &Path) -> image::ImageResult<()> {
+        let pixels = self.render(params, time);
+        image_io::write_png_rgba8(path, self.width, self.height, &pixels)
+    }
+}
+
+// a `--record start,end,fps,dir` request
+#[derive(Clone, Debug)]
+pub struct RecordSpec {
+    start: f32,
+    end: f32,
+    fps: f32,
+    dir: PathBuf,
+}
+
+impl FromStr for RecordSpec {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // the directory goes last so it may itself contain commas
+        let mut parts = s.splitn(4, ',');
+        let mut number = |name: &str| -> Result<f32, String> {
+            let part = parts.next().ok_or(format!("missing {name}"))?;
+            part.trim()
+                .parse()
+                .ok()
+                .filter(|number: &f32| number.is_finite())
+                .ok_or(format!("invalid {name} `{part}`"))
+        };
+        let start = number("start time")?;
+        let end = number("end time")?;
+        let fps = number("fps")?;
+        let dir = parts.next().ok_or("missing output directory")?.trim();
+        if end <= start {
+            return Err("end time must be after start time".into());
+        }
+        if fps <= 0.0 {
+            return Err("fps must be positive".into());
+        }
+        Ok(Self {
+            start,
+            end,
+            fps,
+            dir: PathBuf::from(dir),
+        })
+    }
+}
+
+// writes frame_00001.png, frame_00002.png, ... at evenly spaced shader times so
+// the sequence is reproducible regardless of how long each frame takes
+pub fn record(
+    renderer: &mut HeadlessRenderer,
+    params: &SceneParams,
+    spec: &RecordSpec,
+) -> image::ImageResult<()> {
+    std::fs::create_dir_all(&spec.dir)?;
+    let frames = ((spec.end - spec.start) * spec.fps).round().max(1.0) as u32;
+    for i in 0..frames {
+        let time = spec.start + i as f32 / spec.fps;
+        let path = spec.dir.join(format!("frame_{:05}.png", i + 1));
+        renderer.save(params, time, &path)?;
+        println!("Wrote {} (t = {time:.3})", path.display());
+    }
+    println!(
+        "Encode with: ffmpeg -framerate {} -i {}/frame_%05d.png -pix_fmt yuv420p out.mp4",
+        spec.fps,
+        spec.dir.display()
+    );
+    Ok(())
+}
@@ -1,23 +1,487 @@
-use std::time::Instant;
+mod accumulate;
+mod adaptive;
+#[cfg(not(target_arch = "wasm32"))]
+mod audio;
+mod exposure;
+mod gui;
+#[cfg(not(target_arch = "wasm32"))]
+mod headless;
+mod particles;
+mod reaction;
+#[cfg(not(target_arch = "wasm32"))]
+mod snapshot;
+mod snippet;
+mod ssaa;
 
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+#[cfg(not(target_arch = "wasm32"))]
+use clap::Parser;
+use math::{Mat4, Vec3};
 use wgpu::util::DeviceExt;
-use winit::{dpi::LogicalSize, event::*, event_loop::EventLoop};
+// std's Instant panics in the browser; natively web-time just re-exports it
+use web_time::Instant;
+use winit::{
+    dpi::LogicalSize,
+    event::*,
+    event_loop::EventLoop,
+    keyboard::{Key, NamedKey},
+    window::Fullscreen,
+};
+
+const WINDOW_TITLE: &str = "wgpu playground";
+
+// the window as first opened; `--title`, `--width` and `--height` natively
+struct WindowSettings {
+    title: String,
+    // logical pixels
+    width: u32,
+    height: u32,
+}
+
+impl Default for WindowSettings {
+    fn default() -> Self {
+        Self {
+            title: WINDOW_TITLE.to_string(),
+            width: 800,
+            height: 600,
+        }
+    }
+}
+
+// scenes selectable with the number keys, in the order of `map_scene`'s switch
+const SCENE_NAMES: &[&str] = &[
+    "nugget",
+    "tori",
+    "twisted box",
+    "mandelbulb",
+    "menger sponge",
+    "repetition",
+];
+
+// `fs_main` outputs, in the order of its `debug_mode` switch; `N` cycles them
+const DEBUG_VIEWS: &[&str] = &["lit", "normals", "steps", "depth", "march"];
+
+// the curves `fs_main` maps exposed color into 0..1 with, in the order of its
+// `tonemap` switch; `T` cycles them
+const TONEMAPS: &[&str] = &["none", "Reinhard", "ACES"];
+
+// what the window shows; `P` cycles through them
+#[derive(Clone, Copy, PartialEq)]
+enum Demo {
+    Sdf,
+    Particles,
+    ReactionDiffusion,
+}
+
+impl Demo {
+    fn next(self) -> Self {
+        match self {
+            Demo::Sdf => Demo::Particles,
+            Demo::Particles => Demo::ReactionDiffusion,
+            Demo::ReactionDiffusion => Demo::Sdf,
+        }
+    }
+
+    // shown in the title, in place of the SDF scene name
+    fn name(self) -> Option<&'static str> {
+        match self {
+            Demo::Sdf => None,
+            Demo::Particles => Some("particles"),
+            Demo::ReactionDiffusion => Some("reaction-diffusion"),
+        }
+    }
+}
 
 #[repr(C)]
 #[derive(Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
 struct Globals {
+    camera_pos: Vec3,
     time: f32,
-    _pad: [f32; 3],
+    camera_target: Vec3,
+    _pad: f32,
+    resolution: [f32; 2],
+    mouse: [f32; 2],
+    scene: u32,
+    shadow_k: f32,
+    ao_strength: f32,
+    exposure: f32,
+    gamma: f32,
+    max_steps: u32,
+    _pad1: [u32; 2],
+    base_color: [f32; 4],
+    rim_color: [f32; 4],
+    bg_top: [f32; 4],
+    bg_bottom: [f32; 4],
+    camera: Mat4,
+    audio: [f32; 3],
+    focal_length: f32,
+    debug_mode: u32,
+    bulb_power: f32,
+    bulb_iterations: u32,
+    fog_density: f32,
+    fog_color: [f32; 4],
+    jitter: [f32; 2],
+    frame: u32,
+    adaptive_steps: u32,
+    light_dir: Vec3,
+    sponge_iterations: u32,
+    repeat_period: f32,
+    ring_count: u32,
+    aa_samples: u32,
+    tonemap: u32,
+    subsurface_color: [f32; 4],
+    subsurface_strength: f32,
+    subsurface_depth: f32,
+    _pad2: [u32; 2],
+}
+
+// normalized cursor position before the cursor ever enters the window; picked so
+// the mouse-steered light starts out at the original fixed direction
+const DEFAULT_MOUSE: [f32; 2] = [0.3, 0.85];
+
+// `J`/`L` turn the light around the vertical axis and `I`/`K` raise and lower
+// it, at this many degrees a second while held
+const LIGHT_KEYS: [&str; 4] = ["j", "l", "i", "k"];
+const LIGHT_TURN_SPEED: f32 = 90.0;
+// short of straight up or down, where the azimuth stops meaning anything
+const LIGHT_ELEVATION_RANGE: std::ops::RangeInclusive<f32> = -89.0..=89.0;
+
+// radians of yaw/pitch per logical pixel of mouse drag
+const ORBIT_SENSITIVITY: f32 = 0.01;
+// keep pitch just shy of straight up/down so the look-at basis never flips
+const MAX_PITCH: f32 = 1.5;
+// eye-to-target distance: close enough to see the crunch without entering the
+// nugget, far enough to frame it small
+const DISTANCE_RANGE: std::ops::RangeInclusive<f32> = 1.5..=6.0;
+// distance factor per wheel notch, and pixels of touchpad scroll per notch
+const ZOOM_STEP: f32 = 0.9;
+const PIXELS_PER_LINE: f32 = 40.0;
+
+// seconds of shader time per arrow-key step while paused
+const TIME_STEP: f32 = 1.0 / 60.0;
+
+// penumbra sharpness for the soft shadows; larger is harder
+const DEFAULT_SHADOW_K: f32 = 8.0;
+const DEFAULT_AO_STRENGTH: f32 = 1.0;
+// how brightly the thin parts glow, and how far into the surface counts as
+// thin; about the size of the nugget's crunch
+const DEFAULT_SUBSURFACE_STRENGTH: f32 = 1.0;
+const DEFAULT_SUBSURFACE_DEPTH: f32 = 0.15;
+const SUBSURFACE_STRENGTH_RANGE: std::ops::RangeInclusive<f32> = 0.0..=4.0;
+const SUBSURFACE_DEPTH_RANGE: std::ops::RangeInclusive<f32> = 0.02..=1.0;
+
+// 1/1.25 reproduces the original baked-in pow(col, 0.8)
+const DEFAULT_GAMMA: f32 = 1.25;
+// quarter of a stop per key press
+const EXPOSURE_STEP: f32 = 1.189_207_1;
+
+const DEFAULT_MAX_STEPS: u32 = 96;
+
+// the classic power-8 bulb
+const DEFAULT_BULB_POWER: f32 = 8.0;
+const DEFAULT_BULB_ITERATIONS: u32 = 8;
+// each level is a third the size of the last, so past about five the new holes
+// are smaller than a pixel at the default view
+const DEFAULT_SPONGE_ITERATIONS: u32 = 4;
+// the repetition scene's grid spacing, and how many spheres make up its ring
+const DEFAULT_REPEAT_PERIOD: f32 = 0.6;
+const DEFAULT_RING_COUNT: u32 = 8;
+
+// jittered rays per pixel that `Q` steps through; 1 is the single centred ray
+const AA_SAMPLES: [u32; 4] = [1, 2, 4, 8];
+const MAX_AA_SAMPLES: u32 = 16;
+
+// vertical field of view in degrees; about 58.1 matches the original focal
+// length of 1.8 screen half-heights
+const DEFAULT_FOV: f32 = 58.109_21;
+const FOV_STEP: f32 = 5.0;
+const FOV_RANGE: std::ops::RangeInclusive<f32> = 10.0..=120.0;
+
+// the original hardcoded nugget palette
+const DEFAULT_BASE_COLOR: [f32; 3] = [0.85, 0.55, 0.2];
+const DEFAULT_RIM_COLOR: [f32; 3] = [1.0, 0.8, 0.5];
+const DEFAULT_BG_TOP: [f32; 3] = [0.1, 0.0, 0.15];
+const DEFAULT_BG_BOTTOM: [f32; 3] = [0.02, 0.0, 0.05];
+// halfway between the sky colors, so distant surfaces sink into the backdrop
+const DEFAULT_FOG_COLOR: [f32; 3] = [0.06, 0.0, 0.1];
+// light coming back out of the nugget, redder than its surface
+const DEFAULT_SUBSURFACE_COLOR: [f32; 3] = [0.9, 0.4, 0.12];
+
+struct OrbitCamera {
+    yaw: f32,
+    pitch: f32,
+    distance: f32,
+    target: Vec3,
+}
+
+impl Default for OrbitCamera {
+    fn default() -> Self {
+        // matches the original fixed camera at (0, 0.2, 3)
+        Self {
+            yaw: 0.0,
+            pitch: 0.0,
+            distance: 3.0,
+            target: Vec3::new(0.0, 0.2, 0.0),
+        }
+    }
+}
+
+// everything the shader reads besides time and resolution, shared by the window
+// and headless paths so both start from the same look
+struct SceneParams {
+    scene: u32,
+    shadow_k: f32,
+    ao_strength: f32,
+    // the translucency that makes thin parts glow; color in the palette
+    subsurface_strength: f32,
+    subsurface_depth: f32,
+    exposure: f32,
+    gamma: f32,
+    max_steps: u32,
+    mouse: [f32; 2],
+    // degrees; azimuth 0 points the light along +z, towards the default camera,
+    // and positive elevation puts it overhead
+    light_azimuth: f32,
+    light_elevation: f32,
+    camera: OrbitCamera,
+    fov: f32,
+    debug_mode: u32,
+    bulb_power: f32,
+    bulb_iterations: u32,
+    sponge_iterations: u32,
+    repeat_period: f32,
+    ring_count: u32,
+    // rays the shader averages per pixel, at hashed offsets within it
+    aa_samples: u32,
+    // index into `TONEMAPS`
+    tonemap: u32,
+    // extinction per unit of march distance; 0 is no fog
+    fog_density: f32,
+    palette: Palette,
+    // bass, mid and treble levels from `--audio`, otherwise silent
+    audio: [f32; 3],
+}
+
+#[derive(Clone, Copy, PartialEq)]
+struct Palette {
+    base: [f32; 3],
+    rim: [f32; 3],
+    bg_top: [f32; 3],
+    bg_bottom: [f32; 3],
+    fog: [f32; 3],
+    subsurface: [f32; 3],
+}
+
+impl Default for Palette {
+    fn default() -> Self {
+        Self {
+            base: DEFAULT_BASE_COLOR,
+            rim: DEFAULT_RIM_COLOR,
+            bg_top: DEFAULT_BG_TOP,
+            bg_bottom: DEFAULT_BG_BOTTOM,
+            fog: DEFAULT_FOG_COLOR,
+            subsurface: DEFAULT_SUBSURFACE_COLOR,
+        }
+    }
+}
+
+fn rgba([r, g, b]: [f32; 3]) -> [f32; 4] {
+    [r, g, b, 1.0]
+}
+
+impl Default for SceneParams {
+    fn default() -> Self {
+        let (light_azimuth, light_elevation) = light_towards_cursor(DEFAULT_MOUSE);
+        Self {
+            scene: 0,
+            shadow_k: DEFAULT_SHADOW_K,
+            ao_strength: DEFAULT_AO_STRENGTH,
+            subsurface_strength: DEFAULT_SUBSURFACE_STRENGTH,
+            subsurface_depth: DEFAULT_SUBSURFACE_DEPTH,
+            exposure: 1.0,
+            gamma: DEFAULT_GAMMA,
+            max_steps: DEFAULT_MAX_STEPS,
+            mouse: DEFAULT_MOUSE,
+            light_azimuth,
+            light_elevation,
+            camera: OrbitCamera::default(),
+            fov: DEFAULT_FOV,
+            debug_mode: 0,
+            bulb_power: DEFAULT_BULB_POWER,
+            bulb_iterations: DEFAULT_BULB_ITERATIONS,
+            sponge_iterations: DEFAULT_SPONGE_ITERATIONS,
+            repeat_period: DEFAULT_REPEAT_PERIOD,
+            ring_count: DEFAULT_RING_COUNT,
+            aa_samples: 1,
+            tonemap: 0,
+            fog_density: 0.0,
+            palette: Palette::default(),
+            audio: [0.0; 3],
+        }
+    }
+}
+
+// the cursor steers the light across the front hemisphere: the corners of the
+// window point it at (±1, ±1, 0.3)
+fn light_towards_cursor(mouse: [f32; 2]) -> (f32, f32) {
+    let [x, y] = mouse.map(|m| m * 2.0 - 1.0);
+    let z = 0.3;
+    (
+        x.atan2(z).to_degrees(),
+        y.atan2((x * x + z * z).sqrt()).to_degrees(),
+    )
+}
+
+impl SceneParams {
+    fn light_dir(&self) -> Vec3 {
+        let (sa, ca) = self.light_azimuth.to_radians().sin_cos();
+        let (se, ce) = self.light_elevation.to_radians().sin_cos();
+        Vec3::new(sa * ce, se, ca * ce)
+    }
+
+    fn globals(&self, time: f32, resolution: [f32; 2]) -> Globals {
+        let eye = self.camera.eye();
+        Globals {
+            camera_pos: eye,
+            time,
+            camera_target: self.camera.target,
+            _pad: 0.0,
+            resolution,
+            mouse: self.mouse,
+            scene: self.scene,
+            shadow_k: self.shadow_k,
+            ao_strength: self.ao_strength,
+            exposure: self.exposure,
+            gamma: self.gamma,
+            max_steps: self.max_steps,
+            _pad1: [0; 2],
+            base_color: rgba(self.palette.base),
+            rim_color: rgba(self.palette.rim),
+            bg_top: rgba(self.palette.bg_top),
+            bg_bottom: rgba(self.palette.bg_bottom),
+            camera: Mat4::look_at(eye, self.camera.target, Vec3::new(0.0, 1.0, 0.0)),
+            audio: self.audio,
+            focal_length: 1.0 / (self.fov.to_radians() * 0.5).tan(),
+            debug_mode: self.debug_mode,
+            bulb_power: self.bulb_power,
+            bulb_iterations: self.bulb_iterations,
+            fog_density: self.fog_density,
+            fog_color: rgba(self.palette.fog),
+            jitter: [0.0; 2],
+            frame: 0,
+            adaptive_steps: 0,
+            light_dir: self.light_dir(),
+            sponge_iterations: self.sponge_iterations,
+            repeat_period: self.repeat_period,
+            // no copies would be a division by zero in the shader
+            ring_count: self.ring_count.max(1),
+            aa_samples: self.aa_samples.clamp(1, MAX_AA_SAMPLES),
+            tonemap: self.tonemap,
+            subsurface_color: rgba(self.palette.subsurface),
+            subsurface_strength: self.subsurface_strength,
+            // a zero depth would divide by zero probing the thickness
+            subsurface_depth: self.subsurface_depth.max(*SUBSURFACE_DEPTH_RANGE.start()),
+            _pad2: [0; 2],
+        }
+    }
 }
 
-const SHADER: &str = r#"
+impl OrbitCamera {
+    fn eye(&self) -> Vec3 {
+        let (sy, cy) = self.yaw.sin_cos();
+        let (sp, cp) = self.pitch.sin_cos();
+        self.target + Vec3::new(sy * cp, sp, cy * cp) * self.distance
+    }
+
+    fn drag(&mut self, dx: f32, dy: f32) {
+        self.yaw -= dx * ORBIT_SENSITIVITY;
+        self.pitch = (self.pitch + dy * ORBIT_SENSITIVITY).clamp(-MAX_PITCH, MAX_PITCH);
+    }
+
+    // positive `notches` (scrolling up) move the eye closer
+    fn zoom(&mut self, notches: f32) {
+        self.distance = (self.distance * ZOOM_STEP.powf(notches))
+            .clamp(*DISTANCE_RANGE.start(), *DISTANCE_RANGE.end());
+    }
+}
+
+// the SDF primitives in sdf.wgsl come first, so every scene can use them
+const SHADER: &str = concat!(
+    include_str!("sdf.wgsl"),
+    r#"
 struct Globals {
+    camera_pos: vec3<f32>,
     time: f32,
+    camera_target: vec3<f32>,
+    resolution: vec2<f32>,
+    // cursor position in 0..1, origin at the bottom-left like `uv`
+    mouse: vec2<f32>,
+    scene: u32,
+    shadow_k: f32,
+    ao_strength: f32,
+    exposure: f32,
+    gamma: f32,
+    // raymarch iteration cap
+    max_steps: u32,
+    // palette; alpha is unused
+    base_color: vec4<f32>,
+    rim_color: vec4<f32>,
+    bg_top: vec4<f32>,
+    bg_bottom: vec4<f32>,
+    // camera-to-world: columns are right, up, forward and the eye position
+    camera: mat4x4<f32>,
+    // bass, mid and treble in 0..1 with `--audio`; all zero without it
+    audio: vec3<f32>,
+    // distance to the image plane in screen half-heights, from the vertical FOV
+    focal_length: f32,
+    // 0 lit, 1 normals, 2 march step heatmap, 3 depth, 4 march outcome
+    debug_mode: u32,
+    // exponent and iteration count of the mandelbulb scene
+    bulb_power: f32,
+    bulb_iterations: u32,
+    // exponential distance fog on hits; density 0 turns it off
+    fog_density: f32,
+    fog_color: vec4<f32>,
+    // sub-pixel offset of the camera rays, in pixels, and how many frames the
+    // running average holds with this one; both 0 unless accumulating (`A`)
+    jitter: vec2<f32>,
+    frame: u32,
+    // 1 caps each pixel's march from `step_grid` (`G`), 0 marches to `max_steps`
+    adaptive_steps: u32,
+    // unit vector towards the light, which the cursor and `I`/`J`/`K`/`L` steer
+    light_dir: vec3<f32>,
+    // levels of holes in the menger sponge scene
+    sponge_iterations: u32,
+    // spacing of the repetition scene's grid of boxes, and the number of
+    // spheres in its ring
+    repeat_period: f32,
+    ring_count: u32,
+    // rays averaged per pixel (`Q`); 1 is the single ray through `jitter`
+    aa_samples: u32,
+    // 0 clips, 1 Reinhard, 2 ACES (`T`)
+    tonemap: u32,
+    // translucency: the color and brightness of light scattered back out of
+    // the surface, and how far in `thickness` looks for the far side
+    subsurface_color: vec4<f32>,
+    subsurface_strength: f32,
+    subsurface_depth: f32,
 };
 
 @group(0) @binding(0)
 var<uniform> globals: Globals;
 
+// the steps `fs_steps` took through the middle of each cell of a coarse grid
+// over the screen, as a fraction of `max_steps`
+@group(0) @binding(1)
+var step_grid: texture_2d<f32>;
+
+// how far apart neighbouring pixels' rays are, per unit along them; set from
+// screen-space derivatives at the start of `fs_main`, where they're allowed
+// (derivatives need uniform control flow), for the SDFs to filter detail by
+var<private> pixel_footprint: f32 = 0.0;
+
 struct VSOut {
     @builtin(position) pos: vec4<f32>,
     @location(0) uv: vec2<f32>,
@@ -49,27 +513,150 @@ fn rot_y(a: f32) -> mat3x3<f32> {
 }
 
 fn nugget_sdf(p: vec3<f32>, t: f32) -> f32 {
-    // rotate the nugget over time
+    // rotate the nugget over time, and swell it on the bass
     let r = rot_y(t * 0.7);
-    var q = r * p;
+    let pulse = 1.0 + 0.2 * globals.audio.x;
+    var q = r * p / pulse;
 
     // base blobby sphere
-    var d = length(q) - 0.8;
+    var d = sd_sphere(q, 0.8);
 
     // a few lumpy bits
-    d = min(d, length(q - vec3<f32>(0.35, 0.15, 0.1)) - 0.35);
-    d = min(d, length(q - vec3<f32>(-0.3, -0.2, 0.2)) - 0.3);
-    d = min(d, length(q - vec3<f32>(0.1, 0.25, -0.25)) - 0.28);
+    d = op_union(d, sd_sphere(q - vec3<f32>(0.35, 0.15, 0.1), 0.35));
+    d = op_union(d, sd_sphere(q - vec3<f32>(-0.3, -0.2, 0.2), 0.3));
+    d = op_union(d, sd_sphere(q - vec3<f32>(0.1, 0.25, -0.25), 0.28));
 
-    // small sinusoidal roughness to feel crunchy
-    let rough = 0.08 * (sin(q.x * 8.0) * sin(q.y * 9.0) * sin(q.z * 7.0));
+    // small sinusoidal roughness to feel crunchy; it fades out when a pixel
+    // covers a quarter to a half of its shortest wave, past which it can only
+    // alias into shimmer
+    let footprint = pixel_footprint * length(p - globals.camera_pos) / pulse;
+    let waves = 9.0 * footprint / 6.2831853;
+    let fade = 1.0 - smoothstep(0.25, 0.5, waves);
+    let rough = 0.08 * (sin(q.x * 8.0) * sin(q.y * 9.0) * sin(q.z * 7.0)) * fade;
     d = d + rough;
 
+    return d * pulse;
+}
+
+fn tori_sdf(p: vec3<f32>, t: f32) -> f32 {
+    // a flat ring with two upright rings linked through it
+    let q = rot_y(t * 0.5) * p;
+    let radii = vec2<f32>(0.6, 0.15);
+    var d = sd_torus(q, radii);
+    let left = q + vec3<f32>(0.6, 0.0, 0.0);
+    let right = q - vec3<f32>(0.6, 0.0, 0.0);
+    d = op_union(d, sd_torus(left.xzy, radii));
+    d = op_union(d, sd_torus(right.xzy, radii));
     return d;
 }
 
+fn twisted_box_sdf(p: vec3<f32>, t: f32) -> f32 {
+    // twist around y by an amount that swings back and forth
+    let a = p.y * 1.5 * sin(t * 0.5);
+    let c = cos(a);
+    let s = sin(a);
+    let q = vec3<f32>(c * p.x - s * p.z, p.y, s * p.x + c * p.z);
+    // the twist stretches distances, so under-step to keep the march stable
+    return sd_box(q, vec3<f32>(0.4, 0.9, 0.4)) * 0.7;
+}
+
+// distance estimate from the running derivative of the power-n bulb formula,
+// z -> z^n + c in spherical coordinates
+fn mandelbulb_sdf(p: vec3<f32>, t: f32) -> f32 {
+    let c = rot_y(t * 0.2) * p;
+    let power = globals.bulb_power;
+    var z = c;
+    var dr = 1.0;
+    var r = 0.0;
+    for (var i: u32 = 0u; i < globals.bulb_iterations; i = i + 1u) {
+        r = length(z);
+        if r > 2.0 {
+            break;
+        }
+        let theta = acos(clamp(z.z / r, -1.0, 1.0)) * power;
+        let phi = atan2(z.y, z.x) * power;
+        dr = pow(r, power - 1.0) * power * dr + 1.0;
+        let zr = pow(r, power);
+        z = zr * vec3<f32>(sin(theta) * cos(phi), sin(theta) * sin(phi), cos(theta)) + c;
+    }
+    r = length(z);
+    return 0.5 * log(r) * r / dr;
+}
+
+// a unit cube with a cross-shaped hole punched through the middle of each face,
+// then through each of the 20 smaller cubes left, and so on: every level folds
+// space into repeated cells a third the size, and carves the cross out of the
+// box distance there
+fn menger_sdf(p: vec3<f32>, t: f32) -> f32 {
+    // two thirds of a unit across, to frame like the other scenes
+    let size = 0.65;
+    let q = rot_y(t * 0.3) * p / size;
+    var d = sd_box(q, vec3<f32>(1.0));
+    var scale = 1.0;
+    for (var i: u32 = 0u; i < globals.sponge_iterations; i = i + 1u) {
+        // each cell is -1..1 on every axis; floor-based, since `%` keeps the
+        // sign of negative coordinates
+        let x = q * scale;
+        let cell = x - 2.0 * floor(x * 0.5) - vec3<f32>(1.0);
+        scale = scale * 3.0;
+        // distance to the middle third of the cell along each axis, so the
+        // cross is where two of them are inside it at once
+        let r = abs(vec3<f32>(1.0) - 3.0 * abs(cell));
+        let cross = min(max(r.x, r.y), min(max(r.y, r.z), max(r.z, r.x)));
+        d = op_subtract(d, -(cross - 1.0) / scale);
+    }
+    return d * size;
+}
+
+// one box and one sphere, repeated: the box across a floor of cells
+// `repeat_period` apart that drifts towards the camera, the sphere around a
+// spinning ring of `ring_count` hovering over it
+fn repetition_sdf(p: vec3<f32>, t: f32) -> f32 {
+    let period = globals.repeat_period;
+    let local = op_repeat_xz(p - vec3<f32>(0.0, -0.8, t * 0.3), period);
+    // the nearest box may be in a neighbouring cell, on whichever sides of
+    // its own box the point is, so those three count too; with only this
+    // cell's, a step could cross into the next cell and land inside its box
+    let side = sign(local.xz) * period;
+    // small enough for the boxes' corners to stay inside the smallest cell
+    // as they turn
+    let turn = rot_y(t * 0.5);
+    var box = 1e9;
+    for (var i = 0; i < 4; i = i + 1) {
+        let offset = vec2<f32>(f32(i & 1), f32(i >> 1u)) * side;
+        let tile = turn * (local - vec3<f32>(offset.x, 0.0, offset.y));
+        box = min(box, sd_box(tile, vec3<f32>(0.13, 0.1, 0.13)) - 0.03);
+    }
+
+    let count = f32(globals.ring_count);
+    let ring = op_repeat_polar(rot_y(t * 0.4) * (p - vec3<f32>(0.0, 0.3, 0.0)), count);
+    // shrinks as the ring fills up, so neighbours never overlap
+    let radius = min(0.2, 0.9 * 0.8 * sin(3.1415927 / count));
+    let sphere = sd_sphere(ring - vec3<f32>(0.8, 0.0, 0.0), radius);
+    return op_union(box, sphere);
+}
+
 fn map_scene(p: vec3<f32>, t: f32) -> f32 {
-    return nugget_sdf(p, t);
+    switch globals.scene {
+        case 1u: {
+            return tori_sdf(p, t);
+        }
+        case 2u: {
+            return twisted_box_sdf(p, t);
+        }
+        case 3u: {
+            return mandelbulb_sdf(p, t);
+        }
+        case 4u: {
+            return menger_sdf(p, t);
+        }
+        case 5u: {
+            return repetition_sdf(p, t);
+        }
+        default: {
+            return nugget_sdf(p, t);
+        }
+    }
 }
 
 fn estimate_normal(p: vec3<f32>, t: f32) -> vec3<f32> {
@@ -81,90 +668,631 @@ fn estimate_normal(p: vec3<f32>, t: f32) -> vec3<f32> {
     return normalize(vec3<f32>(nx, ny, nz));
 }
 
-@fragment
-fn fs_main(in: VSOut) -> @location(0) vec4<f32> {
-    // normalized screen coordinates
-    let uv = in.uv * 2.0 - vec2<f32>(1.0, 1.0);
-    let aspect = 800.0 / 600.0;
-    let p = vec2<f32>(uv.x * aspect, uv.y);
+// iq's soft shadow: march toward the light and keep the narrowest miss,
+// scaled by distance travelled, as the penumbra factor
+fn soft_shadow(ro: vec3<f32>, rd: vec3<f32>, t: f32, k: f32) -> f32 {
+    var res = 1.0;
+    var s = 0.02;
+    for (var i: i32 = 0; i < 48; i = i + 1) {
+        let h = map_scene(ro + rd * s, t);
+        if h < 0.001 {
+            return 0.0;
+        }
+        res = min(res, k * h / s);
+        s = s + clamp(h, 0.01, 0.25);
+        if s > 6.0 {
+            break;
+        }
+    }
+    return clamp(res, 0.0, 1.0);
+}
 
-    let t = globals.time;
+// sample the field at a few steps along the normal: the closer the surface is
+// to those points than their distance from p, the more occluded p is
+fn ambient_occlusion(p: vec3<f32>, n: vec3<f32>, t: f32) -> f32 {
+    var occ = 0.0;
+    var weight = 1.0;
+    for (var i: i32 = 0; i < 5; i = i + 1) {
+        let h = 0.01 + 0.03 * f32(i);
+        let d = map_scene(p + n * h, t);
+        occ = occ + (h - d) * weight;
+        weight = weight * 0.95;
+    }
+    return clamp(1.0 - 3.0 * globals.ao_strength * occ, 0.0, 1.0);
+}
+
+// how much of the way in from `p`, against the normal and out to
+// `subsurface_depth`, is still that deep inside: 1 in a body thicker than the
+// depth, where the distance to the surface grows as fast as the probe goes in,
+// falling towards 0 where the far side is close behind, as in thin lumps,
+// ridges and crunch
+fn thickness(p: vec3<f32>, n: vec3<f32>, t: f32) -> f32 {
+    var inside = 0.0;
+    for (var i: i32 = 1; i <= 4; i = i + 1) {
+        let h = globals.subsurface_depth * f32(i) / 4.0;
+        inside = inside + clamp(-map_scene(p - n * h, t) / h, 0.0, 1.0);
+    }
+    return inside / 4.0;
+}
+
+// blue through green and yellow to red for 0..1
+fn heatmap(x: f32) -> vec3<f32> {
+    let v = clamp(x, 0.0, 1.0);
+    return clamp(
+        vec3<f32>(4.0 * v - 2.0, 2.0 - abs(4.0 * v - 2.0), 2.0 - 4.0 * v),
+        vec3<f32>(0.0),
+        vec3<f32>(1.0),
+    );
+}
 
-    // camera setup
-    let ro = vec3<f32>(0.0, 0.2, 3.0);
-    let rd = normalize(vec3<f32>(p.x, p.y, -1.8));
+// a point on the image plane, in screen half-heights from its middle, for a
+// position on the screen in 0..1
+fn screen_point(screen_uv: vec2<f32>) -> vec2<f32> {
+    let uv = screen_uv * 2.0 - vec2<f32>(1.0, 1.0);
+    let aspect = globals.resolution.x / globals.resolution.y;
+    return vec2<f32>(uv.x * aspect, uv.y);
+}
 
-    // raymarch
-    var dist = 0.0;
-    var hit = false;
-    var pos = ro;
+// look-at camera orbiting the target
+fn camera_ray(p: vec2<f32>) -> vec3<f32> {
+    return normalize((globals.camera * vec4<f32>(p.x, p.y, globals.focal_length, 0.0)).xyz);
+}
 
-    for (var i: i32 = 0; i < 96; i = i + 1) {
-        pos = ro + rd * dist;
-        let d = map_scene(pos, t);
+struct March {
+    dist: f32,
+    hit: bool,
+    escaped: bool,
+    pos: vec3<f32>,
+    steps: u32,
+    // nearest the ray came to the surface, for outlining near misses, and
+    // where along the ray that was
+    closest: f32,
+    closest_dist: f32,
+};
+
+fn march(ro: vec3<f32>, rd: vec3<f32>, t: f32, max_steps: u32) -> March {
+    var m = March(0.0, false, false, ro, 0u, 1e9, 0.0);
+    for (var i: u32 = 0u; i < max_steps; i = i + 1u) {
+        m.steps = i + 1u;
+        m.pos = ro + rd * m.dist;
+        let d = map_scene(m.pos, t);
+        if d < m.closest {
+            m.closest = d;
+            m.closest_dist = m.dist;
+        }
         if d < 0.002 {
-            hit = true;
+            m.hit = true;
             break;
         }
-        dist = dist + d;
-        if dist > 8.0 {
+        m.dist = m.dist + d;
+        if m.dist > 8.0 {
+            m.escaped = true;
             break;
         }
     }
+    return m;
+}
 
-    var col = vec3<f32>(0.02, 0.0, 0.06);
+// the most steps any of the 3x3 cells around this pixel's took, with half as
+// many again and a few more to spare, since a cell's one ray can miss detail
+// its neighbours' pixels run into
+fn step_budget(screen_uv: vec2<f32>) -> u32 {
+    let size = vec2<i32>(textureDimensions(step_grid));
+    // texel rows run down the screen, `uv` up it
+    let cell = vec2<i32>(vec2<f32>(screen_uv.x, 1.0 - screen_uv.y) * vec2<f32>(size));
+    var need = 0.0;
+    for (var dy = -1; dy <= 1; dy = dy + 1) {
+        for (var dx = -1; dx <= 1; dx = dx + 1) {
+            let texel = clamp(cell + vec2<i32>(dx, dy), vec2<i32>(0), size - 1);
+            need = max(need, textureLoad(step_grid, texel, 0).r);
+        }
+    }
+    return min(globals.max_steps, u32(need * f32(globals.max_steps) * 1.5) + 8u);
+}
 
-    if hit {
-        let n = estimate_normal(pos, t);
+// the adaptive steps prepass: one unjittered ray per cell of `step_grid`
+@fragment
+fn fs_steps(in: VSOut) -> @location(0) vec4<f32> {
+    let rd = camera_ray(screen_point(in.uv));
+    let m = march(globals.camera_pos, rd, globals.time, globals.max_steps);
+    return vec4<f32>(f32(m.steps) / f32(globals.max_steps), 0.0, 0.0, 1.0);
+}
 
-        let light_dir = normalize(vec3<f32>(-0.4, 0.7, 0.3));
-        let diff = max(dot(n, light_dir), 0.0);
+// a point in the pixel for sample `i` of `count`, as an offset from its centre
+// in pixels: the samples take a column of the pixel each, at hashed heights and
+// positions within their columns, which differ from pixel to pixel so what
+// aliasing is left is noise rather than a pattern (pcg3d)
+fn sample_offset(pixel: vec2<u32>, i: u32, count: u32) -> vec2<f32> {
+    var v = vec3<u32>(pixel, i) * 1664525u + 1013904223u;
+    v.x += v.y * v.z;
+    v.y += v.z * v.x;
+    v.z += v.x * v.y;
+    v = v ^ (v >> vec3<u32>(16u));
+    v.x += v.y * v.z;
+    v.y += v.z * v.x;
+    let h = vec2<f32>(v.xy) / 4294967296.0;
+    return vec2<f32>((f32(i) + h.x) / f32(count), h.y) - 0.5;
+}
 
-        // simple fake subsurface / bounce from below
-        let subsurf = max(dot(n, vec3<f32>(0.0, -1.0, 0.0)), 0.0);
+@fragment
+fn fs_main(in: VSOut) -> @location(0) vec4<f32> {
+    let screen_uv = in.uv + globals.jitter / globals.resolution;
+    // derivatives are only allowed out here in uniform control flow, so the
+    // footprint is the centre ray's for every sample
+    pixel_footprint = length(fwidth(camera_ray(screen_point(screen_uv))));
+    if globals.aa_samples <= 1u {
+        return sample_pixel(screen_uv, in.uv);
+    }
+    var sum = vec3<f32>(0.0);
+    for (var i = 0u; i < globals.aa_samples; i = i + 1u) {
+        let offset = sample_offset(vec2<u32>(in.pos.xy), i, globals.aa_samples);
+        sum = sum + sample_pixel(screen_uv + offset / globals.resolution, in.uv).rgb;
+    }
+    return vec4<f32>(sum / f32(globals.aa_samples), 1.0);
+}
 
-        // crunchy nugget base color
-        let base = vec3<f32>(0.85, 0.55, 0.2);
+// the finished color of one ray through `screen_uv`; `pixel_uv` is the middle
+// of the pixel, which the adaptive step budget is looked up at
+fn sample_pixel(screen_uv: vec2<f32>, pixel_uv: vec2<f32>) -> vec4<f32> {
+    let p = screen_point(screen_uv);
+    let t = globals.time;
+    let ro = globals.camera_pos;
+    let rd = camera_ray(p);
 
-        let nugget = base * (0.25 + 0.85 * diff) + vec3<f32>(0.3, 0.15, 0.05) * subsurf;
+    var max_steps = globals.max_steps;
+    if globals.adaptive_steps != 0u {
+        max_steps = step_budget(pixel_uv);
+    }
+    let m = march(ro, rd, t, max_steps);
 
-        // slight rim light
-        let view_dir = normalize(ro - pos);
-        let rim = pow(1.0 - max(dot(n, view_dir), 0.0), 3.0);
+    switch globals.debug_mode {
+        case 1u: {
+            if !m.hit {
+                return vec4<f32>(0.0, 0.0, 0.0, 1.0);
+            }
+            return vec4<f32>(estimate_normal(m.pos, t) * 0.5 + 0.5, 1.0);
+        }
+        case 2u: {
+            return vec4<f32>(heatmap(f32(m.steps) / f32(globals.max_steps)), 1.0);
+        }
+        case 3u: {
+            let depth = select(0.0, 1.0 - m.dist / 8.0, m.hit);
+            return vec4<f32>(vec3<f32>(depth), 1.0);
+        }
+        case 4u: {
+            // how each march ended: hits green and escapes blue, both fading
+            // darker the more steps they took; rays that ran out of steps
+            // without either are magenta, and escapes that grazed the surface
+            // are yellow, which outlines every silhouette
+            let cost = f32(m.steps) / f32(globals.max_steps);
+            if m.hit {
+                return vec4<f32>(vec3<f32>(0.1, 1.0, 0.2) * (1.0 - 0.8 * cost), 1.0);
+            }
+            if !m.escaped {
+                return vec4<f32>(1.0, 0.0, 1.0, 1.0);
+            }
+            if m.closest < 0.02 {
+                return vec4<f32>(1.0, 0.85, 0.0, 1.0);
+            }
+            return vec4<f32>(vec3<f32>(0.1, 0.3, 1.0) * (1.0 - 0.8 * cost), 1.0);
+        }
+        default: {}
+    }
+
+    // background gradient, flashing toward the rim color on the treble
+    let y = p.y * 0.5 + 0.5;
+    let background = mix(globals.bg_bottom.rgb, globals.bg_top.rgb, y)
+        + globals.rim_color.rgb * (0.15 * globals.audio.z);
 
-        col = nugget + rim * vec3<f32>(1.0, 0.8, 0.5);
+    var col = background;
+    if m.hit {
+        col = shade(m.pos, ro, m.dist, t);
     } else {
-        // background gradient
-        let y = p.y * 0.5 + 0.5;
-        col = mix(
-            vec3<f32>(0.02, 0.0, 0.05),
-            vec3<f32>(0.1, 0.0, 0.15),
-            y
-        );
+        // a miss that passed within a pixel of the surface is partly covered
+        // by it, so it blends in the surface as seen from the nearest point,
+        // which smooths the silhouette over about a pixel
+        let coverage = 1.0 - smoothstep(0.0, pixel_footprint * m.closest_dist, m.closest);
+        if coverage > 0.0 {
+            let edge = ro + rd * m.closest_dist;
+            col = mix(background, shade(edge, ro, m.closest_dist, t), coverage);
+        }
     }
 
-    // clamp and slight gamma
-    col = min(col, vec3<f32>(1.0, 1.0, 1.0));
-    col = pow(col, vec3<f32>(0.8, 0.8, 0.8));
+    // expose, tone map and gamma
+    col = tonemap(col * globals.exposure);
+    col = pow(col, vec3<f32>(1.0 / globals.gamma));
 
     return vec4<f32>(col, 1.0);
 }
-"#;
+
+// exposed linear color into 0..1
+fn tonemap(col: vec3<f32>) -> vec3<f32> {
+    switch globals.tonemap {
+        case 1u: {
+            return col / (col + 1.0);
+        }
+        case 2u: {
+            // Narkowicz's fit of the ACES filmic curve
+            let mapped = (col * (2.51 * col + 0.03)) / (col * (2.43 * col + 0.59) + 0.14);
+            return clamp(mapped, vec3<f32>(0.0), vec3<f32>(1.0));
+        }
+        default: {
+            return min(col, vec3<f32>(1.0));
+        }
+    }
+}
+
+// the lit surface at `pos`, `dist` along the ray from the eye at `ro`
+fn shade(pos: vec3<f32>, ro: vec3<f32>, dist: f32, t: f32) -> vec3<f32> {
+    let n = estimate_normal(pos, t);
+
+    let light_dir = globals.light_dir;
+    let shadow = soft_shadow(pos + n * 0.01, light_dir, t, globals.shadow_k);
+    let diff = max(dot(n, light_dir), 0.0) * shadow;
+
+    // crunchy nugget base color
+    let base = globals.base_color.rgb;
+
+    // occlusion only darkens the indirect light; shadows handle the direct term
+    let ao = ambient_occlusion(pos, n, t);
+
+    // translucency: light scattered through the surface comes back out, the
+    // more the thinner it is there. Most arrives from the light behind the
+    // surface, which needn't reach it directly, the rest from all round,
+    // which occlusion darkens like the ambient term
+    let through = max(dot(-n, light_dir), 0.0) + 0.25 * ao;
+    let subsurface = globals.subsurface_color.rgb * globals.subsurface_strength
+        * (1.0 - thickness(pos, n, t)) * through;
+    let nugget = base * (0.25 * ao + 0.85 * diff) + subsurface;
+
+    // slight rim light
+    let view_dir = normalize(ro - pos);
+    let rim = pow(1.0 - max(dot(n, view_dir), 0.0), 3.0);
+
+    let col = nugget + rim * globals.rim_color.rgb * (1.0 + 2.0 * globals.audio.y);
+
+    // the further the hit, the more of it is hidden behind fog
+    let fog = 1.0 - exp(-globals.fog_density * dist);
+    return mix(col, globals.fog_color.rgb, fog);
+}
+"#
+);
+
+// averages frame times over roughly one-second windows for the title bar readout
+struct FrameCounter {
+    window_start: Instant,
+    frames: u32,
+}
+
+impl FrameCounter {
+    const REPORT_INTERVAL: Duration = Duration::from_secs(1);
+
+    fn new() -> Self {
+        Self {
+            window_start: Instant::now(),
+            frames: 0,
+        }
+    }
+
+    // returns (fps, ms per frame) once per report interval
+    fn tick(&mut self) -> Option<(f32, f32)> {
+        self.frames += 1;
+        let elapsed = self.window_start.elapsed();
+        if elapsed < Self::REPORT_INTERVAL {
+            return None;
+        }
+        let secs = elapsed.as_secs_f32();
+        let stats = (
+            self.frames as f32 / secs,
+            secs * 1000.0 / self.frames as f32,
+        );
+        self.window_start = Instant::now();
+        self.frames = 0;
+        Some(stats)
+    }
+}
+
+async fn request_device(adapter: &wgpu::Adapter) -> (wgpu::Device, wgpu::Queue) {
+    adapter
+        .request_device(&wgpu::DeviceDescriptor {
+            label: None,
+            required_features: wgpu::Features::empty(),
+            required_limits: wgpu::Limits::default(),
+            experimental_features: wgpu::ExperimentalFeatures::default(),
+            memory_hints: wgpu::MemoryHints::default(),
+            trace: wgpu::Trace::default(),
+        })
+        .await
+        .expect("Failed to create device")
+}
+
+// the Globals uniform buffer and the bind group the shader reads it through
+struct GlobalsBinding {
+    buffer: wgpu::Buffer,
+    layout: wgpu::BindGroupLayout,
+    bind_group: wgpu::BindGroup,
+}
+
+// `step_grid` is the adaptive steps grid, which the bind group holds alongside
+// the uniforms
+fn create_globals_binding(device: &wgpu::Device, step_grid: &wgpu::TextureView) -> GlobalsBinding {
+    // filled in before every draw
+    let buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some("Globals Buffer"),
+        contents: bytemuck::bytes_of(&<Globals as bytemuck::Zeroable>::zeroed()),
+        usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+    });
+
+    let layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        label: Some("Globals BGL"),
+        entries: &[
+            wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            },
+            wgpu::BindGroupLayoutEntry {
+                binding: 1,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Texture {
+                    sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                    view_dimension: wgpu::TextureViewDimension::D2,
+                    multisampled: false,
+                },
+                count: None,
+            },
+        ],
+    });
+
+    let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+        label: Some("Globals BG"),
+        layout: &layout,
+        entries: &[
+            wgpu::BindGroupEntry {
+                binding: 0,
+                resource: buffer.as_entire_binding(),
+            },
+            wgpu::BindGroupEntry {
+                binding: 1,
+                resource: wgpu::BindingResource::TextureView(step_grid),
+            },
+        ],
+    });
+
+    GlobalsBinding {
+        buffer,
+        layout,
+        bind_group,
+    }
+}
+
+// a shader from disk, reread on `R`; the browser build only has the embedded one
+#[derive(Clone)]
+#[cfg_attr(target_arch = "wasm32", allow(dead_code))]
+enum ShaderFile {
+    // `--shader`: the whole WGSL
+    Whole(PathBuf),
+    // `--sdf`: just `map_scene`, in the embedded shader
+    Scene(PathBuf),
+}
+
+// the embedded shader unless `--shader` or `--sdf` points at a file
+fn load_shader_source(file: Option<&ShaderFile>) -> Result<String, String> {
+    let read = |path: &Path| {
+        std::fs::read_to_string(path).map_err(|err| format!("{}: {err}", path.display()))
+    };
+    match file {
+        Some(ShaderFile::Whole(path)) => read(path),
+        Some(ShaderFile::Scene(path)) => snippet::splice(SHADER, &read(path)?)
+            .map_err(|err| format!("{}: {err}", path.display())),
+        None => Ok(SHADER.to_string()),
+    }
+}
+
+// compiles the shader and builds the pipeline inside an error scope, so a broken
+// WGSL comes back as a message instead of hitting wgpu's panicking error handler
+async fn build_pipeline(
+    device: &wgpu::Device,
+    layout: &wgpu::PipelineLayout,
+    source: &str,
+    format: wgpu::TextureFormat,
+    sample_count: u32,
+) -> Result<(wgpu::ShaderModule, wgpu::RenderPipeline), String> {
+    device.push_error_scope(wgpu::ErrorFilter::Validation);
+    let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+        label: Some("Neon Shader"),
+        source: wgpu::ShaderSource::Wgsl(source.into()),
+    });
+    let pipeline = create_render_pipeline(device, layout, &shader, format, sample_count);
+    match device.pop_error_scope().await {
+        Some(err) => Err(err.to_string()),
+        None => Ok((shader, pipeline)),
+    }
+}
+
+async fn load_pipeline(
+    device: &wgpu::Device,
+    layout: &wgpu::PipelineLayout,
+    file: Option<&ShaderFile>,
+    format: wgpu::TextureFormat,
+    sample_count: u32,
+) -> Result<(String, wgpu::ShaderModule, wgpu::RenderPipeline), String> {
+    let source = load_shader_source(file)?;
+    let (shader, pipeline) = build_pipeline(device, layout, &source, format, sample_count).await?;
+    Ok((source, shader, pipeline))
+}
+
+fn create_pipeline_layout(
+    device: &wgpu::Device,
+    globals_layout: &wgpu::BindGroupLayout,
+) -> wgpu::PipelineLayout {
+    device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        label: Some("Render Pipeline Layout"),
+        bind_group_layouts: &[globals_layout],
+        push_constant_ranges: &[],
+    })
+}
+
+fn create_render_pipeline(
+    device: &wgpu::Device,
+    layout: &wgpu::PipelineLayout,
+    shader: &wgpu::ShaderModule,
+    format: wgpu::TextureFormat,
+    sample_count: u32,
+) -> wgpu::RenderPipeline {
+    create_fragment_pipeline(device, layout, shader, "fs_main", format, sample_count)
+}
+
+// the fullscreen triangle with another of the shader's fragment entry points
+fn create_fragment_pipeline(
+    device: &wgpu::Device,
+    layout: &wgpu::PipelineLayout,
+    shader: &wgpu::ShaderModule,
+    fragment_entry: &str,
+    format: wgpu::TextureFormat,
+    sample_count: u32,
+) -> wgpu::RenderPipeline {
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some("Fullscreen Triangle Pipeline"),
+        layout: Some(layout),
+        vertex: wgpu::VertexState {
+            module: shader,
+            entry_point: Some("vs_main"),
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+            buffers: &[],
+        },
+        primitive: wgpu::PrimitiveState {
+            topology: wgpu::PrimitiveTopology::TriangleList,
+            strip_index_format: None,
+            front_face: wgpu::FrontFace::Ccw,
+            cull_mode: Some(wgpu::Face::Back),
+            polygon_mode: wgpu::PolygonMode::Fill,
+            unclipped_depth: false,
+            conservative: false,
+        },
+        depth_stencil: None,
+        multisample: wgpu::MultisampleState {
+            count: sample_count,
+            mask: !0,
+            alpha_to_coverage_enabled: false,
+        },
+        fragment: Some(wgpu::FragmentState {
+            module: shader,
+            entry_point: Some(fragment_entry),
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+            targets: &[Some(wgpu::ColorTargetState {
+                format,
+                blend: Some(wgpu::BlendState::REPLACE),
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+        }),
+        multiview: None,
+        cache: None,
+    })
+}
+
+// multisampled color target that resolves into the swapchain; None without MSAA
+fn create_msaa_view(
+    device: &wgpu::Device,
+    config: &wgpu::SurfaceConfiguration,
+    sample_count: u32,
+) -> Option<wgpu::TextureView> {
+    if sample_count <= 1 {
+        return None;
+    }
+    let texture = device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("MSAA Target"),
+        size: wgpu::Extent3d {
+            width: config.width,
+            height: config.height,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count,
+        dimension: wgpu::TextureDimension::D2,
+        format: config.format,
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+        view_formats: &[],
+    });
+    Some(texture.create_view(&wgpu::TextureViewDescriptor::default()))
+}
 
 struct State<'window> {
+    window: &'window winit::window::Window,
     surface: wgpu::Surface<'window>,
     device: wgpu::Device,
     queue: wgpu::Queue,
     config: wgpu::SurfaceConfiguration,
+    shader: wgpu::ShaderModule,
+    pipeline_layout: wgpu::PipelineLayout,
     render_pipeline: wgpu::RenderPipeline,
-    globals_buffer: wgpu::Buffer,
-    globals_bind_group: wgpu::BindGroup,
-    start_instant: Instant,
+    #[cfg(not(target_arch = "wasm32"))]
+    shader_file: Option<ShaderFile>,
+    // set while the last reload failed; the previous pipeline keeps rendering
+    shader_error: Option<String>,
+    // MSAA levels the adapter supports for the surface format, always including 1
+    sample_counts: Vec<u32>,
+    sample_count: u32,
+    msaa_view: Option<wgpu::TextureView>,
+    // the scene pipeline again at one sample, for the supersampled target
+    supersampled_pipeline: wgpu::RenderPipeline,
+    ssaa: ssaa::Supersampler,
+    // `A` averages the SDF over frames instead
+    accumulator: accumulate::Accumulator,
+    // vsync modes to cycle through; Fifo is always supported and comes first
+    present_modes: Vec<wgpu::PresentMode>,
+    globals: GlobalsBinding,
+    // `G` caps the march per screen region from a prepass
+    adaptive: adaptive::AdaptiveSteps,
+    // `E` hands the exposure over to this
+    auto_exposure: exposure::AutoExposure,
+    particles: particles::Particles,
+    reaction: reaction::ReactionDiffusion,
+    demo: Demo,
+    gui: gui::Gui,
+    // shader time is accumulated so it can be paused and scrubbed
+    time: f32,
+    time_scale: f32,
+    paused: bool,
+    last_frame: Instant,
+    params: SceneParams,
+    dragging: bool,
+    last_cursor: Option<winit::dpi::PhysicalPosition<f64>>,
+    // which of `LIGHT_KEYS` are held down
+    light_keys: [bool; 4],
+    // physical pixels per logical one; the surface is always configured in
+    // physical pixels, so a HiDPI display renders at its full resolution
+    scale_factor: f64,
+    #[cfg(not(target_arch = "wasm32"))]
+    audio: Option<audio::Audio>,
+    // where `F5` saves and `F9` loads the scene state
+    #[cfg(not(target_arch = "wasm32"))]
+    snapshot_path: PathBuf,
+    // what the title bar readout starts with
+    title: String,
 }
 
 impl<'window> State<'window> {
-    async fn new(window: &'window winit::window::Window) -> Self {
-        let size = window.inner_size();
+    async fn new(
+        window: &'window winit::window::Window,
+        settings: WindowSettings,
+        params: SceneParams,
+        shader_file: Option<ShaderFile>,
+        ssaa_scale: u32,
+    ) -> Self {
+        // some platforms report no size until the first `Resized`; the requested
+        // one keeps the first frames, and their aspect, right until then
+        let size = match window.inner_size() {
+            size if size.width > 0 && size.height > 0 => size,
+            _ => {
+                LogicalSize::new(settings.width, settings.height).to_physical(window.scale_factor())
+            }
+        };
 
         let instance = wgpu::Instance::default();
         let surface = instance
@@ -180,17 +1308,7 @@ impl<'window> State<'window> {
             .await
             .expect("No suitable GPU adapters found on the system!");
 
-        let (device, queue) = adapter
-            .request_device(&wgpu::DeviceDescriptor {
-                label: None,
-                required_features: wgpu::Features::empty(),
-                required_limits: wgpu::Limits::default(),
-                experimental_features: wgpu::ExperimentalFeatures::default(),
-                memory_hints: wgpu::MemoryHints::default(),
-                trace: wgpu::Trace::default(),
-            })
-            .await
-            .expect("Failed to create device");
+        let (device, queue) = request_device(&adapter).await;
 
         let surface_caps = surface.get_capabilities(&adapter);
         let surface_format = surface_caps
@@ -212,100 +1330,132 @@ impl<'window> State<'window> {
         };
         surface.configure(&device, &config);
 
-        // Globals uniform buffer
-        let globals = Globals {
-            time: 0.0,
-            _pad: [0.0; 3],
-        };
+        let present_modes: Vec<wgpu::PresentMode> = [
+            wgpu::PresentMode::Fifo,
+            wgpu::PresentMode::Mailbox,
+            wgpu::PresentMode::Immediate,
+        ]
+        .into_iter()
+        .filter(|mode| {
+            *mode == wgpu::PresentMode::Fifo || surface_caps.present_modes.contains(mode)
+        })
+        .collect();
 
-        let globals_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some("Globals Buffer"),
-            contents: bytemuck::bytes_of(&globals),
-            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
-        });
-
-        let globals_bind_group_layout =
-            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-                label: Some("Globals BGL"),
-                entries: &[wgpu::BindGroupLayoutEntry {
-                    binding: 0,
-                    visibility: wgpu::ShaderStages::FRAGMENT,
-                    ty: wgpu::BindingType::Buffer {
-                        ty: wgpu::BufferBindingType::Uniform,
-                        has_dynamic_offset: false,
-                        min_binding_size: None,
-                    },
-                    count: None,
-                }],
-            });
+        let mut adaptive = adaptive::AdaptiveSteps::new(&device);
+        let globals = create_globals_binding(&device, adaptive.grid_view());
+        let pipeline_layout = create_pipeline_layout(&device, &globals.layout);
 
-        let globals_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
-            label: Some("Globals BG"),
-            layout: &globals_bind_group_layout,
-            entries: &[wgpu::BindGroupEntry {
-                binding: 0,
-                resource: globals_buffer.as_entire_binding(),
-            }],
-        });
-
-        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
-            label: Some("Neon Shader"),
-            source: wgpu::ShaderSource::Wgsl(SHADER.into()),
-        });
-
-        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
-            label: Some("Render Pipeline Layout"),
-            bind_group_layouts: &[&globals_bind_group_layout],
-            push_constant_ranges: &[],
-        });
-
-        let render_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-            label: Some("Fullscreen Triangle Pipeline"),
-            layout: Some(&pipeline_layout),
-            vertex: wgpu::VertexState {
-                module: &shader,
-                entry_point: Some("vs_main"),
-                compilation_options: wgpu::PipelineCompilationOptions::default(),
-                buffers: &[],
-            },
-            primitive: wgpu::PrimitiveState {
-                topology: wgpu::PrimitiveTopology::TriangleList,
-                strip_index_format: None,
-                front_face: wgpu::FrontFace::Ccw,
-                cull_mode: Some(wgpu::Face::Back),
-                polygon_mode: wgpu::PolygonMode::Fill,
-                unclipped_depth: false,
-                conservative: false,
-            },
-            depth_stencil: None,
-            multisample: wgpu::MultisampleState {
-                count: 1,
-                mask: !0,
-                alpha_to_coverage_enabled: false,
-            },
-            fragment: Some(wgpu::FragmentState {
-                module: &shader,
-                entry_point: Some("fs_main"),
-                compilation_options: wgpu::PipelineCompilationOptions::default(),
-                targets: &[Some(wgpu::ColorTargetState {
-                    format: config.format,
-                    blend: Some(wgpu::BlendState::REPLACE),
-                    write_mask: wgpu::ColorWrites::ALL,
-                })],
-            }),
-            multiview: None,
-            cache: None,
-        });
+        let format_features = adapter.get_texture_format_features(config.format);
+        let sample_counts: Vec<u32> = [1, 2, 4, 8]
+            .into_iter()
+            .filter(|&n| format_features.flags.sample_count_supported(n))
+            .collect();
+        let sample_count = 1;
+
+        // a broken shader file at startup falls back to the embedded shader
+        let (source, shader, render_pipeline, shader_error) = match load_pipeline(
+            &device,
+            &pipeline_layout,
+            shader_file.as_ref(),
+            config.format,
+            sample_count,
+        )
+        .await
+        {
+            Ok((source, shader, pipeline)) => (source, shader, pipeline, None),
+            Err(err) => {
+                eprintln!("Shader error, using the built-in shader:\n{err}");
+                let (shader, pipeline) = build_pipeline(
+                    &device,
+                    &pipeline_layout,
+                    SHADER,
+                    config.format,
+                    sample_count,
+                )
+                .await
+                .expect("built-in shader failed to compile");
+                (SHADER.to_string(), shader, pipeline, Some(err))
+            }
+        };
+        let msaa_view = create_msaa_view(&device, &config, sample_count);
+        let supersampled_pipeline =
+            create_render_pipeline(&device, &pipeline_layout, &shader, config.format, 1);
+        let ssaa_scale = if ssaa::Supersampler::fits(&device, ssaa_scale, size.width, size.height) {
+            ssaa_scale
+        } else {
+            eprintln!("SSAA {ssaa_scale}x exceeds the GPU's texture size limit, using 1x");
+            1
+        };
+        let ssaa = ssaa::Supersampler::new(
+            &device,
+            config.format,
+            sample_count,
+            ssaa_scale,
+            config.width,
+            config.height,
+        );
+
+        adaptive.set_shader(&device, &pipeline_layout, &shader, &source);
+        let auto_exposure = exposure::AutoExposure::new(
+            &device,
+            &globals.layout,
+            adaptive.grid_view(),
+            &pipeline_layout,
+            &shader,
+        );
+        let accumulator = accumulate::Accumulator::new(
+            &device,
+            &pipeline_layout,
+            &shader,
+            config.format,
+            sample_count,
+            config.width,
+            config.height,
+        );
+        let particles = particles::Particles::new(&device, config.format, sample_count);
+        let reaction = reaction::ReactionDiffusion::new(&device, config.format, sample_count);
+        let gui = gui::Gui::new(window, &device, config.format);
 
         Self {
+            window,
             surface,
             device,
             queue,
             config,
+            shader,
+            pipeline_layout,
             render_pipeline,
-            globals_buffer,
-            globals_bind_group,
-            start_instant: Instant::now(),
+            #[cfg(not(target_arch = "wasm32"))]
+            shader_file,
+            shader_error,
+            sample_counts,
+            sample_count,
+            msaa_view,
+            supersampled_pipeline,
+            ssaa,
+            accumulator,
+            present_modes,
+            globals,
+            adaptive,
+            auto_exposure,
+            particles,
+            reaction,
+            demo: Demo::Sdf,
+            gui,
+            time: 0.0,
+            time_scale: 1.0,
+            paused: false,
+            last_frame: Instant::now(),
+            params,
+            dragging: false,
+            last_cursor: None,
+            light_keys: [false; 4],
+            scale_factor: window.scale_factor(),
+            #[cfg(not(target_arch = "wasm32"))]
+            audio: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            snapshot_path: PathBuf::from(snapshot::DEFAULT_PATH),
+            title: settings.title,
         }
     }
 
@@ -314,17 +1464,456 @@ impl<'window> State<'window> {
             self.config.width = new_size.width;
             self.config.height = new_size.height;
             self.surface.configure(&self.device, &self.config);
+            self.msaa_view = create_msaa_view(&self.device, &self.config, self.sample_count);
+            let (width, height) = (new_size.width, new_size.height);
+            if ssaa::Supersampler::fits(&self.device, self.ssaa.scale(), width, height) {
+                self.ssaa.resize(&self.device, width, height);
+            } else {
+                self.ssaa.set_scale(&self.device, 1, width, height);
+                println!("SSAA: 1x (the window is too large for more)");
+            }
+            self.accumulator.resize(&self.device, width, height);
+        }
+    }
+
+    // the mode switch arrives as a `Resized` event, which reconfigures the
+    // surface and so the resolution uniform
+    fn toggle_fullscreen(&self) {
+        let fullscreen = match self.window.fullscreen() {
+            Some(_) => None,
+            None => Some(Fullscreen::Borderless(None)),
+        };
+        self.window.set_fullscreen(fullscreen);
+    }
+
+    // printed as the code that recreates the view, to paste into the defaults
+    fn print_camera(&self) {
+        let OrbitCamera {
+            yaw,
+            pitch,
+            distance,
+            target,
+        } = self.params.camera;
+        let eye = self.params.camera.eye();
+        println!(
+            "OrbitCamera {{ yaw: {yaw:.4}, pitch: {pitch:.4}, distance: {distance:.4}, target: Vec3::new({:.4}, {:.4}, {:.4}) }}, fov: {:.2} (eye at {:.4}, {:.4}, {:.4})",
+            target.x, target.y, target.z, self.params.fov, eye.x, eye.y, eye.z
+        );
+    }
+
+    fn cycle_present_mode(&mut self) {
+        let i = self
+            .present_modes
+            .iter()
+            .position(|&mode| mode == self.config.present_mode)
+            .unwrap_or(0);
+        self.config.present_mode = self.present_modes[(i + 1) % self.present_modes.len()];
+        self.surface.configure(&self.device, &self.config);
+        println!("Present mode: {:?}", self.config.present_mode);
+    }
+
+    fn cycle_msaa(&mut self) {
+        let i = self
+            .sample_counts
+            .iter()
+            .position(|&n| n == self.sample_count)
+            .unwrap_or(0);
+        self.sample_count = self.sample_counts[(i + 1) % self.sample_counts.len()];
+        self.render_pipeline = create_render_pipeline(
+            &self.device,
+            &self.pipeline_layout,
+            &self.shader,
+            self.config.format,
+            self.sample_count,
+        );
+        self.particles
+            .set_sample_count(&self.device, self.sample_count);
+        self.reaction
+            .set_sample_count(&self.device, self.sample_count);
+        self.ssaa.set_sample_count(&self.device, self.sample_count);
+        self.accumulator
+            .set_sample_count(&self.device, self.sample_count);
+        self.msaa_view = create_msaa_view(&self.device, &self.config, self.sample_count);
+        println!("MSAA: {}x", self.sample_count);
+    }
+
+    // skips scales whose target wouldn't fit in a texture at this window size
+    fn cycle_ssaa(&mut self) {
+        let (width, height) = (self.config.width, self.config.height);
+        let i = ssaa::SCALES
+            .iter()
+            .position(|&n| n == self.ssaa.scale())
+            .unwrap_or(0);
+        let scale = ssaa::SCALES[i + 1..]
+            .iter()
+            .copied()
+            .find(|&n| ssaa::Supersampler::fits(&self.device, n, width, height))
+            .unwrap_or(1);
+        self.ssaa.set_scale(&self.device, scale, width, height);
+        println!("SSAA: {scale}x");
+    }
+
+    // the browser build has no shader file to reload and can't block on the GPU
+    #[cfg(not(target_arch = "wasm32"))]
+    fn reload_shader(&mut self) {
+        let result = pollster::block_on(load_pipeline(
+            &self.device,
+            &self.pipeline_layout,
+            self.shader_file.as_ref(),
+            self.config.format,
+            self.sample_count,
+        ));
+        match result {
+            Ok((source, shader, pipeline)) => {
+                self.supersampled_pipeline = create_render_pipeline(
+                    &self.device,
+                    &self.pipeline_layout,
+                    &shader,
+                    self.config.format,
+                    1,
+                );
+                self.auto_exposure
+                    .set_shader(&self.device, &self.pipeline_layout, &shader);
+                self.accumulator
+                    .set_shader(&self.device, &self.pipeline_layout, &shader);
+                self.adaptive
+                    .set_shader(&self.device, &self.pipeline_layout, &shader, &source);
+                self.shader = shader;
+                self.render_pipeline = pipeline;
+                self.shader_error = None;
+                println!("Shader reloaded");
+            }
+            Err(err) => {
+                eprintln!("Shader error, keeping the last good pipeline:\n{err}");
+                self.shader_error = Some(err);
+            }
+        }
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn save_snapshot(&self) {
+        match snapshot::Snapshot::of(&self.params).save(&self.snapshot_path) {
+            Ok(()) => println!("Saved state to {}", self.snapshot_path.display()),
+            Err(err) => eprintln!("Failed to save state: {err}"),
+        }
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn load_snapshot(&mut self) {
+        match snapshot::Snapshot::load(&self.snapshot_path) {
+            Ok(snapshot) => {
+                snapshot.apply(&mut self.params);
+                println!("Loaded state from {}", self.snapshot_path.display());
+            }
+            Err(err) => eprintln!("Failed to load state: {err}"),
+        }
+    }
+
+    // the window keeps its logical size across a move to a display with another
+    // scale factor, so the surface grows or shrinks with it right away rather
+    // than rendering at the old resolution until (or unless) `Resized` arrives
+    fn scale_factor_changed(
+        &mut self,
+        scale_factor: f64,
+        mut inner_size_writer: winit::event::InnerSizeWriter,
+    ) {
+        let logical = winit::dpi::PhysicalSize::new(self.config.width, self.config.height)
+            .to_logical::<f64>(self.scale_factor);
+        let size = logical.to_physical(scale_factor);
+        self.scale_factor = scale_factor;
+        // refused when the window manager picks the size itself; its `Resized`
+        // then follows with the real one
+        if inner_size_writer.request_inner_size(size).is_ok() {
+            self.resize(size);
+        }
+    }
+
+    // cursor positions are physical pixels, like the surface they're measured
+    // against
+    fn cursor_moved(&mut self, position: winit::dpi::PhysicalPosition<f64>) {
+        if self.dragging {
+            // while orbiting, the drag drives the camera and the light stays put;
+            // in logical pixels, so it turns as fast on any display
+            if let Some(last) = self.last_cursor {
+                let scale = self.scale_factor as f32;
+                self.params.camera.drag(
+                    (position.x - last.x) as f32 / scale,
+                    (position.y - last.y) as f32 / scale,
+                );
+            }
+        } else {
+            let x = position.x as f32 / self.config.width as f32;
+            let y = 1.0 - position.y as f32 / self.config.height as f32;
+            self.params.mouse = [x.clamp(0.0, 1.0), y.clamp(0.0, 1.0)];
+            (self.params.light_azimuth, self.params.light_elevation) =
+                light_towards_cursor(self.params.mouse);
+        }
+        self.last_cursor = Some(position);
+    }
+
+    fn mouse_wheel(&mut self, delta: MouseScrollDelta) {
+        let notches = match delta {
+            MouseScrollDelta::LineDelta(_, y) => y,
+            MouseScrollDelta::PixelDelta(pos) => {
+                (pos.y / self.scale_factor) as f32 / PIXELS_PER_LINE
+            }
+        };
+        self.params.camera.zoom(notches);
+    }
+
+    fn gui_consumes(&mut self, event: &WindowEvent) -> bool {
+        let consumed = self.gui.on_window_event(self.window, event);
+        // always let button and key releases through so a drag or a held
+        // light key can't get stuck on
+        match event {
+            WindowEvent::MouseInput {
+                state: ElementState::Released,
+                ..
+            }
+            | WindowEvent::KeyboardInput {
+                event:
+                    KeyEvent {
+                        state: ElementState::Released,
+                        ..
+                    },
+                ..
+            } => false,
+            _ => consumed,
+        }
+    }
+
+    fn mouse_input(&mut self, button: MouseButton, state: ElementState) {
+        if button == MouseButton::Left {
+            self.dragging = state == ElementState::Pressed;
+        }
+    }
+
+    fn set_light_key(&mut self, key: &Key, held: bool) -> bool {
+        let Key::Character(c) = key else {
+            return false;
+        };
+        match LIGHT_KEYS.iter().position(|k| c.eq_ignore_ascii_case(k)) {
+            Some(i) => {
+                self.light_keys[i] = held;
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn key_released(&mut self, key: &Key) {
+        self.set_light_key(key, false);
+    }
+
+    // turns the light by however long its keys have been held since the last
+    // frame, so it moves smoothly rather than at the keyboard's repeat rate
+    fn turn_light(&mut self, dt: f32) {
+        let axis = |negative: usize, positive: usize| {
+            (self.light_keys[positive] as i32 - self.light_keys[negative] as i32) as f32
+        };
+        let (azimuth, elevation) = (axis(0, 1), axis(3, 2));
+        if azimuth == 0.0 && elevation == 0.0 {
+            return;
+        }
+        let step = LIGHT_TURN_SPEED * dt;
+        self.params.light_azimuth =
+            (self.params.light_azimuth + azimuth * step + 180.0).rem_euclid(360.0) - 180.0;
+        self.params.light_elevation = (self.params.light_elevation + elevation * step)
+            .clamp(*LIGHT_ELEVATION_RANGE.start(), *LIGHT_ELEVATION_RANGE.end());
+    }
+
+    fn key_pressed(&mut self, key: &Key) {
+        if self.set_light_key(key, true) {
+            return;
+        }
+        match key {
+            Key::Named(NamedKey::Space) => self.paused = !self.paused,
+            Key::Named(NamedKey::F1) => self.gui.visible = !self.gui.visible,
+            Key::Named(NamedKey::F11) => self.toggle_fullscreen(),
+            Key::Named(NamedKey::Home) => {
+                self.params.camera = OrbitCamera::default();
+                self.params.fov = DEFAULT_FOV;
+            }
+            Key::Character(c) if c.eq_ignore_ascii_case("c") => self.print_camera(),
+            Key::Named(NamedKey::ArrowLeft) if self.paused => self.time -= TIME_STEP,
+            Key::Named(NamedKey::ArrowRight) if self.paused => self.time += TIME_STEP,
+            Key::Character(c) if c.eq_ignore_ascii_case("m") => self.cycle_msaa(),
+            Key::Character(c) if c.eq_ignore_ascii_case("a") => {
+                self.accumulator.toggle();
+                println!(
+                    "Accumulation: {}",
+                    if self.accumulator.enabled {
+                        "on"
+                    } else {
+                        "off"
+                    }
+                );
+            }
+            Key::Character(c) if c.eq_ignore_ascii_case("s") => self.cycle_ssaa(),
+            Key::Character(c) if c.eq_ignore_ascii_case("q") => {
+                let i = AA_SAMPLES
+                    .iter()
+                    .position(|&n| n == self.params.aa_samples)
+                    .map_or(0, |i| (i + 1) % AA_SAMPLES.len());
+                self.params.aa_samples = AA_SAMPLES[i];
+                println!("Shader samples per pixel: {}", self.params.aa_samples);
+            }
+            Key::Character(c) if c.eq_ignore_ascii_case("g") => {
+                self.adaptive.enabled = !self.adaptive.enabled;
+                println!(
+                    "Adaptive steps: {}",
+                    match (self.adaptive.enabled, self.adaptive.active()) {
+                        (false, _) => "off",
+                        (true, true) => "on",
+                        (true, false) => "on, but the shader has no fs_steps prepass",
+                    }
+                );
+            }
+            Key::Character(c) if c.eq_ignore_ascii_case("v") => self.cycle_present_mode(),
+            Key::Character(c) if c.eq_ignore_ascii_case("p") => {
+                self.demo = self.demo.next();
+                if self.demo == Demo::ReactionDiffusion {
+                    self.reaction.reseed();
+                }
+            }
+            #[cfg(not(target_arch = "wasm32"))]
+            Key::Character(c) if c.eq_ignore_ascii_case("r") => self.reload_shader(),
+            #[cfg(not(target_arch = "wasm32"))]
+            Key::Named(NamedKey::F5) => self.save_snapshot(),
+            #[cfg(not(target_arch = "wasm32"))]
+            Key::Named(NamedKey::F9) => self.load_snapshot(),
+            Key::Character(c) if c == "[" || c == "]" => {
+                let factor = if c == "[" { 0.8 } else { 1.25 };
+                self.params.shadow_k = (self.params.shadow_k * factor).clamp(1.0, 64.0);
+                println!("Shadow softness k: {:.2}", self.params.shadow_k);
+            }
+            Key::Character(c) if c == ";" || c == "'" => {
+                let step = if c == ";" { -0.25 } else { 0.25 };
+                self.params.ao_strength = (self.params.ao_strength + step).clamp(0.0, 4.0);
+                println!("AO strength: {:.2}", self.params.ao_strength);
+            }
+            Key::Character(c) if c.eq_ignore_ascii_case("y") || c.eq_ignore_ascii_case("u") => {
+                let step = if c.eq_ignore_ascii_case("y") {
+                    -0.25
+                } else {
+                    0.25
+                };
+                self.params.subsurface_strength = (self.params.subsurface_strength + step).clamp(
+                    *SUBSURFACE_STRENGTH_RANGE.start(),
+                    *SUBSURFACE_STRENGTH_RANGE.end(),
+                );
+                println!(
+                    "Subsurface strength: {:.2}",
+                    self.params.subsurface_strength
+                );
+            }
+            Key::Character(c) if c.eq_ignore_ascii_case("e") => {
+                self.auto_exposure.toggle();
+                println!(
+                    "Auto exposure: {}",
+                    if self.auto_exposure.enabled {
+                        "on"
+                    } else {
+                        "off"
+                    }
+                );
+            }
+            Key::Character(c) if c == "-" || c == "=" => {
+                // adjusting by hand takes over from wherever auto exposure got to
+                if self.auto_exposure.enabled {
+                    self.auto_exposure.toggle();
+                    println!("Auto exposure: off");
+                }
+                let factor = if c == "-" {
+                    1.0 / EXPOSURE_STEP
+                } else {
+                    EXPOSURE_STEP
+                };
+                self.params.exposure = (self.params.exposure * factor).clamp(1.0 / 16.0, 16.0);
+                println!(
+                    "Exposure: {:.3} ({:+.2} EV)",
+                    self.params.exposure,
+                    self.params.exposure.log2()
+                );
+            }
+            Key::Character(c) if c.eq_ignore_ascii_case("t") => {
+                self.params.tonemap = (self.params.tonemap + 1) % TONEMAPS.len() as u32;
+                println!("Tone mapping: {}", TONEMAPS[self.params.tonemap as usize]);
+            }
+            Key::Character(c) if c.eq_ignore_ascii_case("n") => {
+                self.params.debug_mode = (self.params.debug_mode + 1) % DEBUG_VIEWS.len() as u32;
+                println!("View: {}", DEBUG_VIEWS[self.params.debug_mode as usize]);
+            }
+            Key::Character(c) if c.eq_ignore_ascii_case("z") || c.eq_ignore_ascii_case("x") => {
+                let step = if c.eq_ignore_ascii_case("z") {
+                    -FOV_STEP
+                } else {
+                    FOV_STEP
+                };
+                self.params.fov =
+                    (self.params.fov + step).clamp(*FOV_RANGE.start(), *FOV_RANGE.end());
+                println!("Field of view: {:.1}°", self.params.fov);
+            }
+            Key::Character(c) if c == "," || c == "." => {
+                let step = if c == "," { -0.05 } else { 0.05 };
+                self.params.gamma = (self.params.gamma + step).clamp(0.5, 3.0);
+                println!("Gamma: {:.2}", self.params.gamma);
+            }
+            Key::Character(c) => {
+                if let Some(n) = c.chars().next().and_then(|c| c.to_digit(10)) {
+                    if (1..=SCENE_NAMES.len() as u32).contains(&n) {
+                        self.params.scene = n - 1;
+                    }
+                }
+            }
+            _ => {}
         }
     }
 
+    fn advance_time(&mut self) {
+        let now = Instant::now();
+        let dt = (now - self.last_frame).as_secs_f32();
+        if !self.paused {
+            self.time += dt * self.time_scale;
+        }
+        // the light keys work in real time, paused or not
+        self.turn_light(dt);
+        self.last_frame = now;
+    }
+
     fn render(&mut self) -> Result<(), wgpu::SurfaceError> {
-        let elapsed = self.start_instant.elapsed().as_secs_f32();
-        let globals = Globals {
-            time: elapsed,
-            _pad: [0.0; 3],
+        let before = self.time;
+        self.advance_time();
+        let resolution = [self.config.width as f32, self.config.height as f32];
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(audio) = &self.audio {
+            self.params.audio = audio.levels();
+        }
+        // supersampled, the scene is drawn at the target's resolution;
+        // accumulating takes the place of supersampling
+        let accumulating = self.demo == Demo::Sdf && self.accumulator.enabled;
+        let supersampled = match self.demo {
+            Demo::Sdf if !accumulating => self.ssaa.view(),
+            _ => None,
         };
+        let scale = if supersampled.is_some() {
+            self.ssaa.scale() as f32
+        } else {
+            1.0
+        };
+        if self.demo == Demo::Sdf {
+            self.auto_exposure
+                .update(&self.device, &mut self.params.exposure);
+        }
+        let mut globals = self
+            .params
+            .globals(self.time, [resolution[0] * scale, resolution[1] * scale]);
+        globals.adaptive_steps = self.adaptive.active() as u32;
+        if accumulating {
+            self.accumulator.advance(&self.queue, &mut globals);
+        }
         self.queue
-            .write_buffer(&self.globals_buffer, 0, bytemuck::bytes_of(&globals));
+            .write_buffer(&self.globals.buffer, 0, bytemuck::bytes_of(&globals));
 
         let frame = self.surface.get_current_texture()?;
         let view = frame
@@ -335,13 +1924,66 @@ impl<'window> State<'window> {
             .device
             .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
 
+        // the simulations only advance while they're on screen
+        let dt = self.time - before;
+        let mut metered = false;
+        match self.demo {
+            Demo::Sdf => {
+                self.adaptive
+                    .prepass(&mut encoder, &self.globals.bind_group);
+                metered = self
+                    .auto_exposure
+                    .meter(&self.queue, &mut encoder, &globals)
+            }
+            Demo::Particles => self.particles.update(
+                &self.queue,
+                &mut encoder,
+                self.time,
+                dt,
+                resolution[0] / resolution[1],
+            ),
+            Demo::ReactionDiffusion => self.reaction.update(&mut encoder, dt),
+        }
+
+        if accumulating {
+            self.accumulator
+                .render(&mut encoder, &self.globals.bind_group);
+        }
+
+        if let Some(target) = supersampled {
+            let mut rpass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Supersampled Pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: target,
+                    depth_slice: None,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+            rpass.set_pipeline(&self.supersampled_pipeline);
+            rpass.set_bind_group(0, &self.globals.bind_group, &[]);
+            rpass.draw(0..3, 0..1);
+        }
+
+        // with MSAA we draw into the multisampled target and resolve into the frame
+        let (target, resolve_target) = match &self.msaa_view {
+            Some(msaa) => (msaa, Some(&view)),
+            None => (&view, None),
+        };
+
         {
             let mut rpass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
                 label: None,
                 color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                    view: &view,
+                    view: target,
                     depth_slice: None,
-                    resolve_target: None,
+                    resolve_target,
                     ops: wgpu::Operations {
                         load: wgpu::LoadOp::Clear(wgpu::Color {
                             r: 0.0,
@@ -357,61 +1999,340 @@ impl<'window> State<'window> {
                 occlusion_query_set: None,
             });
 
-            rpass.set_pipeline(&self.render_pipeline);
-            rpass.set_bind_group(0, &self.globals_bind_group, &[]);
-            rpass.draw(0..3, 0..1);
+            match self.demo {
+                Demo::Sdf if accumulating => self.accumulator.draw(&mut rpass),
+                Demo::Sdf if supersampled.is_some() => self.ssaa.draw(&mut rpass),
+                Demo::Sdf => {
+                    rpass.set_pipeline(&self.render_pipeline);
+                    rpass.set_bind_group(0, &self.globals.bind_group, &[]);
+                    rpass.draw(0..3, 0..1);
+                }
+                Demo::Particles => self.particles.draw(&mut rpass),
+                Demo::ReactionDiffusion => self.reaction.draw(&mut rpass),
+            }
         }
 
-        self.queue.submit(Some(encoder.finish()));
+        let mut commands = Vec::new();
+        if self.gui.visible || self.shader_error.is_some() {
+            commands = self.gui.draw(
+                self.window,
+                &self.device,
+                &self.queue,
+                &mut encoder,
+                &view,
+                [self.config.width, self.config.height],
+                &mut self.params,
+                &mut self.time_scale,
+                self.shader_error.as_deref(),
+            );
+        }
+        commands.push(encoder.finish());
+        self.queue.submit(commands);
+        if metered {
+            self.auto_exposure.map();
+        }
         frame.present();
         Ok(())
     }
 }
 
-fn main() {
-    let event_loop = EventLoop::new().unwrap();
-    let window = event_loop
-        .create_window(
-            winit::window::WindowAttributes::default()
-                .with_title("wgpu playground - neon fractal")
-                .with_inner_size(LogicalSize::new(800.0, 600.0)),
-        )
-        .unwrap();
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Parser)]
+#[command(about = "Real-time SDF raymarching playground")]
+struct Args {
+    /// Render a single frame offscreen to `--out` instead of opening a window
+    #[arg(long)]
+    headless: bool,
+    /// Shader time in seconds for the headless frame
+    #[arg(long, default_value_t = 0.0)]
+    time: f32,
+    /// Output PNG path for headless rendering
+    #[arg(long, default_value = "frame.png")]
+    out: PathBuf,
+    /// Render headlessly over a time range to numbered PNGs: `start,end,fps,dir`
+    #[arg(long, value_name = "START,END,FPS,DIR")]
+    record: Option<headless::RecordSpec>,
+    /// Window width in logical pixels, or the headless image width in pixels
+    #[arg(long, default_value_t = 800, value_parser = clap::value_parser!(u32).range(1..))]
+    width: u32,
+    /// Window height in logical pixels, or the headless image height in pixels
+    #[arg(long, default_value_t = 600, value_parser = clap::value_parser!(u32).range(1..))]
+    height: u32,
+    /// Window title, ahead of the scene name and frame rate
+    #[arg(long, default_value = WINDOW_TITLE)]
+    title: String,
+    /// Scene to render, numbered like the 1-9 scene keys
+    #[arg(long, default_value_t = 1, value_parser = clap::value_parser!(u32).range(1..=SCENE_NAMES.len() as i64))]
+    scene: u32,
+    /// Load the WGSL from this file instead of the built-in shader; `R` reloads it
+    #[arg(long)]
+    shader: Option<PathBuf>,
+    /// Splice this file's `fn map_scene(p: vec3<f32>, t: f32) -> f32` into the built-in shader in place of the scenes; `R` reloads it
+    #[arg(long, value_name = "FILE.wgsl", conflicts_with = "shader")]
+    sdf: Option<PathBuf>,
+    /// Supersample the SDF scene by this factor per axis, in the window and headless; `S` cycles it
+    #[arg(long, default_value_t = 1, value_parser = clap::value_parser!(u32).range(1..=4))]
+    ssaa: u32,
+    /// Average this many headless frames, each with the camera rays nudged within the pixel, like `A` in the window; takes the place of `--ssaa`
+    #[arg(long, default_value_t = 1, value_parser = clap::value_parser!(u32).range(1..))]
+    accumulate: u32,
+    /// Average this many jittered rays per pixel in the SDF shader, in the window and headless; `Q` steps through 1, 2, 4 and 8
+    #[arg(long, default_value_t = 1, value_parser = clap::value_parser!(u32).range(1..=MAX_AA_SAMPLES as i64))]
+    aa_samples: u32,
+    /// Cap the headless SDF march per screen region from a low-resolution prepass, like `G` in the window
+    #[arg(long)]
+    adaptive_steps: bool,
+    /// Drive the nugget with the default audio input: it swells on the bass
+    #[arg(long)]
+    audio: bool,
+    /// Start from scene state saved with `F5`, overriding `--scene`; `F5` and `F9` then use this file
+    #[arg(long, value_name = "STATE.json")]
+    load: Option<PathBuf>,
+}
 
-    let mut state = pollster::block_on(State::new(&window));
+#[cfg(not(target_arch = "wasm32"))]
+impl Args {
+    fn shader_file(&self) -> Option<ShaderFile> {
+        match (&self.shader, &self.sdf) {
+            (Some(path), _) => Some(ShaderFile::Whole(path.clone())),
+            (None, Some(path)) => Some(ShaderFile::Scene(path.clone())),
+            (None, None) => None,
+        }
+    }
+}
 
-    event_loop
-        .run(|event, elwt| match event {
-            Event::WindowEvent { event, window_id } if window_id == window.id() => match event {
+#[cfg(not(target_arch = "wasm32"))]
+fn headless_renderer(args: &Args) -> headless::HeadlessRenderer {
+    let renderer = load_shader_source(args.shader_file().as_ref()).and_then(|source| {
+        pollster::block_on(headless::HeadlessRenderer::new(
+            args.width,
+            args.height,
+            &source,
+            args.ssaa,
+            args.accumulate,
+            args.adaptive_steps,
+        ))
+    });
+    renderer.unwrap_or_else(|err| {
+        eprintln!("Shader error:\n{err}");
+        std::process::exit(1);
+    })
+}
+
+fn window_attributes(
+    settings: &WindowSettings,
+    params: &SceneParams,
+) -> winit::window::WindowAttributes {
+    winit::window::WindowAttributes::default()
+        .with_title(format!(
+            "{} - {}",
+            settings.title, SCENE_NAMES[params.scene as usize]
+        ))
+        .with_inner_size(LogicalSize::new(settings.width, settings.height))
+}
+
+fn handle_event(
+    state: &mut State,
+    frame_counter: &mut FrameCounter,
+    event: Event<()>,
+    elwt: &winit::event_loop::ActiveEventLoop,
+) {
+    let window = state.window;
+    match event {
+        Event::WindowEvent { event, window_id } if window_id == window.id() => {
+            if state.gui_consumes(&event) {
+                return;
+            }
+            match event {
                 WindowEvent::CloseRequested => elwt.exit(),
                 WindowEvent::Resized(size) => state.resize(size),
-                WindowEvent::ScaleFactorChanged { .. } => {
-                    // We'll get a Resized event as well; handle resize there.
+                WindowEvent::CursorMoved { position, .. } => state.cursor_moved(position),
+                WindowEvent::MouseInput {
+                    state: button_state,
+                    button,
+                    ..
+                } => state.mouse_input(button, button_state),
+                WindowEvent::MouseWheel { delta, .. } => state.mouse_wheel(delta),
+                WindowEvent::KeyboardInput {
+                    event:
+                        KeyEvent {
+                            logical_key,
+                            state: ElementState::Pressed,
+                            ..
+                        },
+                    ..
+                } => state.key_pressed(&logical_key),
+                WindowEvent::KeyboardInput {
+                    event:
+                        KeyEvent {
+                            logical_key,
+                            state: ElementState::Released,
+                            ..
+                        },
+                    ..
+                } => state.key_released(&logical_key),
+                WindowEvent::CursorLeft { .. } => {
+                    // Keep the last position so the light doesn't jump when leaving.
                 }
+                WindowEvent::ScaleFactorChanged {
+                    scale_factor,
+                    inner_size_writer,
+                } => state.scale_factor_changed(scale_factor, inner_size_writer),
                 _ => {}
-            },
-            Event::AboutToWait => {
-                match state.render() {
-                    Ok(()) => {}
-                    Err(wgpu::SurfaceError::Lost | wgpu::SurfaceError::Outdated) => {
-                        let size = window.inner_size();
-                        state.resize(size);
-                    }
-                    Err(wgpu::SurfaceError::OutOfMemory) => {
-                        eprintln!("Out of memory, exiting");
-                        elwt.exit();
-                    }
-                    Err(wgpu::SurfaceError::Timeout) => {
-                        eprintln!("Surface timeout");
-                    }
-                    Err(wgpu::SurfaceError::Other) => {
-                        eprintln!("Surface error: Other");
-                    }
+            }
+        }
+        Event::AboutToWait => {
+            match state.render() {
+                Ok(()) => {}
+                Err(wgpu::SurfaceError::Lost | wgpu::SurfaceError::Outdated) => {
+                    let size = window.inner_size();
+                    state.resize(size);
+                }
+                Err(wgpu::SurfaceError::OutOfMemory) => {
+                    eprintln!("Out of memory, exiting");
+                    elwt.exit();
+                }
+                Err(wgpu::SurfaceError::Timeout) => {
+                    eprintln!("Surface timeout");
+                }
+                Err(wgpu::SurfaceError::Other) => {
+                    eprintln!("Surface error: Other");
                 }
-                window.request_redraw();
             }
-            _ => {}
-        })
+            if let Some((fps, ms)) = frame_counter.tick() {
+                let scene = state
+                    .demo
+                    .name()
+                    .unwrap_or(SCENE_NAMES[state.params.scene as usize]);
+                let mut view = match state.params.debug_mode {
+                    0 => String::new(),
+                    mode => format!(" [{}]", DEBUG_VIEWS[mode as usize]),
+                };
+                if state.demo == Demo::Sdf && state.accumulator.enabled {
+                    view += &format!(" ({} frames)", state.accumulator.frames());
+                }
+                let exposure = state.params.exposure;
+                window.set_title(&format!(
+                    "{} - {scene}{view} | {fps:.1} fps ({ms:.2} ms) | exposure {exposure:.2} ({:+.2} EV), {}",
+                    state.title,
+                    exposure.log2(),
+                    TONEMAPS[state.params.tonemap as usize]
+                ));
+            }
+            window.request_redraw();
+        }
+        _ => {}
+    }
+}
+
+// winit 0.30 deprecates the closure-based `run`/`create_window` in favour of
+// `ApplicationHandler`; the closure form is still the simplest fit here.
+#[allow(deprecated)]
+#[cfg(not(target_arch = "wasm32"))]
+fn main() {
+    let args = Args::parse();
+    let mut params = SceneParams {
+        scene: args.scene - 1,
+        aa_samples: args.aa_samples,
+        ..SceneParams::default()
+    };
+    if let Some(path) = &args.load {
+        match snapshot::Snapshot::load(path) {
+            Ok(snapshot) => snapshot.apply(&mut params),
+            Err(err) => {
+                eprintln!("Failed to load state: {err}");
+                std::process::exit(1);
+            }
+        }
+    }
+
+    if let Some(spec) = &args.record {
+        let mut renderer = headless_renderer(&args);
+        if let Err(err) = headless::record(&mut renderer, &params, spec) {
+            eprintln!("Failed to record frames: {err}");
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    if args.headless {
+        let mut renderer = headless_renderer(&args);
+        if let Err(err) = renderer.save(&params, args.time, &args.out) {
+            eprintln!("Failed to write {}: {err}", args.out.display());
+            std::process::exit(1);
+        }
+        println!("Wrote {}", args.out.display());
+        return;
+    }
+
+    let settings = WindowSettings {
+        title: args.title.clone(),
+        width: args.width,
+        height: args.height,
+    };
+    let event_loop = EventLoop::new().unwrap();
+    let window = event_loop
+        .create_window(window_attributes(&settings, &params))
+        .unwrap();
+
+    let mut state = pollster::block_on(State::new(
+        &window,
+        settings,
+        params,
+        args.shader_file(),
+        args.ssaa,
+    ));
+    if let Some(path) = args.load {
+        state.snapshot_path = path;
+    }
+    if args.audio {
+        match audio::Audio::start() {
+            Ok(audio) => state.audio = Some(audio),
+            Err(err) => eprintln!("Audio input unavailable, continuing without it: {err}"),
+        }
+    }
+    let mut frame_counter = FrameCounter::new();
+
+    event_loop
+        .run(|event, elwt| handle_event(&mut state, &mut frame_counter, event, elwt))
         .unwrap();
 }
 
+// in the browser the canvas goes into `#playground` (or the body), and since
+// nothing may block there the async setup runs as a task that then hands control
+// to the page's event loop
+#[allow(deprecated)]
+#[cfg(target_arch = "wasm32")]
+fn main() {
+    use winit::platform::web::{EventLoopExtWebSys, WindowExtWebSys};
+
+    console_error_panic_hook::set_once();
+
+    let settings = WindowSettings::default();
+    let params = SceneParams::default();
+    let event_loop = EventLoop::new().unwrap();
+    let window = event_loop
+        .create_window(window_attributes(&settings, &params))
+        .unwrap();
+
+    let document = web_sys::window()
+        .and_then(|win| win.document())
+        .expect("no document");
+    let parent = document
+        .get_element_by_id("playground")
+        .or_else(|| document.body().map(Into::into))
+        .expect("no element to attach the canvas to");
+    let canvas = window.canvas().expect("window has no canvas");
+    parent
+        .append_child(&canvas)
+        .expect("failed to attach the canvas");
+
+    // State borrows the window for the rest of the page's life
+    let window: &'static winit::window::Window = Box::leak(Box::new(window));
+    wasm_bindgen_futures::spawn_local(async move {
+        let mut state = State::new(window, settings, params, None, 1).await;
+        let mut frame_counter = FrameCounter::new();
+        event_loop
+            .spawn(move |event, elwt| handle_event(&mut state, &mut frame_counter, event, elwt));
+    });
+}
@@ -0,0 +1,345 @@
+use wgpu::util::DeviceExt;
+
+// alternative to the SDF demo: a compute pass integrates particles around two
+// circling attractors, then an instanced pass draws each one as a soft dot
+pub const PARTICLE_COUNT: u32 = 65_536;
+const WORKGROUP_SIZE: u32 = 64;
+
+// large steps (e.g. after the window was hidden) would fling particles away
+const MAX_DT: f32 = 1.0 / 30.0;
+
+#[repr(C)]
+#[derive(Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+struct Particle {
+    pos: [f32; 2],
+    vel: [f32; 2],
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+struct SimParams {
+    time: f32,
+    dt: f32,
+    aspect: f32,
+    count: u32,
+}
+
+const PARTICLE_SHADER: &str = r#"
+struct Particle {
+    pos: vec2<f32>,
+    vel: vec2<f32>,
+};
+
+struct SimParams {
+    time: f32,
+    dt: f32,
+    // width / height, to keep the dots round
+    aspect: f32,
+    count: u32,
+};
+
+@group(0) @binding(0)
+var<uniform> sim: SimParams;
+@group(0) @binding(1)
+var<storage, read_write> particles: array<Particle>;
+
+// softened inverse-square pull toward `center`
+fn pull(pos: vec2<f32>, center: vec2<f32>) -> vec2<f32> {
+    let d = center - pos;
+    let r2 = dot(d, d) + 0.02;
+    return d * (0.25 / (r2 * sqrt(r2)));
+}
+
+@compute @workgroup_size(64)
+fn cs_main(@builtin(global_invocation_id) id: vec3<u32>) {
+    let i = id.x;
+    if i >= sim.count {
+        return;
+    }
+    var p = particles[i];
+    let a = vec2<f32>(cos(sim.time * 0.5), sin(sim.time * 0.7)) * 0.5;
+    let acc = pull(p.pos, a) + pull(p.pos, -a);
+    // a little drag keeps the swarm from heating up forever
+    p.vel = (p.vel + acc * sim.dt) * pow(0.7, sim.dt);
+    p.pos = p.pos + p.vel * sim.dt;
+    particles[i] = p;
+}
+
+struct VSOut {
+    @builtin(position) pos: vec4<f32>,
+    @location(0) color: vec3<f32>,
+    // -1..1 across the dot
+    @location(1) local: vec2<f32>,
+};
+
+@vertex
+fn vs_particle(
+    @builtin(vertex_index) vi: u32,
+    @location(0) pos: vec2<f32>,
+    @location(1) vel: vec2<f32>,
+) -> VSOut {
+    var corners = array<vec2<f32>, 6>(
+        vec2<f32>(-1.0, -1.0),
+        vec2<f32>(1.0, -1.0),
+        vec2<f32>(1.0, 1.0),
+        vec2<f32>(-1.0, -1.0),
+        vec2<f32>(1.0, 1.0),
+        vec2<f32>(-1.0, 1.0),
+    );
+    let corner = corners[vi];
+    let center = pos * vec2<f32>(1.0 / sim.aspect, 1.0);
+    var out: VSOut;
+    out.pos = vec4<f32>(center + corner * vec2<f32>(0.005 / sim.aspect, 0.005), 0.0, 1.0);
+    // slow particles are blue, fast ones orange
+    let speed = clamp(length(vel) * 0.5, 0.0, 1.0);
+    out.color = mix(vec3<f32>(0.2, 0.4, 1.0), vec3<f32>(1.0, 0.5, 0.1), speed);
+    out.local = corner;
+    return out;
+}
+
+@fragment
+fn fs_particle(in: VSOut) -> @location(0) vec4<f32> {
+    let falloff = 1.0 - dot(in.local, in.local);
+    if falloff <= 0.0 {
+        discard;
+    }
+    return vec4<f32>(in.color * falloff * 0.15, 1.0);
+}
+"#;
+
+pub struct Particles {
+    params: wgpu::Buffer,
+    particles: wgpu::Buffer,
+    // the compute pass writes the particles as storage, the draw reads them as an
+    // instance vertex buffer; a pass can't hold both, so they bind separately
+    compute_bind_group: wgpu::BindGroup,
+    render_bind_group: wgpu::BindGroup,
+    compute_pipeline: wgpu::ComputePipeline,
+    shader: wgpu::ShaderModule,
+    render_layout: wgpu::PipelineLayout,
+    render_pipeline: wgpu::RenderPipeline,
+    format: wgpu::TextureFormat,
+}
+
+// a golden-angle spiral filling the unit disc, each particle already moving
+// around the centre so the swarm starts out swirling
+fn initial_particles() -> Vec<Particle> {
+    (0..PARTICLE_COUNT)
+        .map(|i| {
+            let r = (i as f32 / PARTICLE_COUNT as f32).sqrt();
+            let angle = i as f32 * 2.399_963;
+            let (s, c) = angle.sin_cos();
+            Particle {
+                pos: [r * c, r * s],
+                vel: [-s * 0.3, c * 0.3],
+            }
+        })
+        .collect()
+}
+
+fn uniform_entry(binding: u32, visibility: wgpu::ShaderStages) -> wgpu::BindGroupLayoutEntry {
+    wgpu::BindGroupLayoutEntry {
+        binding,
+        visibility,
+        ty: wgpu::BindingType::Buffer {
+            ty: wgpu::BufferBindingType::Uniform,
+            has_dynamic_offset: false,
+            min_binding_size: None,
+        },
+        count: None,
+    }
+}
+
+impl Particles {
+    pub fn new(device: &wgpu::Device, format: wgpu::TextureFormat, sample_count: u32) -> Self {
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Particle Shader"),
+            source: wgpu::ShaderSource::Wgsl(PARTICLE_SHADER.into()),
+        });
+
+        let params = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Particle Params"),
+            size: std::mem::size_of::<SimParams>() as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let particles = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Particle Buffer"),
+            contents: bytemuck::cast_slice(&initial_particles()),
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::VERTEX,
+        });
+
+        let compute_bgl = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Particle Compute BGL"),
+            entries: &[
+                uniform_entry(0, wgpu::ShaderStages::COMPUTE),
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Storage { read_only: false },
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+        });
+        let render_bgl = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Particle Render BGL"),
+            entries: &[uniform_entry(0, wgpu::ShaderStages::VERTEX)],
+        });
+
+        let compute_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Particle Compute BG"),
+            layout: &compute_bgl,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: params.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: particles.as_entire_binding(),
+                },
+            ],
+        });
+        let render_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Particle Render BG"),
+            layout: &render_bgl,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: params.as_entire_binding(),
+            }],
+        });
+
+        let compute_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Particle Compute Layout"),
+            bind_group_layouts: &[&compute_bgl],
+            push_constant_ranges: &[],
+        });
+        let compute_pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("Particle Compute Pipeline"),
+            layout: Some(&compute_layout),
+            module: &shader,
+            entry_point: Some("cs_main"),
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+            cache: None,
+        });
+
+        let render_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Particle Render Layout"),
+            bind_group_layouts: &[&render_bgl],
+            push_constant_ranges: &[],
+        });
+        let render_pipeline =
+            create_particle_pipeline(device, &render_layout, &shader, format, sample_count);
+
+        Self {
+            params,
+            particles,
+            compute_bind_group,
+            render_bind_group,
+            compute_pipeline,
+            shader,
+            render_layout,
+            render_pipeline,
+            format,
+        }
+    }
+
+    // the draw has to match the MSAA target it lands in
+    pub fn set_sample_count(&mut self, device: &wgpu::Device, sample_count: u32) {
+        self.render_pipeline = create_particle_pipeline(
+            device,
+            &self.render_layout,
+            &self.shader,
+            self.format,
+            sample_count,
+        );
+    }
+
+    // advances the simulation by `dt` seconds of shader time
+    pub fn update(
+        &self,
+        queue: &wgpu::Queue,
+        encoder: &mut wgpu::CommandEncoder,
+        time: f32,
+        dt: f32,
+        aspect: f32,
+    ) {
+        let params = SimParams {
+            time,
+            dt: dt.clamp(-MAX_DT, MAX_DT),
+            aspect,
+            count: PARTICLE_COUNT,
+        };
+        queue.write_buffer(&self.params, 0, bytemuck::bytes_of(&params));
+
+        let mut cpass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+            label: Some("Particle Update"),
+            timestamp_writes: None,
+        });
+        cpass.set_pipeline(&self.compute_pipeline);
+        cpass.set_bind_group(0, &self.compute_bind_group, &[]);
+        cpass.dispatch_workgroups(PARTICLE_COUNT.div_ceil(WORKGROUP_SIZE), 1, 1);
+    }
+
+    pub fn draw(&self, rpass: &mut wgpu::RenderPass) {
+        rpass.set_pipeline(&self.render_pipeline);
+        rpass.set_bind_group(0, &self.render_bind_group, &[]);
+        rpass.set_vertex_buffer(0, self.particles.slice(..));
+        rpass.draw(0..6, 0..PARTICLE_COUNT);
+    }
+}
+
+fn create_particle_pipeline(
+    device: &wgpu::Device,
+    layout: &wgpu::PipelineLayout,
+    shader: &wgpu::ShaderModule,
+    format: wgpu::TextureFormat,
+    sample_count: u32,
+) -> wgpu::RenderPipeline {
+    // added up, so dense regions glow
+    let additive = wgpu::BlendComponent {
+        src_factor: wgpu::BlendFactor::One,
+        dst_factor: wgpu::BlendFactor::One,
+        operation: wgpu::BlendOperation::Add,
+    };
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some("Particle Render Pipeline"),
+        layout: Some(layout),
+        vertex: wgpu::VertexState {
+            module: shader,
+            entry_point: Some("vs_particle"),
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+            buffers: &[wgpu::VertexBufferLayout {
+                array_stride: std::mem::size_of::<Particle>() as u64,
+                step_mode: wgpu::VertexStepMode::Instance,
+                attributes: &wgpu::vertex_attr_array![0 => Float32x2, 1 => Float32x2],
+            }],
+        },
+        primitive: wgpu::PrimitiveState::default(),
+        depth_stencil: None,
+        multisample: wgpu::MultisampleState {
+            count: sample_count,
+            mask: !0,
+            alpha_to_coverage_enabled: false,
+        },
+        fragment: Some(wgpu::FragmentState {
+            module: shader,
+            entry_point: Some("fs_particle"),
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+            targets: &[Some(wgpu::ColorTargetState {
+                format,
+                blend: Some(wgpu::BlendState {
+                    color: additive,
+                    alpha: additive,
+                }),
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+        }),
+        multiview: None,
+        cache: None,
+    })
+}
@@ -0,0 +1,351 @@
+// Gray-Scott reaction-diffusion on a pair of ping-pong textures: each compute step
+// reads one and writes the other, then a fullscreen pass shows the latest
+const SIZE: u32 = 512;
+const WORKGROUP_SIZE: u32 = 8;
+const FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba16Float;
+
+// simulation steps per second of shader time; the pattern grows slowly, so it
+// takes a lot of them
+const STEPS_PER_SECOND: f32 = 600.0;
+// at most this many per frame, so a long hitch can't stall the GPU
+const MAX_STEPS_PER_FRAME: u32 = 32;
+
+const REACTION_SHADER: &str = r#"
+// r = u (the feed chemical), g = v (the one that eats it)
+@group(0) @binding(0)
+var src: texture_2d<f32>;
+@group(0) @binding(1)
+var dst: texture_storage_2d<rgba16float, write>;
+
+const FEED: f32 = 0.037;
+const KILL: f32 = 0.06;
+const DIFFUSE_U: f32 = 1.0;
+const DIFFUSE_V: f32 = 0.5;
+
+// neighbours wrap around, so the pattern tiles seamlessly
+fn load(p: vec2<i32>) -> vec2<f32> {
+    let size = vec2<i32>(textureDimensions(src));
+    return textureLoad(src, (p + size) % size, 0).rg;
+}
+
+@compute @workgroup_size(8, 8)
+fn cs_step(@builtin(global_invocation_id) id: vec3<u32>) {
+    let size = textureDimensions(src);
+    if id.x >= size.x || id.y >= size.y {
+        return;
+    }
+    let p = vec2<i32>(id.xy);
+    let c = load(p);
+    // 3x3 Laplacian: 0.2 for edges, 0.05 for corners
+    let edges = load(p + vec2<i32>(1, 0)) + load(p - vec2<i32>(1, 0))
+        + load(p + vec2<i32>(0, 1)) + load(p - vec2<i32>(0, 1));
+    let corners = load(p + vec2<i32>(1, 1)) + load(p - vec2<i32>(1, 1))
+        + load(p + vec2<i32>(1, -1)) + load(p - vec2<i32>(1, -1));
+    let lap = edges * 0.2 + corners * 0.05 - c;
+
+    let uvv = c.x * c.y * c.y;
+    let u = c.x + DIFFUSE_U * lap.x - uvv + FEED * (1.0 - c.x);
+    let v = c.y + DIFFUSE_V * lap.y + uvv - (KILL + FEED) * c.y;
+    textureStore(dst, p, vec4<f32>(clamp(u, 0.0, 1.0), clamp(v, 0.0, 1.0), 0.0, 1.0));
+}
+
+fn hash(p: vec2<u32>) -> f32 {
+    var h = p.x * 374761393u + p.y * 668265263u;
+    h = (h ^ (h >> 13u)) * 1274126177u;
+    return f32(h ^ (h >> 16u)) / 4294967295.0;
+}
+
+// all u, with v dropped into a scattering of small squares to start reacting
+@compute @workgroup_size(8, 8)
+fn cs_seed(@builtin(global_invocation_id) id: vec3<u32>) {
+    let size = textureDimensions(dst);
+    if id.x >= size.x || id.y >= size.y {
+        return;
+    }
+    let cell = id.xy / 16u;
+    var v = 0.0;
+    if hash(cell) > 0.9 {
+        v = 0.5;
+    }
+    textureStore(dst, vec2<i32>(id.xy), vec4<f32>(1.0 - v, v, 0.0, 1.0));
+}
+
+@group(0) @binding(0)
+var state: texture_2d<f32>;
+@group(0) @binding(1)
+var state_sampler: sampler;
+
+// fullscreen triangle
+@vertex
+fn vs_blit(@builtin(vertex_index) vi: u32) -> @builtin(position) vec4<f32> {
+    let uv = vec2<f32>(f32((vi << 1u) & 2u), f32(vi & 2u));
+    return vec4<f32>(uv * 2.0 - 1.0, 0.0, 1.0);
+}
+
+@fragment
+fn fs_blit(@builtin(position) pos: vec4<f32>) -> @location(0) vec4<f32> {
+    // each texel covers 2x2 pixels whatever the window size; the sampler
+    // repeats the (seamless) pattern to fill it
+    let size = vec2<f32>(textureDimensions(state));
+    let v = textureSample(state, state_sampler, pos.xy / (size * 2.0)).g;
+    let t = smoothstep(0.1, 0.4, v);
+    let col = mix(vec3<f32>(0.02, 0.03, 0.08), vec3<f32>(0.2, 0.9, 0.8), t)
+        + vec3<f32>(1.0, 0.6, 0.3) * smoothstep(0.3, 0.5, v);
+    return vec4<f32>(col, 1.0);
+}
+"#;
+
+pub struct ReactionDiffusion {
+    // step bind groups: [0] reads texture 0 and writes texture 1, [1] the reverse
+    step_bind_groups: [wgpu::BindGroup; 2],
+    // blit bind groups showing texture 0 / texture 1
+    blit_bind_groups: [wgpu::BindGroup; 2],
+    step_pipeline: wgpu::ComputePipeline,
+    seed_pipeline: wgpu::ComputePipeline,
+    shader: wgpu::ShaderModule,
+    blit_layout: wgpu::PipelineLayout,
+    blit_pipeline: wgpu::RenderPipeline,
+    format: wgpu::TextureFormat,
+    // which texture holds the latest state
+    current: usize,
+    // fractional steps carried over between frames
+    pending: f32,
+    reseed: bool,
+}
+
+fn create_state_texture(device: &wgpu::Device) -> wgpu::TextureView {
+    device
+        .create_texture(&wgpu::TextureDescriptor {
+            label: Some("Reaction State"),
+            size: wgpu::Extent3d {
+                width: SIZE,
+                height: SIZE,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: FORMAT,
+            usage: wgpu::TextureUsages::STORAGE_BINDING | wgpu::TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        })
+        .create_view(&wgpu::TextureViewDescriptor::default())
+}
+
+fn texture_entry(binding: u32, visibility: wgpu::ShaderStages) -> wgpu::BindGroupLayoutEntry {
+    wgpu::BindGroupLayoutEntry {
+        binding,
+        visibility,
+        ty: wgpu::BindingType::Texture {
+            sample_type: wgpu::TextureSampleType::Float { filterable: true },
+            view_dimension: wgpu::TextureViewDimension::D2,
+            multisampled: false,
+        },
+        count: None,
+    }
+}
+
+impl ReactionDiffusion {
+    pub fn new(device: &wgpu::Device, format: wgpu::TextureFormat, sample_count: u32) -> Self {
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Reaction Shader"),
+            source: wgpu::ShaderSource::Wgsl(REACTION_SHADER.into()),
+        });
+        let views = [create_state_texture(device), create_state_texture(device)];
+
+        let step_bgl = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Reaction Step BGL"),
+            entries: &[
+                texture_entry(0, wgpu::ShaderStages::COMPUTE),
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::StorageTexture {
+                        access: wgpu::StorageTextureAccess::WriteOnly,
+                        format: FORMAT,
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                    },
+                    count: None,
+                },
+            ],
+        });
+        let step_bind_groups = [0, 1].map(|i| {
+            device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("Reaction Step BG"),
+                layout: &step_bgl,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: wgpu::BindingResource::TextureView(&views[i]),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: wgpu::BindingResource::TextureView(&views[1 - i]),
+                    },
+                ],
+            })
+        });
+
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Reaction Sampler"),
+            address_mode_u: wgpu::AddressMode::Repeat,
+            address_mode_v: wgpu::AddressMode::Repeat,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+        let blit_bgl = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Reaction Blit BGL"),
+            entries: &[
+                texture_entry(0, wgpu::ShaderStages::FRAGMENT),
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+        });
+        let blit_bind_groups = [0, 1].map(|i| {
+            device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("Reaction Blit BG"),
+                layout: &blit_bgl,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: wgpu::BindingResource::TextureView(&views[i]),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: wgpu::BindingResource::Sampler(&sampler),
+                    },
+                ],
+            })
+        });
+
+        let step_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Reaction Step Layout"),
+            bind_group_layouts: &[&step_bgl],
+            push_constant_ranges: &[],
+        });
+        let compute_pipeline = |entry_point| {
+            device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                label: Some("Reaction Compute Pipeline"),
+                layout: Some(&step_layout),
+                module: &shader,
+                entry_point: Some(entry_point),
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+                cache: None,
+            })
+        };
+        let step_pipeline = compute_pipeline("cs_step");
+        let seed_pipeline = compute_pipeline("cs_seed");
+
+        let blit_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Reaction Blit Layout"),
+            bind_group_layouts: &[&blit_bgl],
+            push_constant_ranges: &[],
+        });
+        let blit_pipeline =
+            create_blit_pipeline(device, &blit_layout, &shader, format, sample_count);
+
+        Self {
+            step_bind_groups,
+            blit_bind_groups,
+            step_pipeline,
+            seed_pipeline,
+            shader,
+            blit_layout,
+            blit_pipeline,
+            format,
+            current: 0,
+            pending: 0.0,
+            reseed: true,
+        }
+    }
+
+    pub fn set_sample_count(&mut self, device: &wgpu::Device, sample_count: u32) {
+        self.blit_pipeline = create_blit_pipeline(
+            device,
+            &self.blit_layout,
+            &self.shader,
+            self.format,
+            sample_count,
+        );
+    }
+
+    // starts over from a fresh scattering of seeds on the next update
+    pub fn reseed(&mut self) {
+        self.reseed = true;
+    }
+
+    // runs as many simulation steps as `dt` seconds of shader time call for
+    pub fn update(&mut self, encoder: &mut wgpu::CommandEncoder, dt: f32) {
+        self.pending += dt.max(0.0) * STEPS_PER_SECOND;
+        let steps = (self.pending as u32).min(MAX_STEPS_PER_FRAME);
+        self.pending = self.pending.fract();
+
+        let workgroups = SIZE.div_ceil(WORKGROUP_SIZE);
+        let mut cpass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+            label: Some("Reaction Update"),
+            timestamp_writes: None,
+        });
+        if self.reseed {
+            // only the write side of the bind group is used
+            cpass.set_pipeline(&self.seed_pipeline);
+            cpass.set_bind_group(0, &self.step_bind_groups[1 - self.current], &[]);
+            cpass.dispatch_workgroups(workgroups, workgroups, 1);
+            self.reseed = false;
+        }
+        cpass.set_pipeline(&self.step_pipeline);
+        for _ in 0..steps {
+            cpass.set_bind_group(0, &self.step_bind_groups[self.current], &[]);
+            cpass.dispatch_workgroups(workgroups, workgroups, 1);
+            self.current = 1 - self.current;
+        }
+    }
+
+    pub fn draw(&self, rpass: &mut wgpu::RenderPass) {
+        rpass.set_pipeline(&self.blit_pipeline);
+        rpass.set_bind_group(0, &self.blit_bind_groups[self.current], &[]);
+        rpass.draw(0..3, 0..1);
+    }
+}
+
+fn create_blit_pipeline(
+    device: &wgpu::Device,
+    layout: &wgpu::PipelineLayout,
+    shader: &wgpu::ShaderModule,
+    format: wgpu::TextureFormat,
+    sample_count: u32,
+) -> wgpu::RenderPipeline {
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some("Reaction Blit Pipeline"),
+        layout: Some(layout),
+        vertex: wgpu::VertexState {
+            module: shader,
+            entry_point: Some("vs_blit"),
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+            buffers: &[],
+        },
+        primitive: wgpu::PrimitiveState::default(),
+        depth_stencil: None,
+        multisample: wgpu::MultisampleState {
+            count: sample_count,
+            mask: !0,
+            alpha_to_coverage_enabled: false,
+        },
+        fragment: Some(wgpu::FragmentState {
+            module: shader,
+            entry_point: Some("fs_blit"),
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+            targets: &[Some(wgpu::ColorTargetState {
+                format,
+                blend: Some(wgpu::BlendState::REPLACE),
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+        }),
+        multiview: None,
+        cache: None,
+    })
+}
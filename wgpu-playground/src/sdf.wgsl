@@ -0,0 +1,77 @@
+// signed distance primitives and combinators, shared by the built-in scenes;
+// every shape is centred on (or based at) the origin unless it takes points
+
+fn sd_sphere(p: vec3<f32>, radius: f32) -> f32 {
+    return length(p) - radius;
+}
+
+fn sd_box(p: vec3<f32>, half_extents: vec3<f32>) -> f32 {
+    let q = abs(p) - half_extents;
+    return length(max(q, vec3<f32>(0.0))) + min(max(q.x, max(q.y, q.z)), 0.0);
+}
+
+// a ring around y: `radii` is (ring radius, tube radius)
+fn sd_torus(p: vec3<f32>, radii: vec2<f32>) -> f32 {
+    let q = vec2<f32>(length(p.xz) - radii.x, p.y);
+    return length(q) - radii.y;
+}
+
+// the segment from `a` to `b`, thickened by `radius`
+fn sd_capsule(p: vec3<f32>, a: vec3<f32>, b: vec3<f32>, radius: f32) -> f32 {
+    let pa = p - a;
+    let ba = b - a;
+    let h = clamp(dot(pa, ba) / dot(ba, ba), 0.0, 1.0);
+    return length(pa - ba * h) - radius;
+}
+
+// a solid cone with its tip at the origin, opening down -y for `height`;
+// `angle` is the sine and cosine of its half-angle
+fn sd_cone(p: vec3<f32>, angle: vec2<f32>, height: f32) -> f32 {
+    let q = height * vec2<f32>(angle.x / angle.y, -1.0);
+    let w = vec2<f32>(length(p.xz), p.y);
+    // nearest points on the slanted side and on the base
+    let a = w - q * clamp(dot(w, q) / dot(q, q), 0.0, 1.0);
+    let b = w - q * vec2<f32>(clamp(w.x / q.x, 0.0, 1.0), 1.0);
+    let k = sign(q.y);
+    let d = min(dot(a, a), dot(b, b));
+    let s = max(k * (w.x * q.y - w.y * q.x), k * (w.y - q.y));
+    return sqrt(d) * sign(s);
+}
+
+// domain repetition: where `p` falls within its cell of a grid `period` apart
+// on x and z, so a shape at the origin is repeated across the whole plane as
+// cheaply as it's drawn once. The shape has to fit inside a cell, or the
+// copies in the neighbouring cells are cut off at its edge
+fn op_repeat_xz(p: vec3<f32>, period: f32) -> vec3<f32> {
+    let cell = round(p.xz / period);
+    return vec3<f32>(p.x - cell.x * period, p.y, p.z - cell.y * period);
+}
+
+// angular repetition around y: `count` copies of whatever sits on +x, spread
+// evenly around the circle; `p` is turned into the sector around +x
+fn op_repeat_polar(p: vec3<f32>, count: f32) -> vec3<f32> {
+    let sector = 6.2831853 / count;
+    var angle = atan2(p.z, p.x);
+    angle = angle - sector * round(angle / sector);
+    let r = length(p.xz);
+    return vec3<f32>(r * cos(angle), p.y, r * sin(angle));
+}
+
+fn op_union(a: f32, b: f32) -> f32 {
+    return min(a, b);
+}
+
+fn op_intersect(a: f32, b: f32) -> f32 {
+    return max(a, b);
+}
+
+// `a` with `b` carved out of it
+fn op_subtract(a: f32, b: f32) -> f32 {
+    return max(a, -b);
+}
+
+// union with the seam rounded over a width of about `k`
+fn smin(a: f32, b: f32, k: f32) -> f32 {
+    let h = max(k - abs(a - b), 0.0) / k;
+    return min(a, b) - h * h * k * 0.25;
+}
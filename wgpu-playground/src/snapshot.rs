@@ -0,0 +1,192 @@
+// the tweakable scene state as a JSON file, so a good-looking configuration can
+// be bookmarked with `F5` and brought back with `F9` or `--load`. Audio levels
+// are live input and aren't saved; fields missing from a file keep their
+// defaults, so older snapshots still load.
+
+use std::path::Path;
+
+use math::Vec3;
+use serde::{Deserialize, Serialize};
+
+use crate::{OrbitCamera, Palette, SceneParams};
+
+// where `F5` saves when no `--load` file was given
+pub const DEFAULT_PATH: &str = "playground-state.json";
+
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub struct Snapshot {
+    scene: u32,
+    shadow_k: f32,
+    ao_strength: f32,
+    subsurface_strength: f32,
+    subsurface_depth: f32,
+    exposure: f32,
+    gamma: f32,
+    max_steps: u32,
+    mouse: [f32; 2],
+    light_azimuth: f32,
+    light_elevation: f32,
+    camera: CameraSnapshot,
+    fov: f32,
+    debug_mode: u32,
+    bulb_power: f32,
+    bulb_iterations: u32,
+    sponge_iterations: u32,
+    repeat_period: f32,
+    ring_count: u32,
+    aa_samples: u32,
+    tonemap: u32,
+    fog_density: f32,
+    palette: PaletteSnapshot,
+}
+
+#[derive(Serialize, Deserialize)]
+struct CameraSnapshot {
+    yaw: f32,
+    pitch: f32,
+    distance: f32,
+    target: [f32; 3],
+}
+
+#[derive(Serialize, Deserialize)]
+struct PaletteSnapshot {
+    base: [f32; 3],
+    rim: [f32; 3],
+    bg_top: [f32; 3],
+    bg_bottom: [f32; 3],
+    fog: [f32; 3],
+    // newer than the rest, so a saved palette may be without it
+    #[serde(default = "default_subsurface")]
+    subsurface: [f32; 3],
+}
+
+fn default_subsurface() -> [f32; 3] {
+    crate::DEFAULT_SUBSURFACE_COLOR
+}
+
+impl Default for Snapshot {
+    fn default() -> Self {
+        Self::of(&SceneParams::default())
+    }
+}
+
+impl Snapshot {
+    pub fn of(params: &SceneParams) -> Self {
+        let camera = &params.camera;
+        let palette = &params.palette;
+        Self {
+            scene: params.scene,
+            shadow_k: params.shadow_k,
+            ao_strength: params.ao_strength,
+            subsurface_strength: params.subsurface_strength,
+            subsurface_depth: params.subsurface_depth,
+            exposure: params.exposure,
+            gamma: params.gamma,
+            max_steps: params.max_steps,
+            mouse: params.mouse,
+            light_azimuth: params.light_azimuth,
+            light_elevation: params.light_elevation,
+            camera: CameraSnapshot {
+                yaw: camera.yaw,
+                pitch: camera.pitch,
+                distance: camera.distance,
+                target: [camera.target.x, camera.target.y, camera.target.z],
+            },
+            fov: params.fov,
+            debug_mode: params.debug_mode,
+            bulb_power: params.bulb_power,
+            bulb_iterations: params.bulb_iterations,
+            sponge_iterations: params.sponge_iterations,
+            repeat_period: params.repeat_period,
+            ring_count: params.ring_count,
+            aa_samples: params.aa_samples,
+            tonemap: params.tonemap,
+            fog_density: params.fog_density,
+            palette: PaletteSnapshot {
+                base: palette.base,
+                rim: palette.rim,
+                bg_top: palette.bg_top,
+                bg_bottom: palette.bg_bottom,
+                fog: palette.fog,
+                subsurface: palette.subsurface,
+            },
+        }
+    }
+
+    // overwrites everything saved, leaving the audio levels alone
+    pub fn apply(&self, params: &mut SceneParams) {
+        let [x, y, z] = self.camera.target;
+        params.scene = self.scene;
+        params.shadow_k = self.shadow_k;
+        params.ao_strength = self.ao_strength;
+        params.subsurface_strength = self.subsurface_strength;
+        params.subsurface_depth = self.subsurface_depth;
+        params.exposure = self.exposure;
+        params.gamma = self.gamma;
+        params.max_steps = self.max_steps;
+        params.mouse = self.mouse;
+        params.light_azimuth = self.light_azimuth;
+        params.light_elevation = self.light_elevation;
+        params.camera = OrbitCamera {
+            yaw: self.camera.yaw,
+            pitch: self.camera.pitch,
+            distance: self.camera.distance,
+            target: Vec3::new(x, y, z),
+        };
+        params.fov = self.fov;
+        params.debug_mode = self.debug_mode;
+        params.bulb_power = self.bulb_power;
+        params.bulb_iterations = self.bulb_iterations;
+        params.sponge_iterations = self.sponge_iterations;
+        params.repeat_period = self.repeat_period;
+        params.ring_count = self.ring_count;
+        params.aa_samples = self.aa_samples;
+        params.tonemap = self.tonemap;
+        params.fog_density = self.fog_density;
+        params.palette = Palette {
+            base: self.palette.base,
+            rim: self.palette.rim,
+            bg_top: self.palette.bg_top,
+            bg_bottom: self.palette.bg_bottom,
+            fog: self.palette.fog,
+            subsurface: self.palette.subsurface,
+        };
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let json = serde_json::to_string_pretty(self).map_err(|err| err.to_string())?;
+        std::fs::write(path, json + "\n").map_err(|err| format!("{}: {err}", path.display()))
+    }
+
+    // scene, view and tone map numbers index the name tables, so ones past the
+    // end are rejected
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let json =
+            std::fs::read_to_string(path).map_err(|err| format!("{}: {err}", path.display()))?;
+        let snapshot: Self =
+            serde_json::from_str(&json).map_err(|err| format!("{}: {err}", path.display()))?;
+        if snapshot.scene as usize >= crate::SCENE_NAMES.len() {
+            return Err(format!(
+                "{}: no scene {}",
+                path.display(),
+                snapshot.scene + 1
+            ));
+        }
+        if snapshot.debug_mode as usize >= crate::DEBUG_VIEWS.len() {
+            return Err(format!(
+                "{}: no view {}",
+                path.display(),
+                snapshot.debug_mode
+            ));
+        }
+        if snapshot.tonemap as usize >= crate::TONEMAPS.len() {
+            return Err(format!(
+                "{}: no tone map {}",
+                path.display(),
+                snapshot.tonemap
+            ));
+        }
+        Ok(snapshot)
+    }
+}
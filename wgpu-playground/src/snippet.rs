@@ -0,0 +1,118 @@
+// `--sdf`: a file with just the scene, spliced into the built-in shader in
+// place of its own `map_scene`, so a new scene needn't carry the raymarcher,
+// lighting and uniforms along with it. The rest of the shader stays, so the
+// snippet can use the SDF primitives, `globals` and the built-in scenes'
+// functions, and define helpers of its own anywhere in the file.
+
+// the built-in dispatch to the scenes, and the scene function a snippet must
+// define in its place
+const BUILT_IN: &str = "fn map_scene(p: vec3<f32>, t: f32) -> f32 {";
+const SIGNATURE: &str = "fn map_scene(p: vec3<f32>, t: f32) -> f32";
+
+// `template` with its `map_scene` swapped for the one in `snippet`
+pub fn splice(template: &str, snippet: &str) -> Result<String, String> {
+    check(snippet)?;
+    let start = template
+        .find(BUILT_IN)
+        .expect("the built-in shader defines map_scene");
+    let end = start
+        + template[start..]
+            .find("\n}\n")
+            .expect("the built-in map_scene ends")
+        + 3;
+    Ok(format!(
+        "{}{snippet}\n{}",
+        &template[..start],
+        &template[end..]
+    ))
+}
+
+// exactly one `map_scene`, taking a vec3<f32> and an f32 and returning an f32;
+// naga reports anything else wrong with the snippet once it's compiled
+fn check(snippet: &str) -> Result<(), String> {
+    // comments might mention it
+    let code: String = snippet
+        .lines()
+        .map(|line| line.split("//").next().unwrap_or_default())
+        .collect::<Vec<_>>()
+        .join("\n");
+    let definitions: Vec<&str> = code
+        .match_indices("fn map_scene")
+        .map(|(i, _)| &code[i + "fn map_scene".len()..])
+        .filter(|rest| rest.trim_start().starts_with('('))
+        .collect();
+    let rest = match definitions[..] {
+        [rest] => rest,
+        [] => return Err(format!("no `{SIGNATURE}` to render")),
+        _ => return Err("more than one `fn map_scene`".to_string()),
+    };
+    let written = rest.split('{').next().unwrap_or_default();
+    let signature: String = written.chars().filter(|c| !c.is_whitespace()).collect();
+    let types: Option<Vec<&str>> = signature
+        .strip_prefix('(')
+        .and_then(|s| s.strip_suffix(")->f32"))
+        .map(|params| {
+            params
+                .split(',')
+                .filter(|param| !param.is_empty())
+                .map(|param| param.split_once(':').map_or("", |(_, ty)| ty))
+                .collect()
+        });
+    match types.as_deref() {
+        Some(["vec3<f32>" | "vec3f", "f32"]) => Ok(()),
+        _ => Err(format!(
+            "`fn map_scene{}` should be `{SIGNATURE}`, the distance to the scene at point `p` and time `t`",
+            written.split_whitespace().collect::<Vec<_>>().join(" ")
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEMPLATE: &str =
+        "const A = 1;\nfn map_scene(p: vec3<f32>, t: f32) -> f32 {\n    return 1.0;\n}\nfn after() {}\n";
+
+    #[test]
+    fn takes_either_vector_spelling() {
+        assert!(check("fn map_scene(p: vec3<f32>, t: f32) -> f32 { return 0.0; }").is_ok());
+        assert!(check("fn map_scene(q: vec3f, time: f32)->f32{return 0.0;}").is_ok());
+        assert!(check("fn map_scene(\n    p : vec3 < f32 >,\n    t : f32,\n) -> f32 {}").is_ok());
+    }
+
+    #[test]
+    fn rejects_the_wrong_signature() {
+        assert!(check("fn map_scene(p: vec3f, t: f32) -> vec3f { return p; }").is_err());
+        assert!(check("fn map_scene(p: vec3f) -> f32 { return 0.0; }").is_err());
+        assert!(check("fn map_scene(p: vec2f, t: f32) -> f32 { return 0.0; }").is_err());
+    }
+
+    #[test]
+    fn needs_exactly_one_definition() {
+        assert!(check("fn scene(p: vec3f, t: f32) -> f32 { return 0.0; }").is_err());
+        // a helper whose name starts the same doesn't count
+        assert!(check("fn map_scene_twice(p: vec3f, t: f32) -> f32 { return 0.0; }").is_err());
+        let twice = "fn map_scene(p: vec3f, t: f32) -> f32 { return 0.0; }\n\
+                     fn map_scene(p: vec3f, t: f32) -> f32 { return 1.0; }";
+        assert!(check(twice).is_err());
+    }
+
+    #[test]
+    fn ignores_definitions_in_comments() {
+        assert!(check("// fn map_scene(p: vec3f, t: f32) -> f32 { return 0.0; }").is_err());
+        let commented = "// was: fn map_scene(p: vec3f) -> f32\n\
+                         fn map_scene(p: vec3f, t: f32) -> f32 { return 0.0; }";
+        assert!(check(commented).is_ok());
+    }
+
+    #[test]
+    fn splices_over_the_built_in_scene() {
+        let snippet = "fn map_scene(p: vec3f, t: f32) -> f32 { return 2.0; }";
+        assert_eq!(
+            splice(TEMPLATE, snippet).unwrap(),
+            format!("const A = 1;\n{snippet}\nfn after() {{}}\n")
+        );
+        assert!(splice(TEMPLATE, "fn helper() {}").is_err());
+    }
+}
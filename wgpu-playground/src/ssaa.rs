@@ -0,0 +1,232 @@
+// supersampling: the SDF is rendered into an offscreen texture `scale` times the
+// output size on each axis, then a fullscreen pass box-filters each
+// `scale`x`scale` block of it down to one output pixel. Unlike MSAA this also
+// smooths the edges and detail computed inside the fragment shader.
+
+// scale factors `S` cycles through; 1 renders straight to the output
+pub const SCALES: &[u32] = &[1, 2, 4];
+
+const DOWNSAMPLE_SHADER: &str = r#"
+// set per pipeline, so the loops below have a constant bound
+override SCALE: u32 = 2u;
+
+@group(0) @binding(0)
+var src: texture_2d<f32>;
+
+// fullscreen triangle
+@vertex
+fn vs_downsample(@builtin(vertex_index) vi: u32) -> @builtin(position) vec4<f32> {
+    let uv = vec2<f32>(f32((vi << 1u) & 2u), f32(vi & 2u));
+    return vec4<f32>(uv * 2.0 - 1.0, 0.0, 1.0);
+}
+
+// loads from an sRGB texture come back linear, so the average is too
+@fragment
+fn fs_downsample(@builtin(position) pos: vec4<f32>) -> @location(0) vec4<f32> {
+    let origin = vec2<u32>(pos.xy) * SCALE;
+    var sum = vec4<f32>(0.0);
+    for (var y = 0u; y < SCALE; y = y + 1u) {
+        for (var x = 0u; x < SCALE; x = x + 1u) {
+            sum = sum + textureLoad(src, origin + vec2<u32>(x, y), 0);
+        }
+    }
+    return sum / f32(SCALE * SCALE);
+}
+"#;
+
+// the high-resolution target and the bind group the downsample reads it through
+struct Target {
+    view: wgpu::TextureView,
+    bind_group: wgpu::BindGroup,
+}
+
+pub struct Supersampler {
+    scale: u32,
+    format: wgpu::TextureFormat,
+    sample_count: u32,
+    shader: wgpu::ShaderModule,
+    bind_group_layout: wgpu::BindGroupLayout,
+    pipeline_layout: wgpu::PipelineLayout,
+    pipeline: wgpu::RenderPipeline,
+    // None at scale 1
+    target: Option<Target>,
+}
+
+impl Supersampler {
+    // `sample_count` is that of the pass the downsample draws into
+    pub fn new(
+        device: &wgpu::Device,
+        format: wgpu::TextureFormat,
+        sample_count: u32,
+        scale: u32,
+        width: u32,
+        height: u32,
+    ) -> Self {
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Downsample Shader"),
+            source: wgpu::ShaderSource::Wgsl(DOWNSAMPLE_SHADER.into()),
+        });
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Downsample BGL"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Texture {
+                    sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                    view_dimension: wgpu::TextureViewDimension::D2,
+                    multisampled: false,
+                },
+                count: None,
+            }],
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Downsample Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = create_downsample_pipeline(
+            device,
+            &pipeline_layout,
+            &shader,
+            format,
+            sample_count,
+            scale,
+        );
+        let mut supersampler = Self {
+            scale,
+            format,
+            sample_count,
+            shader,
+            bind_group_layout,
+            pipeline_layout,
+            pipeline,
+            target: None,
+        };
+        supersampler.resize(device, width, height);
+        supersampler
+    }
+
+    pub fn scale(&self) -> u32 {
+        self.scale
+    }
+
+    // whether the target at `scale` stays within the device's texture size limit
+    pub fn fits(device: &wgpu::Device, scale: u32, width: u32, height: u32) -> bool {
+        width.max(height) * scale <= device.limits().max_texture_dimension_2d
+    }
+
+    pub fn set_scale(&mut self, device: &wgpu::Device, scale: u32, width: u32, height: u32) {
+        self.scale = scale;
+        self.pipeline = create_downsample_pipeline(
+            device,
+            &self.pipeline_layout,
+            &self.shader,
+            self.format,
+            self.sample_count,
+            scale,
+        );
+        self.resize(device, width, height);
+    }
+
+    pub fn set_sample_count(&mut self, device: &wgpu::Device, sample_count: u32) {
+        self.sample_count = sample_count;
+        self.pipeline = create_downsample_pipeline(
+            device,
+            &self.pipeline_layout,
+            &self.shader,
+            self.format,
+            sample_count,
+            self.scale,
+        );
+    }
+
+    // `width` and `height` are the output size
+    pub fn resize(&mut self, device: &wgpu::Device, width: u32, height: u32) {
+        if self.scale <= 1 {
+            self.target = None;
+            return;
+        }
+        let view = device
+            .create_texture(&wgpu::TextureDescriptor {
+                label: Some("Supersampled Target"),
+                size: wgpu::Extent3d {
+                    width: width * self.scale,
+                    height: height * self.scale,
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: self.format,
+                usage: wgpu::TextureUsages::RENDER_ATTACHMENT
+                    | wgpu::TextureUsages::TEXTURE_BINDING,
+                view_formats: &[],
+            })
+            .create_view(&wgpu::TextureViewDescriptor::default());
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Downsample BG"),
+            layout: &self.bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::TextureView(&view),
+            }],
+        });
+        self.target = Some(Target { view, bind_group });
+    }
+
+    // where to render the scene at `scale` times the resolution; None at scale 1
+    pub fn view(&self) -> Option<&wgpu::TextureView> {
+        self.target.as_ref().map(|target| &target.view)
+    }
+
+    pub fn draw(&self, rpass: &mut wgpu::RenderPass) {
+        if let Some(target) = &self.target {
+            rpass.set_pipeline(&self.pipeline);
+            rpass.set_bind_group(0, &target.bind_group, &[]);
+            rpass.draw(0..3, 0..1);
+        }
+    }
+}
+
+fn create_downsample_pipeline(
+    device: &wgpu::Device,
+    layout: &wgpu::PipelineLayout,
+    shader: &wgpu::ShaderModule,
+    format: wgpu::TextureFormat,
+    sample_count: u32,
+    scale: u32,
+) -> wgpu::RenderPipeline {
+    let constants = [("SCALE", scale as f64)];
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some("Downsample Pipeline"),
+        layout: Some(layout),
+        vertex: wgpu::VertexState {
+            module: shader,
+            entry_point: Some("vs_downsample"),
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+            buffers: &[],
+        },
+        primitive: wgpu::PrimitiveState::default(),
+        depth_stencil: None,
+        multisample: wgpu::MultisampleState {
+            count: sample_count,
+            mask: !0,
+            alpha_to_coverage_enabled: false,
+        },
+        fragment: Some(wgpu::FragmentState {
+            module: shader,
+            entry_point: Some("fs_downsample"),
+            compilation_options: wgpu::PipelineCompilationOptions {
+                constants: &constants,
+                ..Default::default()
+            },
+            targets: &[Some(wgpu::ColorTargetState {
+                format,
+                blend: Some(wgpu::BlendState::REPLACE),
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+        }),
+        multiview: None,
+        cache: None,
+    })
+}